@@ -11,7 +11,7 @@ import type {
   SubtaskStatus,
   TaskFull,
 } from "@workopilot/sdk";
-import { notifyApp } from "./socket-notify";
+import { notifyApp, requestApp, subscribeApp } from "./socket-notify";
 
 const program = new Command();
 
@@ -362,7 +362,7 @@ program
           order: parseInt(options.order, 10),
         });
 
-        await notifyApp("subtask", subtask.id, "create");
+        await notifyApp("subtask", subtask.id, "create", task.project_id);
 
         console.log(
           JSON.stringify(
@@ -868,7 +868,7 @@ program
           lastSubtaskId: options.subtask,
         });
 
-        await notifyApp("terminal", terminal.id, existing ? "update" : "create");
+        await notifyApp("terminal", terminal.id, existing ? "update" : "create", task.project_id);
 
         console.log(
           JSON.stringify(
@@ -1200,4 +1200,55 @@ program
     }
   });
 
+program
+  .command("app <method> [params]")
+  .description("Send a request to the running app (ping, window.show, terminal.focus, terminal.action, executions.active)")
+  .action(async (method: string, params?: string) => {
+    try {
+      const response = await requestApp(method, params ? JSON.parse(params) : undefined);
+      console.log(JSON.stringify(response, null, 2));
+      if (!response.ok) {
+        process.exit(1);
+      }
+    } catch (error) {
+      console.error(
+        JSON.stringify({
+          error: "Failed to reach WorkoPilot",
+          message: error instanceof Error ? error.message : String(error),
+        })
+      );
+      process.exit(1);
+    }
+  });
+
+program
+  .command("watch")
+  .description("Stream app events as newline-delimited JSON")
+  .option("-e, --entity <types>", "Comma-separated entity types (task, subtask, execution, terminal, quickfix)")
+  .option("-p, --project <projectId>", "Only events for this project")
+  .option("--event <names>", "Comma-separated event names (db-changed, quickfix-changed)")
+  .action(async (options: { entity?: string; project?: string; event?: string }) => {
+    const list = (value?: string) =>
+      value ? value.split(",").map((item) => item.trim()).filter(Boolean) : undefined;
+
+    try {
+      await subscribeApp(
+        {
+          entity_types: list(options.entity),
+          events: list(options.event),
+          project_id: options.project,
+        },
+        (line) => console.log(line)
+      );
+    } catch (error) {
+      console.error(
+        JSON.stringify({
+          error: "Failed to subscribe to WorkoPilot events",
+          message: error instanceof Error ? error.message : String(error),
+        })
+      );
+      process.exit(1);
+    }
+  });
+
 program.parse();
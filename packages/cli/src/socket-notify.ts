@@ -1,28 +1,68 @@
 import { connect, type Socket } from "node:net";
+import { existsSync, readFileSync } from "node:fs";
+import { homedir, tmpdir } from "node:os";
+import { join } from "node:path";
 
-const SOCKET_PATH = "/tmp/workopilot.sock";
+/**
+ * Mirrors src-tauri/src/ipc_paths.rs: `WORKOPILOT_SOCKET`, then the path the
+ * app wrote to the well-known file, then the per-user default.
+ */
+export function getSocketPath(): string {
+  if (process.env.WORKOPILOT_SOCKET) {
+    return process.env.WORKOPILOT_SOCKET;
+  }
+
+  const dev = process.env.WORKOPILOT_DEV !== undefined;
+  const dataDir = process.env.XDG_DATA_HOME || join(homedir(), ".local", "share");
+  const pointer = join(dataDir, "workopilot", dev ? "socket-path-dev" : "socket-path");
+  if (existsSync(pointer)) {
+    const path = readFileSync(pointer, "utf8").trim();
+    if (path) return path;
+  }
+
+  const runtimeDir = process.env.XDG_RUNTIME_DIR
+    ? join(process.env.XDG_RUNTIME_DIR, "workopilot")
+    : join(tmpdir(), `workopilot-${process.getuid?.() ?? 0}`);
+  return join(runtimeDir, dev ? "workopilot-dev.sock" : "workopilot.sock");
+}
+
+/** Must match `EntityType` in src-tauri/src/db_change.rs. */
+export type NotifyEntityType =
+  | "project"
+  | "task"
+  | "subtask"
+  | "execution"
+  | "terminal"
+  | "category"
+  | "urgency"
+  | "setting";
 
 export interface NotifyPayload {
-  entity_type: "task" | "subtask" | "execution" | "terminal";
+  v: number;
+  entity_type: NotifyEntityType;
   entity_id: string;
   operation: "create" | "update" | "delete";
+  project_id?: string;
 }
 
 export async function notifyApp(
   entityType: NotifyPayload["entity_type"],
   entityId: string,
-  operation: NotifyPayload["operation"]
+  operation: NotifyPayload["operation"],
+  projectId?: string | null
 ): Promise<void> {
   const payload: NotifyPayload = {
+    v: IPC_PROTOCOL_VERSION,
     entity_type: entityType,
     entity_id: entityId,
     operation,
+    project_id: projectId ?? undefined,
   };
 
   return new Promise((resolve) => {
-    const client: Socket = connect(SOCKET_PATH, () => {
+    const client: Socket = connect(getSocketPath(), () => {
       try {
-        client.write(JSON.stringify(payload));
+        client.write(JSON.stringify(payload) + "\n");
         client.end();
       } catch {
         resolve();
@@ -46,3 +86,116 @@ export async function notifyApp(
     }, SOCKET_TIMEOUT_MS);
   });
 }
+
+/** Must match `PROTOCOL_VERSION` in src-tauri/src/ipc_protocol.rs. */
+export const IPC_PROTOCOL_VERSION = 1;
+
+export interface AppResponse<T = unknown> {
+  v: number;
+  id: number | string | null;
+  ok: boolean;
+  result?: T;
+  error?: { code: string; message: string };
+}
+
+/**
+ * Sends one request to the running app and waits for its JSON reply.
+ * Rejects if the app is not running or does not answer in time.
+ */
+export async function requestApp<T = unknown>(
+  method: string,
+  params?: unknown,
+  timeoutMs = 5000
+): Promise<AppResponse<T>> {
+  const request = { v: IPC_PROTOCOL_VERSION, id: 1, method, params };
+
+  return new Promise((resolve, reject) => {
+    let buffer = "";
+    const client: Socket = connect(getSocketPath(), () => {
+      client.write(JSON.stringify(request) + "\n");
+    });
+
+    const timer = setTimeout(() => {
+      client.destroy();
+      reject(new Error(`No reply from WorkoPilot for ${method} after ${timeoutMs}ms`));
+    }, timeoutMs);
+
+    client.on("data", (chunk) => {
+      buffer += chunk.toString();
+      const newline = buffer.indexOf("\n");
+      if (newline === -1) return;
+
+      clearTimeout(timer);
+      client.end();
+      try {
+        resolve(JSON.parse(buffer.slice(0, newline)) as AppResponse<T>);
+      } catch (error) {
+        reject(error);
+      }
+    });
+
+    client.on("error", (error) => {
+      clearTimeout(timer);
+      reject(error);
+    });
+  });
+}
+
+export interface AppEventLine {
+  v: number;
+  event: string;
+  entity_type: string | null;
+  project_id: string | null;
+  payload: unknown;
+  timestamp: string;
+}
+
+export interface SubscriptionFilter {
+  events?: string[];
+  entity_types?: string[];
+  project_id?: string;
+}
+
+/**
+ * Subscribes to app events. `onLine` receives every raw NDJSON line after the
+ * subscription is confirmed. Resolves when the app closes the stream.
+ */
+export async function subscribeApp(
+  filter: SubscriptionFilter,
+  onLine: (line: string) => void
+): Promise<void> {
+  const request = { v: IPC_PROTOCOL_VERSION, id: 1, method: "events.subscribe", params: filter };
+
+  return new Promise((resolve, reject) => {
+    let buffer = "";
+    let subscribed = false;
+    const client: Socket = connect(getSocketPath(), () => {
+      client.write(JSON.stringify(request) + "\n");
+    });
+
+    client.on("data", (chunk) => {
+      buffer += chunk.toString();
+      let newline: number;
+      while ((newline = buffer.indexOf("\n")) !== -1) {
+        const line = buffer.slice(0, newline);
+        buffer = buffer.slice(newline + 1);
+        if (!line.trim()) continue;
+
+        if (!subscribed) {
+          const reply = JSON.parse(line) as AppResponse;
+          if (!reply.ok) {
+            client.destroy();
+            reject(new Error(reply.error?.message ?? "Subscription refused"));
+            return;
+          }
+          subscribed = true;
+          continue;
+        }
+        onLine(line);
+      }
+    });
+
+    client.on("error", reject);
+    client.on("close", () => resolve());
+  });
+}
@@ -1,50 +1,76 @@
 /**
  * @deprecated JSON-RPC handlers are legacy. Use tRPC for new integrations.
- * These handlers are still used by Rust sidecar calls (terminal.rs, commands.rs).
- * Plan: Migrate Rust to use tRPC HTTP calls, then remove this file.
+ * Rust reads and writes data through tRPC (src-tauri/src/trpc_client.rs). Only
+ * `system.ping` and `system.version` are native here; any other method name is
+ * treated as a tRPC procedure path, so stdio can serve as a tRPC transport.
  */
 import { WorkoPilotSDK } from '@workopilot/sdk';
+import { TRPCError } from '@trpc/server';
+import { getHTTPStatusCodeFromError } from '@trpc/server/http';
 import type { JsonRpcRequest, JsonRpcResponse } from './types';
 import { createSuccessResponse, createErrorResponse, JSON_RPC_ERRORS } from './types';
+import { getVersionInfo } from './version';
+import { appRouter, createCallerFactory, type Context } from './trpc';
 
 type HandlerFn = (sdk: WorkoPilotSDK, params: unknown) => Promise<unknown>;
 
 const handlers: Record<string, HandlerFn> = {
-  'projects.get': async (sdk, params) => {
-    const { id } = params as { id: string };
-    return sdk.projects.get(id);
-  },
-
-  'tasks.getFull': async (sdk, params) => {
-    const { id } = params as { id: string };
-    return sdk.tasks.getFull(id);
-  },
-
-  'tasks.updateStatus': async (sdk, params) => {
-    const { id, status, modifiedBy } = params as { 
-      id: string; 
-      status: Parameters<typeof sdk.tasks.updateStatus>[1];
-      modifiedBy?: 'user' | 'ai' | 'cli';
-    };
-    return sdk.tasks.updateStatus(id, status, modifiedBy);
-  },
-
   'system.ping': async () => {
     return { pong: true, timestamp: new Date().toISOString() };
   },
 
   'system.version': async () => {
-    return { version: '0.1.0', runtime: 'bun' };
+    return getVersionInfo();
   },
 };
 
+const createCaller = createCallerFactory(appRouter);
+type Procedure = (input: unknown) => Promise<unknown>;
+
+function findProcedure(context: Context, path: string): Procedure | null {
+  if (!(path in appRouter._def.procedures)) return null;
+
+  const caller = createCaller(context) as unknown as Record<string, unknown>;
+  const procedure = path
+    .split('.')
+    .reduce<unknown>((node, key) => (node as Record<string, unknown> | undefined)?.[key], caller);
+
+  return typeof procedure === 'function' ? (procedure as Procedure) : null;
+}
+
+async function callProcedure(
+  request: JsonRpcRequest,
+  procedure: Procedure
+): Promise<JsonRpcResponse> {
+  try {
+    const result = await procedure(request.params);
+    return createSuccessResponse(request.id, result ?? null);
+  } catch (error) {
+    if (error instanceof TRPCError) {
+      // Same shape as the HTTP error body so Rust maps both transports alike.
+      return createErrorResponse(request.id, JSON_RPC_ERRORS.INTERNAL_ERROR, error.message, {
+        code: error.code,
+        httpStatus: getHTTPStatusCodeFromError(error),
+      });
+    }
+    const message = error instanceof Error ? error.message : 'Unknown error';
+    return createErrorResponse(request.id, JSON_RPC_ERRORS.INTERNAL_ERROR, message);
+  }
+}
+
 export async function handleRequest(
   sdk: WorkoPilotSDK,
-  request: JsonRpcRequest
+  request: JsonRpcRequest,
+  createContext: () => Context
 ): Promise<JsonRpcResponse> {
   const handler = handlers[request.method];
 
   if (!handler) {
+    const procedure = findProcedure(createContext(), request.method);
+    if (procedure) {
+      return callProcedure(request, procedure);
+    }
+
     return createErrorResponse(
       request.id,
       JSON_RPC_ERRORS.METHOD_NOT_FOUND,
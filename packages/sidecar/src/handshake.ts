@@ -0,0 +1,28 @@
+/**
+ * Startup handshake sent to the Rust host as the first stdout line.
+ */
+import { getAvailableMethods } from './handlers';
+import { appRouter } from './trpc';
+import { PROTOCOL_VERSION, RUNTIME, SIDECAR_VERSION } from './version';
+
+export const HANDSHAKE_PREFIX = 'WORKOPILOT_HANDSHAKE=';
+
+export interface Handshake {
+  protocol_version: number;
+  sidecar_version: string;
+  runtime: string;
+  trpc_url: string;
+  methods: string[];
+  procedures: string[];
+}
+
+export function buildHandshake(trpcUrl: string): Handshake {
+  return {
+    protocol_version: PROTOCOL_VERSION,
+    sidecar_version: SIDECAR_VERSION,
+    runtime: RUNTIME,
+    trpc_url: trpcUrl,
+    methods: getAvailableMethods(),
+    procedures: Object.keys(appRouter._def.procedures),
+  };
+}
@@ -3,33 +3,46 @@ import { WorkoPilotSDK } from '@workopilot/sdk';
 import { handleRequest, getAvailableMethods } from './handlers';
 import type { JsonRpcRequest, JsonRpcResponse } from './types';
 import { createErrorResponse, JSON_RPC_ERRORS } from './types';
-import { startTrpcServer, type TrpcServer } from './trpc';
+import { createContext, startTrpcServer, type EntityChangeListener, type TrpcServer } from './trpc';
+import { buildHandshake, HANDSHAKE_PREFIX } from './handshake';
+import { createLogger } from './log';
 
-const LOG_PREFIX = '[SIDECAR]';
-const TRPC_URL_PREFIX = 'TRPC_URL=';
+const log = createLogger('[SIDECAR]');
+/** Notification Rust sends for a request it gave up on (as in LSP). */
+const CANCEL_METHOD = '$/cancelRequest';
 
-function log(...args: unknown[]) {
-  console.error(LOG_PREFIX, ...args);
-}
+type RequestId = string | number;
+type IncomingMessage = { request: JsonRpcRequest } | { cancel: RequestId };
 
 function sendResponse(response: JsonRpcResponse) {
   const json = JSON.stringify(response);
   process.stdout.write(json + '\n');
 }
 
-function emitTrpcUrl(url: string) {
-  process.stdout.write(`${TRPC_URL_PREFIX}${url}\n`);
+/** Server-initiated message (no id); Rust forwards these as Tauri events. */
+function sendNotification(method: string, params: unknown) {
+  process.stdout.write(JSON.stringify({ jsonrpc: '2.0', method, params }) + '\n');
+}
+
+function emitHandshake(url: string) {
+  process.stdout.write(`${HANDSHAKE_PREFIX}${JSON.stringify(buildHandshake(url))}\n`);
 }
 
-function parseRequest(line: string): JsonRpcRequest | null {
+function isRequestId(id: unknown): id is RequestId {
+  return typeof id === 'string' || typeof id === 'number';
+}
+
+function parseMessage(line: string): IncomingMessage | null {
   try {
     const parsed = JSON.parse(line);
-    if (
-      parsed.jsonrpc === '2.0' &&
-      typeof parsed.method === 'string' &&
-      (typeof parsed.id === 'string' || typeof parsed.id === 'number')
-    ) {
-      return parsed as JsonRpcRequest;
+    if (parsed.jsonrpc !== '2.0' || typeof parsed.method !== 'string') {
+      return null;
+    }
+    if (isRequestId(parsed.id)) {
+      return { request: parsed as JsonRpcRequest };
+    }
+    if (parsed.method === CANCEL_METHOD && isRequestId(parsed.params?.id)) {
+      return { cancel: parsed.params.id };
     }
     return null;
   } catch {
@@ -38,33 +51,37 @@ function parseRequest(line: string): JsonRpcRequest | null {
 }
 
 async function main() {
-  log('Starting WorkoPilot sidecar...');
+  log.info('Starting WorkoPilot sidecar...');
   
   let sdk: WorkoPilotSDK;
   let trpcServer: TrpcServer;
+  const onEntityChanged: EntityChangeListener = (change) =>
+    sendNotification('entity.changed', change);
   
   try {
     sdk = await WorkoPilotSDK.create();
-    log('SDK initialized, db path:', sdk.dbPath);
-    log('Available JSON-RPC methods:', getAvailableMethods().length);
+    log.info('SDK initialized, db path:', sdk.dbPath);
+    log.info('Available JSON-RPC methods:', getAvailableMethods().length);
   } catch (error) {
-    log('Failed to initialize SDK:', error);
+    log.error('Failed to initialize SDK:', error);
     process.exit(1);
   }
 
   try {
-    trpcServer = await startTrpcServer({ sdk });
-    log('tRPC server started on:', trpcServer.url);
-    emitTrpcUrl(trpcServer.url);
+    trpcServer = await startTrpcServer({ sdk, onEntityChanged });
+    log.info('tRPC server started on:', trpcServer.url);
+    emitHandshake(trpcServer.url);
   } catch (error) {
-    log('Failed to start tRPC server:', error);
+    log.error('Failed to start tRPC server:', error);
     process.exit(1);
   }
 
   const decoder = new TextDecoder();
   let buffer = '';
+  // Requests being handled; a cancelled one is removed and gets no response.
+  const inFlight = new Set<RequestId>();
 
-  log('Ready to receive JSON-RPC requests');
+  log.info('Ready to receive JSON-RPC requests');
 
   const reader = Bun.stdin.stream().getReader();
 
@@ -74,7 +91,7 @@ async function main() {
         const { done, value } = await reader.read();
         
         if (done) {
-          log('stdin closed, continuing with tRPC server only');
+          log.info('stdin closed, continuing with tRPC server only');
           break;
         }
 
@@ -86,9 +103,9 @@ async function main() {
         for (const line of lines) {
           if (!line.trim()) continue;
 
-          const request = parseRequest(line);
+          const message = parseMessage(line);
           
-          if (!request) {
+          if (!message) {
             sendResponse(
               createErrorResponse(
                 null,
@@ -99,30 +116,44 @@ async function main() {
             continue;
           }
 
-          log('Request:', request.method, request.id);
-          
-          const response = await handleRequest(sdk, request);
-          sendResponse(response);
-          
-          log('Response sent for:', request.id);
+          if ('cancel' in message) {
+            if (inFlight.delete(message.cancel)) {
+              log.info('Cancelled:', message.cancel);
+            }
+            continue;
+          }
+
+          const { request } = message;
+          log.debug('Request:', request.method, request.id);
+          inFlight.add(request.id);
+
+          // Requests are handled concurrently; Rust routes responses by id.
+          handleRequest(sdk, request, () => createContext(sdk, onEntityChanged)).then((response) => {
+            if (!inFlight.delete(request.id)) {
+              log.info('Dropped response for cancelled request:', request.id);
+              return;
+            }
+            sendResponse(response);
+            log.debug('Response sent for:', request.id);
+          });
         }
       }
     } catch (error) {
-      log('stdin read error (non-fatal):', error);
+      log.warn('stdin read error (non-fatal):', error);
     }
   }
 
   readLoop();
 
   process.on('SIGTERM', async () => {
-    log('Received SIGTERM, shutting down');
+    log.info('Received SIGTERM, shutting down');
     trpcServer.stop();
     await sdk.close();
     process.exit(0);
   });
 
   process.on('SIGINT', async () => {
-    log('Received SIGINT, shutting down');
+    log.info('Received SIGINT, shutting down');
     trpcServer.stop();
     await sdk.close();
     process.exit(0);
@@ -0,0 +1,21 @@
+/**
+ * stderr logging. Every line starts with its level (`[error] [SIDECAR] ...`),
+ * which src-tauri/src/sidecar_logs.rs reads instead of guessing from the text.
+ */
+export type LogLevel = 'debug' | 'info' | 'warn' | 'error';
+
+export type Logger = Record<LogLevel, (...args: unknown[]) => void>;
+
+export function createLogger(prefix: string): Logger {
+  const write =
+    (level: LogLevel) =>
+    (...args: unknown[]) =>
+      console.error(`[${level}]`, prefix, ...args);
+
+  return {
+    debug: write('debug'),
+    info: write('info'),
+    warn: write('warn'),
+    error: write('error'),
+  };
+}
@@ -1,9 +1,20 @@
 import type { WorkoPilotSDK } from '@workopilot/sdk';
 
+export interface EntityChange {
+  /** Must match `EntityType` in src-tauri/src/db_change.rs. */
+  entity_type: 'project' | 'task' | 'subtask' | 'execution' | 'category' | 'urgency' | 'setting';
+  entity_id: string;
+  operation: 'create' | 'update' | 'delete';
+  project_id?: string;
+}
+
+export type EntityChangeListener = (change: EntityChange) => void;
+
 export interface Context {
   sdk: WorkoPilotSDK;
+  onEntityChanged?: EntityChangeListener;
 }
 
-export function createContext(sdk: WorkoPilotSDK): Context {
-  return { sdk };
+export function createContext(sdk: WorkoPilotSDK, onEntityChanged?: EntityChangeListener): Context {
+  return { sdk, onEntityChanged };
 }
@@ -1,3 +1,4 @@
 export { appRouter, type AppRouter } from './router';
 export { startTrpcServer, type TrpcServer, type TrpcServerConfig } from './server';
-export { createContext, type Context } from './context';
+export { createContext, type Context, type EntityChange, type EntityChangeListener } from './context';
+export { createCallerFactory } from './trpc';
@@ -1,4 +1,5 @@
 import { router, publicProcedure } from '../trpc';
+import { getVersionInfo } from '../../version';
 
 export const systemRouter = router({
   ping: publicProcedure.query(() => {
@@ -6,6 +7,6 @@ export const systemRouter = router({
   }),
 
   version: publicProcedure.query(() => {
-    return { version: '0.1.0', runtime: 'bun', transport: 'trpc' };
+    return { ...getVersionInfo(), transport: 'trpc' };
   }),
 });
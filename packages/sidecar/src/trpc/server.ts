@@ -1,17 +1,15 @@
 import { fetchRequestHandler } from '@trpc/server/adapters/fetch';
 import type { WorkoPilotSDK } from '@workopilot/sdk';
 import { appRouter } from './router';
-import { createContext } from './context';
+import { createContext, type EntityChangeListener } from './context';
+import { createLogger } from '../log';
 
-const LOG_PREFIX = '[TRPC]';
-
-function log(...args: unknown[]) {
-  console.error(LOG_PREFIX, ...args);
-}
+const log = createLogger('[TRPC]');
 
 export interface TrpcServerConfig {
   sdk: WorkoPilotSDK;
   port?: number;
+  onEntityChanged?: EntityChangeListener;
 }
 
 export interface TrpcServer {
@@ -29,7 +27,7 @@ function corsHeaders() {
 }
 
 export async function startTrpcServer(config: TrpcServerConfig): Promise<TrpcServer> {
-  const { sdk, port = 0 } = config;
+  const { sdk, port = 0, onEntityChanged } = config;
 
   const server = Bun.serve({
     port,
@@ -52,9 +50,9 @@ export async function startTrpcServer(config: TrpcServerConfig): Promise<TrpcSer
           endpoint: '/trpc',
           req: request,
           router: appRouter,
-          createContext: () => createContext(sdk),
+          createContext: () => createContext(sdk, onEntityChanged),
           onError({ error, path }) {
-            log(`Error in ${path}:`, error.message);
+            log.error(`Error in ${path}:`, error.message);
           },
         }).then((response) => {
           const headers = new Headers(response.headers);
@@ -73,7 +71,7 @@ export async function startTrpcServer(config: TrpcServerConfig): Promise<TrpcSer
   const actualPort = server.port;
   const url = `http://localhost:${actualPort}`;
 
-  log(`Server started on ${url}`);
+  log.info(`Server started on ${url}`);
 
   return {
     url,
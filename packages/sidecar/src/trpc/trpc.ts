@@ -1,5 +1,5 @@
 import { initTRPC } from '@trpc/server';
-import type { Context } from './context';
+import type { Context, EntityChange } from './context';
 
 const t = initTRPC.context<Context>().create({
   errorFormatter({ shape, error }) {
@@ -13,6 +13,61 @@ const t = initTRPC.context<Context>().create({
   },
 });
 
+const ENTITY_TYPES: Record<string, EntityChange['entity_type']> = {
+  projects: 'project',
+  tasks: 'task',
+  subtasks: 'subtask',
+  executions: 'execution',
+  settings: 'setting',
+  categories: 'category',
+  urgencies: 'urgency',
+};
+
+function pickString(source: unknown, keys: string[]): string | undefined {
+  if (!source || typeof source !== 'object') return undefined;
+  for (const key of keys) {
+    const value = (source as Record<string, unknown>)[key];
+    if (typeof value === 'string' && value) return value;
+  }
+  return undefined;
+}
+
+function toOperation(procedure: string): EntityChange['operation'] {
+  if (procedure === 'create' || procedure === 'start') return 'create';
+  if (procedure.startsWith('delete') || procedure === 'migrateAndDelete') return 'delete';
+  return 'update';
+}
+
+function describeChange(path: string, input: unknown, result: unknown): EntityChange | null {
+  const [routerName, procedure] = path.split('.');
+  const entityType = ENTITY_TYPES[routerName];
+  if (!entityType || !procedure) return null;
+
+  const entityId =
+    pickString(input, ['id', 'taskId', 'task_id', 'key']) ?? pickString(result, ['id']);
+  if (!entityId) return null;
+
+  return {
+    entity_type: entityType,
+    entity_id: entityId,
+    operation: toOperation(procedure),
+    project_id: pickString(input, ['project_id', 'projectId']) ?? pickString(result, ['project_id']),
+  };
+}
+
+/** Tells the host about every successful mutation so it can refresh without polling. */
+const notifyEntityChanged = t.middleware(async ({ ctx, path, type, getRawInput, next }) => {
+  const result = await next();
+
+  if (type === 'mutation' && result.ok && ctx.onEntityChanged) {
+    const change = describeChange(path, await getRawInput(), result.data);
+    if (change) ctx.onEntityChanged(change);
+  }
+
+  return result;
+});
+
 export const router = t.router;
-export const publicProcedure = t.procedure;
+export const publicProcedure = t.procedure.use(notifyEntityChanged);
 export const middleware = t.middleware;
+export const createCallerFactory = t.createCallerFactory;
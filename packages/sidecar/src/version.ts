@@ -0,0 +1,21 @@
+import packageJson from '../package.json';
+
+/**
+ * Bump PROTOCOL_VERSION whenever the stdio/tRPC contract with
+ * src-tauri/src/sidecar_handshake.rs changes incompatibly.
+ */
+export const PROTOCOL_VERSION = 1;
+
+/** Read from package.json, which is kept in lockstep with the Tauri app version. */
+export const SIDECAR_VERSION: string = packageJson.version;
+
+/** Runtime executing the sidecar; only Bun provides the APIs it uses. */
+export const RUNTIME = `bun ${Bun.version}`;
+
+export function getVersionInfo() {
+  return {
+    version: SIDECAR_VERSION,
+    protocolVersion: PROTOCOL_VERSION,
+    runtime: RUNTIME,
+  };
+}
@@ -1,5 +1,6 @@
 import { spawn, spawnSync } from 'child_process';
 import fs from 'fs';
+import net from 'net';
 import path from 'path';
 import os from 'os';
 
@@ -110,6 +111,110 @@ async function runCli(command, args = []) {
   });
 }
 
+// Mirrors packages/cli/src/socket-notify.ts getSocketPath()
+function getSocketPath() {
+  if (process.env.WORKOPILOT_SOCKET) return process.env.WORKOPILOT_SOCKET;
+
+  const dev = process.env.WORKOPILOT_DEV !== undefined;
+  const dataDir = process.env.XDG_DATA_HOME || path.join(os.homedir(), '.local', 'share');
+  const pointer = path.join(dataDir, 'workopilot', dev ? 'socket-path-dev' : 'socket-path');
+  try {
+    const socketPath = fs.readFileSync(pointer, 'utf8').trim();
+    if (socketPath) return socketPath;
+  } catch {}
+
+  const runtimeDir = process.env.XDG_RUNTIME_DIR
+    ? path.join(process.env.XDG_RUNTIME_DIR, 'workopilot')
+    : path.join(os.tmpdir(), `workopilot-${process.getuid?.() ?? 0}`);
+  return path.join(runtimeDir, dev ? 'workopilot-dev.sock' : 'workopilot.sock');
+}
+
+const HEARTBEAT_INTERVAL_MS = 30_000;
+
+// Sends execution.start/progress/heartbeat/waiting/end straight to the app socket.
+// Best-effort: resolves with the reply, or null if the app is not running.
+function sendExecutionUpdate(method, params) {
+  return new Promise((resolve) => {
+    let buffer = '';
+    const client = net.connect(getSocketPath(), () => {
+      client.write(JSON.stringify({ v: 1, id: 1, method, params }) + '\n');
+    });
+    const timer = setTimeout(() => { client.destroy(); resolve(null); }, 2000);
+
+    client.on('data', (chunk) => {
+      buffer += chunk.toString();
+      const newline = buffer.indexOf('\n');
+      if (newline === -1) return;
+      clearTimeout(timer);
+      client.end();
+      try {
+        const reply = JSON.parse(buffer.slice(0, newline));
+        if (!reply.ok) console.log(`[WorkoPilot] ${method} rejected: ${reply.error?.message}`);
+        resolve(reply);
+      } catch { resolve(null); }
+    });
+    client.on('error', () => { clearTimeout(timer); resolve(null); });
+  });
+}
+
+function getTmuxSession() {
+  if (!process.env.TMUX) return undefined;
+  const result = spawnSync('tmux', ['display-message', '-p', '#S'], { encoding: 'utf8', timeout: 2000 });
+  return result.status === 0 ? result.stdout.trim() || undefined : undefined;
+}
+
+// Starts tracking an execute run once both the skill and the task are known
+async function startExecution(state) {
+  if (state.executing || !state.taskId || state.skillType !== 'execute') return;
+  state.executing = true;
+  state.lastHeartbeat = Date.now();
+
+  try {
+    const task = await runCli('get-task', [state.taskId]);
+    state.subtasks = (task?.subtasks || []).map(s => ({ id: s.id, title: s.title, done: s.status === 'done' }));
+  } catch {
+    state.subtasks = [];
+  }
+
+  await sendExecutionUpdate('execution.start', {
+    task_id: state.taskId,
+    tmux_session: getTmuxSession(),
+    pid: process.pid,
+    total_steps: state.subtasks.length
+  });
+}
+
+const SUBTASK_STATUS_PATTERN = /update-subtask\s+([a-f0-9-]{36})[^\n]*--status[=\s]+(in_progress|done)/;
+
+// Reports a subtask the agent just moved with `update-subtask --status`
+async function reportSubtaskProgress(state, command) {
+  if (!state.executing) return;
+  const match = command?.match(SUBTASK_STATUS_PATTERN);
+  if (!match) return;
+
+  const [, subtaskId, status] = match;
+  const subtask = state.subtasks.find(s => s.id === subtaskId);
+  if (subtask) subtask.done = status === 'done';
+
+  await sendExecutionUpdate('execution.progress', {
+    task_id: state.taskId,
+    current_step: state.subtasks.filter(s => s.done).length,
+    total_steps: state.subtasks.length,
+    description: subtask?.title
+  });
+}
+
+async function endExecution(state, status, errorMessage) {
+  if (!state.executing) return;
+  state.executing = false;
+  state.waitingForInput = false;
+  await sendExecutionUpdate('execution.end', {
+    task_id: state.taskId,
+    status,
+    error_message: errorMessage
+  });
+}
+
 async function updateTaskSubstatus(taskId, substatus) {
   try {
     await runCli('update-task', [taskId, '--substatus', substatus]);
@@ -139,7 +244,12 @@ function getSessionState(sessionID) {
       taskId: null,
       skillType: null,
       hasActivity: false,
-      lastAssistantText: ''
+      lastAssistantText: '',
+      lastHeartbeat: 0,
+      waitingForInput: false,
+      executing: false,
+      subtasks: [],
+      pendingCommands: new Map()
     });
   }
   return sessionState.get(sessionID);
@@ -225,9 +335,10 @@ export const WorkoPilotPlugin = async ({ client, directory }) => {
 
         // Extract taskId from assistant output
         const taskId = extractTaskId(fullText);
-        if (taskId) {
+        if (taskId && !state.executing) {
           state.taskId = taskId;
           console.log(`[WorkoPilot] Extracted taskId: ${taskId}`);
+          await startExecution(state);
         }
 
         // Detect workopilot content in assistant response
@@ -235,13 +346,31 @@ export const WorkoPilotPlugin = async ({ client, directory }) => {
           state.hasActivity = true;
           console.log(`[WorkoPilot] Detected workopilot content in assistant`);
         }
+
+        // The session resumed after going idle: no longer waiting for input
+        if (state.executing && state.waitingForInput) {
+          state.waitingForInput = false;
+          await sendExecutionUpdate('execution.waiting', { task_id: state.taskId, waiting: false });
+        }
+
+        // Keep a running execution alive; the app marks silent ones as timed out
+        if (state.executing && Date.now() - state.lastHeartbeat >= HEARTBEAT_INTERVAL_MS) {
+          state.lastHeartbeat = Date.now();
+          await sendExecutionUpdate('execution.heartbeat', { task_id: state.taskId });
+        }
       }
     },
 
     "tool.execute.before": async (input, output) => {
-      const { tool, sessionID } = input;
+      const { tool, sessionID, callID } = input;
       const { args } = output;
 
+      if (tool === 'bash') {
+        const state = getSessionState(sessionID);
+        if (state.executing && args?.command) state.pendingCommands.set(callID, args.command);
+        return;
+      }
+
       if (tool !== 'use_skill') return;
 
       let skillName = args?.skill_name;
@@ -275,10 +404,20 @@ export const WorkoPilotPlugin = async ({ client, directory }) => {
         const substatus = skillType === 'structure' ? 'structuring' : 'executing';
         await updateTaskSubstatus(state.taskId, substatus);
       }
+
+      await startExecution(state);
     },
 
     "tool.execute.after": async (input, output) => {
-      const { tool, sessionID } = input;
+      const { tool, sessionID, callID } = input;
+
+      if (tool === 'bash') {
+        const state = getSessionState(sessionID);
+        const command = state.pendingCommands.get(callID);
+        state.pendingCommands.delete(callID);
+        await reportSubtaskProgress(state, command);
+        return;
+      }
 
       if (tool !== 'use_skill') return;
 
@@ -305,6 +444,13 @@ export const WorkoPilotPlugin = async ({ client, directory }) => {
           await updateTaskSubstatus(state.taskId, substatus);
         }
 
+        if (state.executing && state.subtasks.length > 0 && state.subtasks.every(s => s.done)) {
+          await endExecution(state, 'completed');
+        } else if (state.executing) {
+          state.waitingForInput = true;
+          await sendExecutionUpdate('execution.waiting', { task_id: state.taskId, waiting: true });
+        }
+
         // Notify Clawdbot via webhook — uses stored assistant text, NOT client.session.messages()
         await notifyClawdbot(sessionID, state, directory);
 
@@ -315,8 +461,16 @@ export const WorkoPilotPlugin = async ({ client, directory }) => {
         state.lastAssistantText = '';
       }
 
+      if (event.type === 'session.error') {
+        const state = sessionState.get(event.properties?.sessionID);
+        const error = event.properties?.error;
+        if (state) await endExecution(state, 'error', error?.data?.message || error?.name);
+      }
+
       if (event.type === 'session.deleted') {
         const sessionID = event.properties?.info?.id;
+        const state = sessionState.get(sessionID);
+        if (state) await endExecution(state, 'cancelled');
         if (sessionID) sessionState.delete(sessionID);
       }
     }
@@ -0,0 +1,12 @@
+use crate::event_bus::AppEvent;
+use crate::AppState;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Emits `event` to the webview and to socket subscribers.
+pub fn emit(app: &AppHandle, event: AppEvent) {
+    if let Err(e) = app.emit(&event.event, &event.payload) {
+        eprintln!("[WORKOPILOT] Failed to emit {} event: {}", event.event, e);
+    }
+
+    app.state::<AppState>().events.publish(&event);
+}
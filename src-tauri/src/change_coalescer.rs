@@ -0,0 +1,373 @@
+use crate::db_change::{DbChangeNotification, EntityType, Operation};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_WINDOW: Duration = Duration::from_millis(300);
+/// Setting holding the coalescing window in milliseconds.
+pub const WINDOW_SETTING: &str = "db_change_window_ms";
+/// A steady stream of changes is still flushed after this many windows.
+const MAX_WINDOWS: u32 = 5;
+
+/// Changes to one project and entity type inside a batch.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BatchGroup {
+    pub project_id: Option<String>,
+    pub entity_type: EntityType,
+    pub created: Vec<String>,
+    pub updated: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+/// Payload of `db-changed-batch`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DbChangedBatchPayload {
+    pub count: usize,
+    /// Highest event journal sequence number covered by the batch.
+    pub last_seq: Option<i64>,
+    pub groups: Vec<BatchGroup>,
+}
+
+/// Net effect of a run of changes to one entity, or `None` if they cancel out.
+fn merge(previous: Operation, next: Operation) -> Option<Operation> {
+    match (previous, next) {
+        (Operation::Create, Operation::Delete) => None,
+        (Operation::Create, _) => Some(Operation::Create),
+        (_, Operation::Delete) => Some(Operation::Delete),
+        // Includes delete followed by create: the row exists again, but changed.
+        _ => Some(Operation::Update),
+    }
+}
+
+/// Parses the window setting (milliseconds), falling back to the default.
+pub fn window_from_setting(value: Option<&str>) -> Duration {
+    value
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_WINDOW)
+}
+
+type ChangeKey = (EntityType, String);
+
+/// Collects change notifications and releases them as one batch once no new
+/// change arrived for `window`.
+pub struct ChangeCoalescer {
+    window: Duration,
+    order: Vec<ChangeKey>,
+    pending: HashMap<ChangeKey, DbChangeNotification>,
+    first_at: Option<Instant>,
+    last_at: Option<Instant>,
+}
+
+impl ChangeCoalescer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            order: Vec::new(),
+            pending: HashMap::new(),
+            first_at: None,
+            last_at: None,
+        }
+    }
+
+    pub fn push(&mut self, notification: DbChangeNotification, now: Instant) {
+        let key = (notification.entity_type, notification.entity_id.clone());
+        self.first_at.get_or_insert(now);
+        self.last_at = Some(now);
+
+        let merged = match self.pending.get(&key) {
+            Some(previous) => merge(previous.operation, notification.operation),
+            None => Some(notification.operation),
+        };
+
+        match merged {
+            Some(operation) => {
+                let project_id = notification.project_id.clone().or_else(|| {
+                    self.pending
+                        .get(&key)
+                        .and_then(|previous| previous.project_id.clone())
+                });
+                if !self.pending.contains_key(&key) {
+                    self.order.push(key.clone());
+                }
+                self.pending.insert(
+                    key,
+                    DbChangeNotification {
+                        operation,
+                        project_id,
+                        ..notification
+                    },
+                );
+            }
+            None => {
+                self.pending.remove(&key);
+                self.order.retain(|k| k != &key);
+            }
+        }
+    }
+
+    /// When the pending changes become ready, if there are any.
+    fn deadline(&self) -> Option<Instant> {
+        let (first_at, last_at) = (self.first_at?, self.last_at?);
+        Some((last_at + self.window).min(first_at + self.window * MAX_WINDOWS))
+    }
+
+    /// Takes the pending changes if the window has passed since the last one
+    /// (or the batch has been open too long). Returns `None` while waiting.
+    pub fn take_ready(&mut self, now: Instant) -> Option<Vec<DbChangeNotification>> {
+        let (first_at, last_at) = (self.first_at?, self.last_at?);
+        let quiet = now.duration_since(last_at) >= self.window;
+        let overdue = now.duration_since(first_at) >= self.window * MAX_WINDOWS;
+        if !quiet && !overdue {
+            return None;
+        }
+
+        self.first_at = None;
+        self.last_at = None;
+        let mut pending = std::mem::take(&mut self.pending);
+        let changes: Vec<DbChangeNotification> = std::mem::take(&mut self.order)
+            .into_iter()
+            .filter_map(|key| pending.remove(&key))
+            .collect();
+
+        Some(changes)
+    }
+}
+
+struct QueueState {
+    coalescer: ChangeCoalescer,
+    closed: bool,
+}
+
+/// A [`ChangeCoalescer`] shared between producers and one emitter thread that
+/// sleeps until the next batch is due instead of polling.
+pub struct ChangeQueue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+}
+
+impl ChangeQueue {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                coalescer: ChangeCoalescer::new(window),
+                closed: false,
+            }),
+            changed: Condvar::new(),
+        }
+    }
+
+    pub fn push(&self, notification: DbChangeNotification) {
+        if let Ok(mut state) = self.state.lock() {
+            state.coalescer.push(notification, Instant::now());
+            self.changed.notify_all();
+        }
+    }
+
+    /// Wakes the emitter and makes [`ChangeQueue::next_batch`] return `None`.
+    pub fn close(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.closed = true;
+            self.changed.notify_all();
+        }
+    }
+
+    /// Blocks until a non-empty batch is ready, or returns `None` once closed.
+    pub fn next_batch(&self) -> Option<Vec<DbChangeNotification>> {
+        let mut state = self.state.lock().ok()?;
+
+        loop {
+            if state.closed {
+                return None;
+            }
+
+            let now = Instant::now();
+            if let Some(changes) = state.coalescer.take_ready(now) {
+                if !changes.is_empty() {
+                    return Some(changes);
+                }
+                continue;
+            }
+
+            state = match state.coalescer.deadline() {
+                Some(deadline) => {
+                    self.changed
+                        .wait_timeout(state, deadline.saturating_duration_since(now))
+                        .ok()?
+                        .0
+                }
+                None => self.changed.wait(state).ok()?,
+            };
+        }
+    }
+}
+
+/// Net effect of `changes` in order, without waiting for a window.
+pub fn coalesce(
+    changes: impl IntoIterator<Item = DbChangeNotification>,
+) -> Vec<DbChangeNotification> {
+    let now = Instant::now();
+    let mut coalescer = ChangeCoalescer::new(Duration::ZERO);
+    for change in changes {
+        coalescer.push(change, now);
+    }
+    coalescer.take_ready(now).unwrap_or_default()
+}
+
+pub fn batch_payload(changes: &[DbChangeNotification]) -> DbChangedBatchPayload {
+    let mut groups: Vec<BatchGroup> = Vec::new();
+
+    for change in changes {
+        let index = match groups
+            .iter()
+            .position(|g| g.project_id == change.project_id && g.entity_type == change.entity_type)
+        {
+            Some(index) => index,
+            None => {
+                groups.push(BatchGroup {
+                    project_id: change.project_id.clone(),
+                    entity_type: change.entity_type,
+                    created: Vec::new(),
+                    updated: Vec::new(),
+                    deleted: Vec::new(),
+                });
+                groups.len() - 1
+            }
+        };
+
+        let group = &mut groups[index];
+        let ids = match change.operation {
+            Operation::Create => &mut group.created,
+            Operation::Update => &mut group.updated,
+            Operation::Delete => &mut group.deleted,
+        };
+        ids.push(change.entity_id.clone());
+    }
+
+    DbChangedBatchPayload {
+        count: changes.len(),
+        last_seq: changes.iter().filter_map(|change| change.seq).max(),
+        groups,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(
+        entity_type: &str,
+        id: &str,
+        operation: &str,
+        project: Option<&str>,
+    ) -> DbChangeNotification {
+        DbChangeNotification {
+            entity_type: EntityType::from_str(entity_type).unwrap(),
+            entity_id: id.to_string(),
+            operation: Operation::from_str(operation).unwrap(),
+            project_id: project.map(str::to_string),
+            seq: None,
+        }
+    }
+
+    fn ops(changes: &[DbChangeNotification]) -> Vec<String> {
+        changes
+            .iter()
+            .map(|c| format!("{} {}", c.operation, c.entity_id))
+            .collect()
+    }
+
+    #[test]
+    fn test_operations_merge_per_entity() {
+        let start = Instant::now();
+        let mut coalescer = ChangeCoalescer::new(DEFAULT_WINDOW);
+
+        coalescer.push(change("subtask", "s1", "create", Some("p1")), start);
+        coalescer.push(change("subtask", "s1", "update", None), start);
+        coalescer.push(change("subtask", "s2", "update", Some("p1")), start);
+        coalescer.push(change("subtask", "s2", "delete", Some("p1")), start);
+        coalescer.push(change("subtask", "s3", "create", Some("p1")), start);
+        coalescer.push(change("subtask", "s3", "delete", Some("p1")), start);
+
+        let changes = coalescer.take_ready(start + DEFAULT_WINDOW).unwrap();
+        assert_eq!(ops(&changes), vec!["create s1", "delete s2"]);
+        assert_eq!(changes[0].project_id.as_deref(), Some("p1"));
+    }
+
+    #[test]
+    fn test_batch_waits_for_quiet_window_but_not_forever() {
+        let start = Instant::now();
+        let step = DEFAULT_WINDOW / 2;
+        let mut coalescer = ChangeCoalescer::new(DEFAULT_WINDOW);
+
+        coalescer.push(change("task", "t1", "update", None), start);
+        assert!(coalescer.take_ready(start + step).is_none());
+
+        let mut now = start;
+        for i in 0..=(MAX_WINDOWS * 2) {
+            now = start + step * i;
+            coalescer.push(change("task", &format!("t{}", i), "update", None), now);
+            if coalescer.take_ready(now).is_some() {
+                break;
+            }
+        }
+        assert!(now.duration_since(start) >= DEFAULT_WINDOW * MAX_WINDOWS);
+        assert!(coalescer.take_ready(now + DEFAULT_WINDOW).is_none());
+    }
+
+    #[test]
+    fn test_queue_blocks_until_batch_is_due_and_stops_on_close() {
+        let queue = std::sync::Arc::new(ChangeQueue::new(Duration::from_millis(20)));
+        let emitter = {
+            let queue = queue.clone();
+            std::thread::spawn(move || {
+                let first = queue.next_batch();
+                (first, queue.next_batch())
+            })
+        };
+
+        let pushed_at = Instant::now();
+        queue.push(change("task", "t1", "update", None));
+        std::thread::sleep(Duration::from_millis(100));
+        queue.close();
+
+        let (first, after_close) = emitter.join().unwrap();
+        assert_eq!(ops(&first.unwrap()), vec!["update t1"]);
+        assert!(after_close.is_none());
+        assert!(pushed_at.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_coalesce_merges_without_a_window() {
+        let changes = coalesce(vec![
+            change("task", "t1", "create", Some("p1")),
+            change("task", "t2", "update", Some("p1")),
+            change("task", "t1", "update", None),
+            change("task", "t2", "delete", Some("p1")),
+        ]);
+        assert_eq!(ops(&changes), vec!["create t1", "delete t2"]);
+        assert!(coalesce(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_batch_payload_groups_by_project_and_entity_type() {
+        let mut changes = vec![
+            change("subtask", "s1", "create", Some("p1")),
+            change("subtask", "s2", "update", Some("p1")),
+            change("task", "t1", "delete", Some("p1")),
+            change("subtask", "s9", "update", Some("p2")),
+        ];
+        changes[0].seq = Some(12);
+        changes[2].seq = Some(9);
+        let payload = batch_payload(&changes);
+
+        assert_eq!(payload.count, 4);
+        assert_eq!(payload.last_seq, Some(12));
+        assert_eq!(payload.groups.len(), 3);
+        assert_eq!(payload.groups[0].created, vec!["s1"]);
+        assert_eq!(payload.groups[0].updated, vec!["s2"]);
+        assert_eq!(payload.groups[1].deleted, vec!["t1"]);
+        assert_eq!(payload.groups[2].project_id.as_deref(), Some("p2"));
+    }
+}
@@ -1,25 +1,15 @@
+use crate::app_events;
+use crate::change_coalescer::{self, DbChangedBatchPayload};
+use crate::database::JournalEntry;
+use crate::db_change::{DbChangeNotification, EntityType, Operation};
+use crate::event_bus::AppEvent;
+use crate::sidecar::SidecarError;
+use crate::terminal::get_project_and_task;
+use crate::trpc_client::TrpcClient;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
 use std::process::Command;
-use tauri::{Emitter, Manager, State};
-
-macro_rules! sidecar_call {
-    ($state:expr, $method:expr) => {{
-        let mut sidecar = $state.sidecar.sidecar.lock().map_err(|e| e.to_string())?;
-        if !sidecar.is_running() {
-            sidecar.start()?;
-        }
-        sidecar.call($method, None)
-    }};
-    ($state:expr, $method:expr, $params:expr) => {{
-        let mut sidecar = $state.sidecar.sidecar.lock().map_err(|e| e.to_string())?;
-        if !sidecar.is_running() {
-            sidecar.start()?;
-        }
-        sidecar.call($method, Some($params))
-    }};
-}
+use tauri::{Manager, State};
 
 // ============================================================================
 // Types (kept for commands that still need them)
@@ -35,8 +25,9 @@ pub struct Project {
     pub display_order: i32,
     #[serde(default)]
     pub routes: Vec<ProjectRoute>,
-    #[serde(default)]
-    pub tmux_config: Option<TmuxConfig>,
+    /// Stored as `tmux_config` for compatibility; used by every multiplexer.
+    #[serde(default, rename = "tmux_config")]
+    pub layout: Option<SessionLayout>,
     #[serde(default)]
     pub business_rules: Option<String>,
     #[serde(default)]
@@ -54,7 +45,7 @@ pub struct ProjectRoute {
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TmuxTab {
+pub struct LayoutTab {
     pub id: String,
     pub name: String,
     pub route_id: String,
@@ -62,10 +53,11 @@ pub struct TmuxTab {
     pub order: i32,
 }
 
+/// Tabs opened by the "launch project" action, independent of the multiplexer.
 #[derive(Serialize, Deserialize, Clone, Debug)]
-pub struct TmuxConfig {
+pub struct SessionLayout {
     pub session_name: String,
-    pub tabs: Vec<TmuxTab>,
+    pub tabs: Vec<LayoutTab>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -419,23 +411,29 @@ pub struct QuickfixPayload {
     pub error: Option<String>,
 }
 
-#[tauri::command]
-pub async fn launch_quickfix_background(
-    app_handle: tauri::AppHandle,
-    state: State<'_, AppState>,
-    project_id: String,
-    task_id: String,
-    quickfix_prompt: String,
-) -> Result<(), String> {
-    let project_result = sidecar_call!(state, "projects.get", json!({ "id": project_id }))?;
-    let project: Project = serde_json::from_value(project_result)
-        .map_err(|e| format!("Deserialize error: {}", e))?;
-    
-    let task_result = sidecar_call!(state, "tasks.getFull", json!({ "id": task_id }))?;
-    let task_full: TaskFull = serde_json::from_value(task_result)
-        .map_err(|e| format!("Deserialize error: {}", e))?;
+fn emit_quickfix(app_handle: &tauri::AppHandle, project_id: &str, payload: QuickfixPayload) {
+    app_events::emit(
+        app_handle,
+        AppEvent::new("quickfix-changed", &payload)
+            .entity("quickfix")
+            .project(Some(project_id.to_string())),
+    );
+}
 
-    let first_route = project.routes.first().ok_or("No routes configured")?;
+/// Resolves the working directory and opencode prompt for a quickfix run.
+fn prepare_quickfix(
+    trpc: &TrpcClient,
+    project_id: &str,
+    task_id: &str,
+    quickfix_prompt: &str,
+) -> Result<(String, String), SidecarError> {
+    let (project, task_full) = get_project_and_task(trpc, project_id, Some(task_id))?;
+    let task_full = task_full.ok_or_else(|| SidecarError::NotFound(format!("Task not found: {}", task_id)))?;
+
+    let first_route = project
+        .routes
+        .first()
+        .ok_or_else(|| SidecarError::Validation("No routes configured".to_string()))?;
     let project_path = first_route.path.clone();
 
     let full_prompt = format!(
@@ -443,7 +441,22 @@ pub async fn launch_quickfix_background(
         task_full.title, task_id, quickfix_prompt
     );
 
-    let _ = app_handle.emit("quickfix-changed", QuickfixPayload {
+    Ok((project_path, full_prompt))
+}
+
+#[tauri::command]
+pub async fn launch_quickfix_background(
+    app_handle: tauri::AppHandle,
+    state: State<'_, AppState>,
+    project_id: String,
+    task_id: String,
+    quickfix_prompt: String,
+) -> Result<(), SidecarError> {
+    let trpc = state.sidecar.sidecar.trpc().map_err(SidecarError::Transport)?;
+    let (project_path, full_prompt) =
+        prepare_quickfix(&trpc, &project_id, &task_id, &quickfix_prompt)?;
+
+    emit_quickfix(&app_handle, &project_id, QuickfixPayload {
         task_id: task_id.clone(),
         status: "running".to_string(),
         prompt: Some(quickfix_prompt.clone()),
@@ -452,6 +465,7 @@ pub async fn launch_quickfix_background(
 
     let app_handle_clone = app_handle.clone();
     let task_id_clone = task_id.clone();
+    let project_id_clone = project_id.clone();
 
     std::thread::spawn(move || {
         let result = Command::new("opencode")
@@ -466,7 +480,7 @@ pub async fn launch_quickfix_background(
                 if !output.status.success() {
                     let error_msg = String::from_utf8_lossy(&output.stderr).to_string();
                     eprintln!("[WorkoPilot] Quickfix failed: {}", error_msg);
-                    let _ = app_handle_clone.emit("quickfix-changed", QuickfixPayload {
+                    emit_quickfix(&app_handle_clone, &project_id_clone, QuickfixPayload {
                         task_id: task_id_clone,
                         status: "failed".to_string(),
                         prompt: None,
@@ -474,7 +488,7 @@ pub async fn launch_quickfix_background(
                     });
                 } else {
                     eprintln!("[WorkoPilot] Quickfix completed successfully");
-                    let _ = app_handle_clone.emit("quickfix-changed", QuickfixPayload {
+                    emit_quickfix(&app_handle_clone, &project_id_clone, QuickfixPayload {
                         task_id: task_id_clone,
                         status: "completed".to_string(),
                         prompt: None,
@@ -485,7 +499,7 @@ pub async fn launch_quickfix_background(
             Err(e) => {
                 let error_msg = e.to_string();
                 eprintln!("[WorkoPilot] Failed to run quickfix: {}", error_msg);
-                let _ = app_handle_clone.emit("quickfix-changed", QuickfixPayload {
+                emit_quickfix(&app_handle_clone, &project_id_clone, QuickfixPayload {
                     task_id: task_id_clone,
                     status: "failed".to_string(),
                     prompt: None,
@@ -527,3 +541,55 @@ pub fn get_user_sessions(
         app_version: s.app_version,
     }).collect())
 }
+
+// ============================================================================
+// Event Journal (replay of change notifications)
+// ============================================================================
+
+const EVENT_PAGE_LIMIT: i64 = 500;
+
+#[derive(Serialize)]
+pub struct EventJournalPage {
+    pub events: Vec<JournalEntry>,
+    pub latest_seq: i64,
+    /// More entries follow; ask again from the last returned `seq`.
+    pub has_more: bool,
+    /// Entries after `since` were already pruned; refetch everything instead.
+    pub truncated: bool,
+    /// Net effect of `events`, shaped like a `db-changed-batch` payload.
+    pub batch: DbChangedBatchPayload,
+}
+
+fn replay_batch(events: &[JournalEntry]) -> DbChangedBatchPayload {
+    let changes = events.iter().filter_map(|event| {
+        Some(DbChangeNotification {
+            entity_type: EntityType::from_str(&event.entity_type)?,
+            entity_id: event.entity_id.clone(),
+            operation: Operation::from_str(&event.operation)?,
+            project_id: event.project_id.clone(),
+            seq: Some(event.seq),
+        })
+    });
+    change_coalescer::batch_payload(&change_coalescer::coalesce(changes))
+}
+
+#[tauri::command]
+pub fn get_events_since(
+    state: State<AppState>,
+    since: i64,
+    limit: Option<i64>,
+) -> Result<EventJournalPage, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    let limit = limit.unwrap_or(EVENT_PAGE_LIMIT).clamp(1, EVENT_PAGE_LIMIT);
+
+    let (oldest_seq, latest_seq) = db.get_journal_bounds().map_err(|e| e.to_string())?;
+    let events = db.get_journal_since(since, limit).map_err(|e| e.to_string())?;
+
+    Ok(EventJournalPage {
+        has_more: events.last().is_some_and(|e| e.seq < latest_seq),
+        truncated: oldest_seq > since + 1,
+        batch: replay_batch(&events),
+        events,
+        latest_seq,
+    })
+}
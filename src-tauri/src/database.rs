@@ -1,10 +1,17 @@
+use crate::db_watcher::OwnWrites;
+use rusqlite::hooks::Action;
 use rusqlite::{params, Connection, Result};
 use std::path::PathBuf;
 
 pub struct Database {
     conn: Connection,
+    own_writes: OwnWrites,
 }
 
+/// Journal entries kept for replay; older ones are pruned.
+const JOURNAL_RETENTION: i64 = 10_000;
+const JOURNAL_PRUNE_EVERY: i64 = 500;
+
 impl Database {
     pub fn new() -> Result<Self> {
         let db_path = Self::get_db_path();
@@ -13,13 +20,48 @@ impl Database {
             std::fs::create_dir_all(parent).ok();
         }
 
-        let conn = Connection::open(&db_path)?;
-        let db = Database { conn };
+        Self::from_connection(Connection::open(&db_path)?)
+    }
+
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    #[cfg(test)]
+    pub fn open_path(path: &std::path::Path) -> Result<Self> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        let own_writes = OwnWrites::default();
+
+        // Change-trigger rows this connection writes, for `db_watcher` to skip.
+        let staged = own_writes.clone();
+        conn.update_hook(Some(move |action, _: &str, table: &str, rowid| {
+            if action == Action::SQLITE_INSERT && table == "operation_logs" {
+                staged.stage(rowid);
+            }
+        }));
+        let committed = own_writes.clone();
+        conn.commit_hook(Some(move || {
+            committed.commit();
+            false
+        }));
+        let rolled_back = own_writes.clone();
+        conn.rollback_hook(Some(move || rolled_back.rollback()));
+
+        let db = Database { conn, own_writes };
         db.init_schema()?;
         Ok(db)
     }
 
-    fn get_db_path() -> PathBuf {
+    /// Trigger rows written through this connection; see [`OwnWrites`].
+    pub fn own_writes(&self) -> OwnWrites {
+        self.own_writes.clone()
+    }
+
+    pub fn get_db_path() -> PathBuf {
         dirs::data_local_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("workopilot")
@@ -69,12 +111,14 @@ impl Database {
         self.migrate_tasks_full_fields()?;
         self.migrate_subtasks_table()?;
         self.migrate_operation_logs_table()?;
+        self.migrate_event_journal_table()?;
         self.migrate_task_executions_table()?;
         self.migrate_user_sessions_table()?;
         self.migrate_projects_display_order()?;
         self.migrate_tasks_substatus()?;
         self.migrate_task_status_values()?;
         self.migrate_projects_color()?;
+        self.migrate_change_triggers()?;
 
         Ok(())
     }
@@ -283,6 +327,65 @@ impl Database {
         Ok(())
     }
 
+    /// Logs every write to the watched tables into `operation_logs`, whoever
+    /// makes it (app, sidecar, CLI, a manual `sqlite3` session), so `db_watcher`
+    /// can pick up changes nobody announced over the socket. `db_watcher` also
+    /// prunes these rows.
+    fn migrate_change_triggers(&self) -> Result<()> {
+        let project_via_task = "(SELECT project_id FROM tasks WHERE id = {row}.task_id)";
+        // Heartbeats only touch `last_heartbeat`, which nobody watches.
+        let execution_update = "UPDATE OF status, current_step, total_steps, current_step_description, \
+                                waiting_for_input, tmux_session, pid, error_message, ended_at";
+        let tables = [
+            ("projects", "project", "{row}.id", "UPDATE"),
+            ("tasks", "task", "{row}.project_id", "UPDATE"),
+            ("subtasks", "subtask", project_via_task, "UPDATE"),
+            ("task_executions", "execution", project_via_task, execution_update),
+        ];
+
+        // Recreated on every start, in one transaction, so existing databases
+        // pick up changes without a window where writes go unlogged.
+        let mut sql = String::from("BEGIN;");
+        for (table, entity_type, project_expr, update_event) in tables {
+            let events = [
+                ("INSERT", "create", "NEW"),
+                (update_event, "update", "NEW"),
+                ("DELETE", "delete", "OLD"),
+            ];
+            for (event, operation, row) in events {
+                let project = project_expr.replace("{row}", row);
+                sql.push_str(&format!(
+                    "DROP TRIGGER IF EXISTS log_{table}_{operation};
+                     CREATE TRIGGER log_{table}_{operation}
+                     AFTER {event} ON {table}
+                     BEGIN
+                         INSERT INTO operation_logs (id, entity_type, entity_id, operation, new_data, source)
+                         VALUES (lower(hex(randomblob(16))), '{entity_type}', {row}.id, '{operation}',
+                                 json_object('project_id', {project}), 'trigger');
+                     END;"
+                ));
+            }
+        }
+        sql.push_str("COMMIT;");
+        self.conn.execute_batch(&sql)
+    }
+
+    fn migrate_event_journal_table(&self) -> Result<()> {
+        self.conn.execute_batch(
+            "
+            CREATE TABLE IF NOT EXISTS event_journal (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                project_id TEXT,
+                created_at TEXT DEFAULT CURRENT_TIMESTAMP
+            );
+            "
+        )?;
+        Ok(())
+    }
+
     fn migrate_task_executions_table(&self) -> Result<()> {
         self.conn.execute_batch(
             "
@@ -1258,6 +1361,7 @@ impl Database {
         }
         if waiting_for_input.is_some() {
             updates.push(format!("waiting_for_input = ?{}", param_count));
+            param_count += 1;
         }
 
         let sql = format!(
@@ -1322,6 +1426,56 @@ impl Database {
         }
     }
 
+    /// Most recent execution of a task, running or finished.
+    pub fn get_latest_execution(&self, task_id: &str) -> Result<Option<TaskExecution>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, task_id, subtask_id, execution_type, status, current_step, total_steps,
+             current_step_description, waiting_for_input, tmux_session, pid, last_heartbeat,
+             error_message, started_at, ended_at
+             FROM task_executions WHERE task_id = ?1 ORDER BY started_at DESC LIMIT 1",
+        )?;
+
+        let result = stmt.query_row([task_id], |row| {
+            Ok(TaskExecution {
+                id: row.get(0)?,
+                task_id: row.get(1)?,
+                subtask_id: row.get(2)?,
+                execution_type: row.get(3)?,
+                status: row.get(4)?,
+                current_step: row.get(5)?,
+                total_steps: row.get(6)?,
+                current_step_description: row.get(7)?,
+                waiting_for_input: row.get::<_, i32>(8)? == 1,
+                tmux_session: row.get(9)?,
+                pid: row.get(10)?,
+                last_heartbeat: row.get(11)?,
+                error_message: row.get(12)?,
+                started_at: row.get(13)?,
+                ended_at: row.get(14)?,
+            })
+        });
+
+        match result {
+            Ok(execution) => Ok(Some(execution)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn get_task_project_id(&self, task_id: &str) -> Result<Option<String>> {
+        let result = self.conn.query_row(
+            "SELECT project_id FROM tasks WHERE id = ?1",
+            [task_id],
+            |row| row.get::<_, Option<String>>(0),
+        );
+
+        match result {
+            Ok(project_id) => Ok(project_id),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn get_all_active_executions(&self) -> Result<Vec<TaskExecution>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, task_id, subtask_id, execution_type, status, current_step, total_steps,
@@ -1452,6 +1606,63 @@ impl Database {
         Ok(sessions)
     }
 
+    /// Records a change notification and returns its sequence number.
+    pub fn append_journal_entry(
+        &self,
+        entity_type: &str,
+        entity_id: &str,
+        operation: &str,
+        project_id: Option<&str>,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO event_journal (entity_type, entity_id, operation, project_id)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![entity_type, entity_id, operation, project_id],
+        )?;
+        let seq = self.conn.last_insert_rowid();
+
+        if seq % JOURNAL_PRUNE_EVERY == 0 {
+            self.conn.execute(
+                "DELETE FROM event_journal WHERE seq <= ?1",
+                [seq - JOURNAL_RETENTION],
+            )?;
+        }
+
+        Ok(seq)
+    }
+
+    /// Entries with a sequence number above `since`, oldest first.
+    pub fn get_journal_since(&self, since: i64, limit: i64) -> Result<Vec<JournalEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT seq, entity_type, entity_id, operation, project_id, created_at
+             FROM event_journal WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+        )?;
+
+        let entries = stmt
+            .query_map(params![since, limit], |row| {
+                Ok(JournalEntry {
+                    seq: row.get(0)?,
+                    entity_type: row.get(1)?,
+                    entity_id: row.get(2)?,
+                    operation: row.get(3)?,
+                    project_id: row.get(4)?,
+                    created_at: row.get(5)?,
+                })
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(entries)
+    }
+
+    /// Oldest and newest retained sequence numbers (0 when the journal is empty).
+    pub fn get_journal_bounds(&self) -> Result<(i64, i64)> {
+        self.conn.query_row(
+            "SELECT COALESCE(MIN(seq), 0), COALESCE(MAX(seq), 0) FROM event_journal",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+    }
+
     pub fn add_task_tokens(&self, task_id: &str, tokens: i64, session_id: &str) -> Result<()> {
         let current_metadata: Option<String> = self
             .conn
@@ -1579,6 +1790,16 @@ pub struct TaskExecution {
     pub ended_at: Option<String>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub struct JournalEntry {
+    pub seq: i64,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub operation: String,
+    pub project_id: Option<String>,
+    pub created_at: String,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Clone)]
 pub struct UserSession {
     pub id: String,
@@ -1694,3 +1915,26 @@ impl TaskFull {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_returns_entries_after_sequence() {
+        let db = Database::open_in_memory().unwrap();
+        let first = db.append_journal_entry("task", "t1", "create", Some("p1")).unwrap();
+        let second = db.append_journal_entry("task", "t1", "update", Some("p1")).unwrap();
+        let third = db.append_journal_entry("subtask", "s1", "delete", None).unwrap();
+        assert!(first < second && second < third);
+
+        let entries = db.get_journal_since(first, 10).unwrap();
+        assert_eq!(
+            entries.iter().map(|e| e.seq).collect::<Vec<_>>(),
+            vec![second, third]
+        );
+        assert_eq!(entries[1].operation, "delete");
+        assert_eq!(db.get_journal_since(first, 1).unwrap().len(), 1);
+        assert_eq!(db.get_journal_bounds().unwrap(), (first, third));
+    }
+}
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Entities a change notification can refer to. Must match `NotifyPayload` in
+/// packages/cli/src/socket-notify.ts and `ENTITY_TYPES` in the sidecar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntityType {
+    Project,
+    Task,
+    Subtask,
+    Execution,
+    Terminal,
+    Category,
+    Urgency,
+    Setting,
+}
+
+impl EntityType {
+    pub const ALL: [EntityType; 8] = [
+        EntityType::Project,
+        EntityType::Task,
+        EntityType::Subtask,
+        EntityType::Execution,
+        EntityType::Terminal,
+        EntityType::Category,
+        EntityType::Urgency,
+        EntityType::Setting,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntityType::Project => "project",
+            EntityType::Task => "task",
+            EntityType::Subtask => "subtask",
+            EntityType::Execution => "execution",
+            EntityType::Terminal => "terminal",
+            EntityType::Category => "category",
+            EntityType::Urgency => "urgency",
+            EntityType::Setting => "setting",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Operation::Create => "create",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        [Operation::Create, Operation::Update, Operation::Delete]
+            .into_iter()
+            .find(|o| o.as_str() == value)
+    }
+}
+
+impl fmt::Display for EntityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `{"entity_type":"task","entity_id":"t1","operation":"update","project_id":"p1"}`
+#[derive(Debug, Deserialize, Clone)]
+pub struct DbChangeNotification {
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    pub operation: Operation,
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// Event journal sequence number, assigned on receipt.
+    #[serde(skip)]
+    pub seq: Option<i64>,
+}
+
+impl DbChangeNotification {
+    pub fn payload(&self) -> DbChangedPayload {
+        DbChangedPayload {
+            entity_type: self.entity_type,
+            entity_id: self.entity_id.clone(),
+            operation: self.operation,
+            project_id: self.project_id.clone(),
+            seq: self.seq,
+        }
+    }
+}
+
+/// Payload of the per-entity `db-changed` event.
+#[derive(Debug, Serialize, Clone)]
+pub struct DbChangedPayload {
+    pub entity_type: EntityType,
+    pub entity_id: String,
+    pub operation: Operation,
+    pub project_id: Option<String>,
+    pub seq: Option<i64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects_unknown_entity_types_and_operations() {
+        let parse = |line: &str| serde_json::from_str::<DbChangeNotification>(line);
+
+        let change = parse(
+            r#"{"entity_type":"urgency","entity_id":"u1","operation":"delete","project_id":"p1"}"#,
+        )
+        .unwrap();
+        assert_eq!(change.entity_type, EntityType::Urgency);
+        assert_eq!(change.operation, Operation::Delete);
+
+        let error = parse(r#"{"entity_type":"tsak","entity_id":"t1","operation":"update"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown variant `tsak`"), "{}", error);
+        assert!(parse(r#"{"entity_type":"task","entity_id":"t1","operation":"upsert"}"#).is_err());
+    }
+
+    #[test]
+    fn test_string_round_trip() {
+        for entity_type in EntityType::ALL {
+            assert_eq!(
+                EntityType::from_str(entity_type.as_str()),
+                Some(entity_type)
+            );
+            assert_eq!(
+                serde_json::to_value(entity_type).unwrap(),
+                serde_json::json!(entity_type.as_str())
+            );
+        }
+        assert_eq!(Operation::from_str("create"), Some(Operation::Create));
+        assert_eq!(Operation::from_str("upsert"), None);
+    }
+}
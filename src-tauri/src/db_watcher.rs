@@ -0,0 +1,425 @@
+use crate::db_change::{DbChangeNotification, EntityType, Operation};
+use rusqlite::{Connection, OpenFlags, Result};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Trigger rows are only read once, shortly after they are written; older
+/// ones are deleted this often.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const TRIGGER_LOG_RETENTION: &str = "-1 days";
+/// How long a trigger row waits for the writer's own notification (socket or
+/// `entity.changed`) to reach the journal before the watcher reports it.
+pub const NOTIFY_GRACE: Duration = Duration::from_secs(2);
+
+/// `operation_logs` rowids written through the app's own connection; the
+/// frontend made those changes itself, so the watcher skips them. Rowids only
+/// count once committed: a rolled-back one may be reused by another writer.
+#[derive(Clone, Default)]
+pub struct OwnWrites(Arc<Mutex<OwnRowids>>);
+
+#[derive(Default)]
+struct OwnRowids {
+    staged: Vec<i64>,
+    committed: BTreeSet<i64>,
+}
+
+impl OwnWrites {
+    pub fn stage(&self, rowid: i64) {
+        if let Ok(mut rowids) = self.0.lock() {
+            rowids.staged.push(rowid);
+        }
+    }
+
+    pub fn commit(&self) {
+        if let Ok(mut rowids) = self.0.lock() {
+            let staged = std::mem::take(&mut rowids.staged);
+            rowids.committed.extend(staged);
+        }
+    }
+
+    pub fn rollback(&self) {
+        if let Ok(mut rowids) = self.0.lock() {
+            rowids.staged.clear();
+        }
+    }
+
+    /// Whether `rowid` was ours, forgetting it and anything older.
+    fn take(&self, rowid: i64) -> bool {
+        let Ok(mut rowids) = self.0.lock() else {
+            return false;
+        };
+        let ours = rowids.committed.contains(&rowid);
+        rowids.committed = rowids.committed.split_off(&(rowid + 1));
+        ours
+    }
+}
+
+/// Detects writes nobody announced: `PRAGMA data_version` changes whenever
+/// another connection commits, and the new `operation_logs` rows (filled by
+/// the change triggers in `database.rs`) say what changed. Rows from the app's
+/// own connection, and changes the journal already has from the socket or the
+/// sidecar, are dropped.
+pub struct DbWatcher {
+    conn: Connection,
+    data_version: i64,
+    last_rowid: i64,
+    own_writes: OwnWrites,
+    grace: Duration,
+    /// Rows seen but still inside `grace`.
+    pending: bool,
+}
+
+impl DbWatcher {
+    pub fn open(path: &Path, own_writes: OwnWrites) -> Result<Self> {
+        // Writable only to prune the trigger rows it has read.
+        let conn = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        conn.busy_timeout(Duration::from_secs(1))?;
+
+        let data_version = data_version(&conn)?;
+        // Only report what happens from now on, not the existing history.
+        let last_rowid = conn.query_row(
+            "SELECT COALESCE(MAX(rowid), 0) FROM operation_logs",
+            [],
+            |row| row.get(0),
+        )?;
+
+        Ok(Self {
+            conn,
+            data_version,
+            last_rowid,
+            own_writes,
+            grace: NOTIFY_GRACE,
+            pending: false,
+        })
+    }
+
+    /// Unannounced changes committed at least `grace` ago, oldest first.
+    pub fn poll(&mut self) -> Result<Vec<DbChangeNotification>> {
+        let version = data_version(&self.conn)?;
+        if version == self.data_version && !self.pending {
+            return Ok(Vec::new());
+        }
+        self.data_version = version;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT l.rowid, l.entity_type, l.entity_id, l.operation,
+                    CASE WHEN json_valid(COALESCE(l.new_data, l.old_data))
+                         THEN json_extract(COALESCE(l.new_data, l.old_data), '$.project_id') END,
+                    l.created_at <= datetime('now', ?2),
+                    EXISTS (SELECT 1 FROM event_journal j
+                            WHERE j.entity_type = l.entity_type AND j.entity_id = l.entity_id
+                              AND j.operation = l.operation
+                              AND j.created_at >= datetime(l.created_at, '-1 seconds'))
+             FROM operation_logs l WHERE l.rowid > ?1 AND l.source = 'trigger' ORDER BY l.rowid",
+        )?;
+
+        let grace = format!("-{} seconds", self.grace.as_secs());
+        let rows = stmt
+            .query_map(rusqlite::params![self.last_rowid, grace], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, bool>(5)?,
+                    row.get::<_, bool>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>>>()?;
+
+        // Rows are in commit order, so everything after the first one still
+        // inside the grace period waits for the next poll.
+        let due = rows.iter().take_while(|row| row.5).count();
+        self.pending = due < rows.len();
+        if let Some((rowid, ..)) = rows[..due].last() {
+            self.last_rowid = *rowid;
+        }
+
+        let changes = rows
+            .into_iter()
+            .take(due)
+            .filter(|(rowid, .., notified)| !self.own_writes.take(*rowid) && !notified)
+            .filter_map(|(_, entity_type, entity_id, operation, project_id, ..)| {
+                Some(DbChangeNotification {
+                    entity_type: EntityType::from_str(&entity_type)?,
+                    entity_id,
+                    operation: Operation::from_str(&operation)?,
+                    project_id,
+                    seq: None,
+                })
+            })
+            .collect();
+
+        Ok(changes)
+    }
+
+    /// Deletes trigger rows past retention that have already been polled.
+    /// The newest row always stays: `operation_logs` has no AUTOINCREMENT, so
+    /// SQLite would otherwise hand out rowids at or below `last_rowid` again.
+    pub fn prune(&self) -> Result<usize> {
+        self.conn.execute(
+            "DELETE FROM operation_logs
+             WHERE source = 'trigger' AND rowid <= ?1 AND created_at < datetime('now', ?2)
+               AND rowid < (SELECT MAX(rowid) FROM operation_logs)",
+            rusqlite::params![self.last_rowid, TRIGGER_LOG_RETENTION],
+        )
+    }
+}
+
+fn data_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+}
+
+/// Background thread polling the database; stopped by [`WatcherHandle::shutdown`]
+/// on app exit.
+pub struct WatcherHandle {
+    shutdown_tx: Option<Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl WatcherHandle {
+    pub fn shutdown(&mut self) {
+        self.shutdown_tx = None;
+
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for WatcherHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+/// Polls the database at `path` on a background thread and hands every
+/// detected change to `on_change`.
+pub fn watch<F>(path: PathBuf, own_writes: OwnWrites, on_change: F) -> WatcherHandle
+where
+    F: Fn(DbChangeNotification) + Send + 'static,
+{
+    let (shutdown_tx, shutdown_rx) = mpsc::channel::<()>();
+
+    let thread = thread::spawn(move || {
+        let mut watcher = match DbWatcher::open(&path, own_writes) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!(
+                    "[WORKOPILOT] Failed to watch database {}: {}",
+                    path.display(),
+                    e
+                );
+                return;
+            }
+        };
+        eprintln!("[WORKOPILOT] Watching database for external changes");
+
+        let mut last_prune: Option<Instant> = None;
+        loop {
+            if last_prune.is_none_or(|at| at.elapsed() >= PRUNE_INTERVAL) {
+                if let Err(e) = watcher.prune() {
+                    eprintln!("[WORKOPILOT] Failed to prune trigger logs: {}", e);
+                }
+                last_prune = Some(Instant::now());
+            }
+
+            match shutdown_rx.recv_timeout(POLL_INTERVAL) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+
+            match watcher.poll() {
+                Ok(changes) => changes.into_iter().for_each(&on_change),
+                Err(e) => eprintln!("[WORKOPILOT] Database watcher error: {}", e),
+            }
+        }
+    });
+
+    WatcherHandle {
+        shutdown_tx: Some(shutdown_tx),
+        thread: Some(thread),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use tempfile::TempDir;
+
+    fn temp_db() -> (TempDir, Database) {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::open_path(&dir.path().join("workopilot.db")).unwrap();
+        (dir, db)
+    }
+
+    fn watcher(dir: &Path, own_writes: OwnWrites) -> DbWatcher {
+        let mut watcher = DbWatcher::open(&dir.join("workopilot.db"), own_writes).unwrap();
+        watcher.grace = Duration::ZERO;
+        watcher
+    }
+
+    fn summary(changes: &[DbChangeNotification]) -> Vec<(Operation, EntityType, &str)> {
+        changes
+            .iter()
+            .map(|c| (c.operation, c.entity_type, c.entity_id.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_detects_writes_from_other_connections() {
+        let (dir, db) = temp_db();
+        let project_id = db.add_project("Projeto", "/tmp/projeto", None).unwrap();
+        // Not the app's connection: its writes count as someone else's.
+        let mut watcher = watcher(dir.path(), OwnWrites::default());
+        assert!(watcher.poll().unwrap().is_empty());
+
+        let task_id = db.add_task(&project_id, "Tarefa", 2, "feature").unwrap();
+        // A writer that bypasses the app entirely, e.g. a manual sqlite3 session.
+        Connection::open(dir.path().join("workopilot.db"))
+            .unwrap()
+            .execute("DELETE FROM tasks WHERE id = ?1", [&task_id])
+            .unwrap();
+
+        let changes = watcher.poll().unwrap();
+        assert_eq!(
+            summary(&changes),
+            vec![
+                (Operation::Create, EntityType::Task, task_id.as_str()),
+                (Operation::Delete, EntityType::Task, task_id.as_str())
+            ]
+        );
+        assert_eq!(changes[0].project_id.as_deref(), Some(project_id.as_str()));
+        assert!(watcher.poll().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_skips_own_and_already_notified_writes() {
+        let (dir, db) = temp_db();
+        let project_id = db.add_project("Projeto", "/tmp/projeto", None).unwrap();
+        let mut watcher = watcher(dir.path(), db.own_writes());
+
+        // The app's own write, then a CLI write it announced over the socket.
+        let own_task = db.add_task(&project_id, "Propria", 2, "feature").unwrap();
+        let other = Connection::open(dir.path().join("workopilot.db")).unwrap();
+        other
+            .execute("UPDATE projects SET name = 'CLI' WHERE id = ?1", [&project_id])
+            .unwrap();
+        db.append_journal_entry("project", &project_id, "update", Some(&project_id))
+            .unwrap();
+        other
+            .execute("DELETE FROM tasks WHERE id = ?1", [&own_task])
+            .unwrap();
+
+        assert_eq!(
+            summary(&watcher.poll().unwrap()),
+            vec![(Operation::Delete, EntityType::Task, own_task.as_str())]
+        );
+    }
+
+    #[test]
+    fn test_holds_rows_until_grace_passes() {
+        let (dir, db) = temp_db();
+        let mut watcher = watcher(dir.path(), OwnWrites::default());
+        watcher.grace = Duration::from_secs(3600);
+
+        let project_id = db.add_project("Projeto", "/tmp/projeto", None).unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+        assert!(watcher.pending);
+
+        // Due now, without another commit to bump data_version.
+        watcher.grace = Duration::ZERO;
+        assert_eq!(
+            summary(&watcher.poll().unwrap()),
+            vec![(Operation::Create, EntityType::Project, project_id.as_str())]
+        );
+        assert!(!watcher.pending);
+    }
+
+    #[test]
+    fn test_heartbeats_are_not_logged() {
+        let (dir, db) = temp_db();
+        let project_id = db.add_project("Projeto", "/tmp/projeto", None).unwrap();
+        let task_id = db.add_task(&project_id, "Tarefa", 2, "feature").unwrap();
+        db.start_execution(&task_id, None, None, None, 2).unwrap();
+        let mut watcher = watcher(dir.path(), OwnWrites::default());
+
+        db.update_execution_progress(&task_id, None, None, None, None)
+            .unwrap();
+        assert!(watcher.poll().unwrap().is_empty());
+
+        db.update_execution_progress(&task_id, Some(1), None, None, None)
+            .unwrap();
+        let changes = watcher.poll().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].entity_type, EntityType::Execution);
+    }
+
+    #[test]
+    fn test_prune_keeps_recent_and_unread_rows() {
+        let (dir, db) = temp_db();
+        let mut watcher = watcher(dir.path(), OwnWrites::default());
+        db.add_project("Antigo", "/tmp/antigo", None).unwrap();
+        db.add_project("Novo", "/tmp/novo", None).unwrap();
+        watcher.poll().unwrap();
+
+        let other = Connection::open(dir.path().join("workopilot.db")).unwrap();
+        other
+            .execute_batch(
+                "UPDATE operation_logs SET id = 'old', created_at = datetime('now', '-2 days')
+                 WHERE rowid = (SELECT MIN(rowid) FROM operation_logs);
+                 INSERT INTO operation_logs (id, entity_type, entity_id, operation, source, created_at)
+                 VALUES ('unread', 'task', 't1', 'update', 'trigger', datetime('now', '-2 days'));",
+            )
+            .unwrap();
+
+        assert_eq!(watcher.prune().unwrap(), 1);
+        let left: i64 = other
+            .query_row(
+                "SELECT COUNT(*) FROM operation_logs WHERE id IN ('old', 'unread')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(left, 1);
+    }
+    #[test]
+    fn test_reports_writes_after_pruning_everything() {
+        let (dir, db) = temp_db();
+        let mut watcher = watcher(dir.path(), OwnWrites::default());
+        db.add_project("Antigo", "/tmp/antigo", None).unwrap();
+        db.add_project("Velho", "/tmp/velho", None).unwrap();
+        assert_eq!(watcher.poll().unwrap().len(), 2);
+
+        // An idle day: every trigger row is past retention.
+        let other = Connection::open(dir.path().join("workopilot.db")).unwrap();
+        other
+            .execute(
+                "UPDATE operation_logs SET created_at = datetime('now', '-2 days')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(watcher.prune().unwrap(), 1);
+
+        other
+            .execute(
+                "INSERT INTO projects (id, name, path) VALUES ('p9', 'Externo', '/tmp/externo')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(
+            summary(&watcher.poll().unwrap()),
+            vec![(Operation::Create, EntityType::Project, "p9")]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Mutex;
+
+/// An event emitted to the webview, mirrored to external subscribers.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AppEvent {
+    pub event: String,
+    pub entity_type: Option<String>,
+    pub project_id: Option<String>,
+    pub payload: Value,
+    pub timestamp: String,
+}
+
+impl AppEvent {
+    pub fn new<S: Serialize>(event: &str, payload: &S) -> Self {
+        Self {
+            event: event.to_string(),
+            entity_type: None,
+            project_id: None,
+            payload: serde_json::to_value(payload).unwrap_or(Value::Null),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn entity(mut self, entity_type: &str) -> Self {
+        self.entity_type = Some(entity_type.to_string());
+        self
+    }
+
+    pub fn project(mut self, project_id: Option<String>) -> Self {
+        self.project_id = project_id;
+        self
+    }
+}
+
+/// What a subscriber wants to see; empty lists match everything.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+pub struct SubscriptionFilter {
+    #[serde(default)]
+    pub events: Vec<String>,
+    #[serde(default)]
+    pub entity_types: Vec<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+impl SubscriptionFilter {
+    pub fn matches(&self, event: &AppEvent) -> bool {
+        let event_ok = self.events.is_empty() || self.events.contains(&event.event);
+        let entity_ok = self.entity_types.is_empty()
+            || event
+                .entity_type
+                .as_ref()
+                .is_some_and(|t| self.entity_types.contains(t));
+        let project_ok = self.project_id.is_none() || event.project_id == self.project_id;
+
+        event_ok && entity_ok && project_ok
+    }
+}
+
+struct Subscriber {
+    filter: SubscriptionFilter,
+    sender: Sender<AppEvent>,
+}
+
+/// Fan-out of app events to socket subscribers. Subscribers whose receiver
+/// was dropped are removed on the next publish.
+#[derive(Default)]
+pub struct EventBus {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self, filter: SubscriptionFilter) -> Receiver<AppEvent> {
+        let (sender, receiver) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(Subscriber { filter, sender });
+        }
+
+        receiver
+    }
+
+    pub fn publish(&self, event: &AppEvent) {
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|subscriber| {
+                !subscriber.filter.matches(event) || subscriber.sender.send(event.clone()).is_ok()
+            });
+        }
+    }
+
+    #[cfg(test)]
+    fn subscriber_count(&self) -> usize {
+        self.subscribers.lock().map(|s| s.len()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task_change(project_id: &str) -> AppEvent {
+        AppEvent::new("db-changed", &serde_json::json!({ "entity_id": "t1" }))
+            .entity("task")
+            .project(Some(project_id.to_string()))
+    }
+
+    #[test]
+    fn test_filter_matches_entity_type_and_project() {
+        let filter = SubscriptionFilter {
+            entity_types: vec!["task".to_string(), "execution".to_string()],
+            project_id: Some("p1".to_string()),
+            ..Default::default()
+        };
+
+        assert!(filter.matches(&task_change("p1")));
+        assert!(!filter.matches(&task_change("p2")));
+        assert!(!filter.matches(&AppEvent::new("quickfix-changed", &Value::Null)));
+        assert!(SubscriptionFilter::default().matches(&task_change("p2")));
+    }
+
+    #[test]
+    fn test_publish_delivers_to_matching_subscribers() {
+        let bus = EventBus::new();
+        let all = bus.subscribe(SubscriptionFilter::default());
+        let only_p2 = bus.subscribe(SubscriptionFilter {
+            project_id: Some("p2".to_string()),
+            ..Default::default()
+        });
+
+        bus.publish(&task_change("p1"));
+
+        assert_eq!(all.try_recv().unwrap().project_id.as_deref(), Some("p1"));
+        assert!(only_p2.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_dropped_subscribers_are_removed() {
+        let bus = EventBus::new();
+        let kept = bus.subscribe(SubscriptionFilter::default());
+        drop(bus.subscribe(SubscriptionFilter::default()));
+
+        bus.publish(&task_change("p1"));
+
+        assert_eq!(bus.subscriber_count(), 1);
+        assert!(kept.try_recv().is_ok());
+    }
+}
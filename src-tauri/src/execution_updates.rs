@@ -0,0 +1,254 @@
+use crate::database::{Database, TaskExecution};
+use crate::sidecar::SidecarError;
+use serde::Deserialize;
+use serde_json::Value;
+
+pub const METHODS: &[&str] = &[
+    "execution.start",
+    "execution.progress",
+    "execution.heartbeat",
+    "execution.waiting",
+    "execution.end",
+];
+const END_STATUSES: &[&str] = &["completed", "error", "cancelled"];
+
+/// Execution lifecycle reported over the socket, e.g. by the OpenCode plugin:
+/// `{"v":1,"id":1,"method":"execution.progress","params":{"task_id":"t1","current_step":2}}`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "method", content = "params")]
+pub enum ExecutionUpdate {
+    #[serde(rename = "execution.start")]
+    Start {
+        task_id: String,
+        subtask_id: Option<String>,
+        tmux_session: Option<String>,
+        pid: Option<i32>,
+        #[serde(default)]
+        total_steps: i32,
+    },
+    #[serde(rename = "execution.progress")]
+    Progress {
+        task_id: String,
+        current_step: Option<i32>,
+        total_steps: Option<i32>,
+        description: Option<String>,
+    },
+    #[serde(rename = "execution.heartbeat")]
+    Heartbeat { task_id: String },
+    #[serde(rename = "execution.waiting")]
+    WaitingForInput {
+        task_id: String,
+        #[serde(default = "waiting_default")]
+        waiting: bool,
+    },
+    #[serde(rename = "execution.end")]
+    End {
+        task_id: String,
+        status: String,
+        error_message: Option<String>,
+    },
+}
+
+fn waiting_default() -> bool {
+    true
+}
+
+fn failed(error: rusqlite::Error) -> SidecarError {
+    SidecarError::Failed(format!("Database error: {}", error))
+}
+
+impl ExecutionUpdate {
+    pub fn parse(method: &str, params: Value) -> Result<Self, String> {
+        serde_json::from_value(serde_json::json!({ "method": method, "params": params }))
+            .map_err(|e| e.to_string())
+    }
+
+    pub fn task_id(&self) -> &str {
+        match self {
+            Self::Start { task_id, .. }
+            | Self::Progress { task_id, .. }
+            | Self::Heartbeat { task_id }
+            | Self::WaitingForInput { task_id, .. }
+            | Self::End { task_id, .. } => task_id,
+        }
+    }
+
+    /// Writes the update to `task_executions` and returns the resulting row.
+    pub fn apply(&self, db: &Database) -> Result<TaskExecution, SidecarError> {
+        let task_id = self.task_id();
+        let running = db.get_active_execution(task_id).map_err(failed)?.is_some();
+
+        match self {
+            Self::Start { .. } if running => {
+                return Err(SidecarError::Validation(format!(
+                    "Task {} already has a running execution",
+                    task_id
+                )))
+            }
+            Self::Start { .. } => {}
+            _ if !running => {
+                return Err(SidecarError::NotFound(format!(
+                    "Task {} has no running execution",
+                    task_id
+                )))
+            }
+            Self::End { status, .. } if !END_STATUSES.contains(&status.as_str()) => {
+                return Err(SidecarError::Validation(format!(
+                    "Invalid end status: {}. Valid values: {}",
+                    status,
+                    END_STATUSES.join(", ")
+                )))
+            }
+            _ => {}
+        }
+
+        let written = match self {
+            Self::Start {
+                subtask_id,
+                tmux_session,
+                pid,
+                total_steps,
+                ..
+            } => db
+                .start_execution(
+                    task_id,
+                    subtask_id.as_deref(),
+                    tmux_session.as_deref(),
+                    *pid,
+                    *total_steps,
+                )
+                .map(|_| ()),
+            Self::Progress {
+                current_step,
+                total_steps,
+                description,
+                ..
+            } => db.update_execution_progress(
+                task_id,
+                *current_step,
+                *total_steps,
+                description.as_deref(),
+                None,
+            ),
+            Self::Heartbeat { .. } => db.update_execution_progress(task_id, None, None, None, None),
+            Self::WaitingForInput { waiting, .. } => {
+                db.update_execution_progress(task_id, None, None, None, Some(*waiting))
+            }
+            Self::End {
+                status,
+                error_message,
+                ..
+            } => db.end_execution(task_id, status, error_message.as_deref()),
+        };
+        written.map_err(failed)?;
+
+        db.get_latest_execution(task_id)
+            .map_err(failed)?
+            .ok_or_else(|| {
+                SidecarError::NotFound(format!("Execution of task {} not found", task_id))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(method: &str, params: Value) -> ExecutionUpdate {
+        ExecutionUpdate::parse(method, params).unwrap()
+    }
+
+    #[test]
+    fn test_parse_methods() {
+        assert_eq!(
+            update("execution.waiting", serde_json::json!({ "task_id": "t1" })),
+            ExecutionUpdate::WaitingForInput {
+                task_id: "t1".to_string(),
+                waiting: true
+            }
+        );
+        assert!(ExecutionUpdate::parse("execution.start", serde_json::json!({})).is_err());
+        assert!(
+            ExecutionUpdate::parse("execution.pause", serde_json::json!({ "task_id": "t1" }))
+                .is_err()
+        );
+    }
+
+    fn db_with_task() -> (Database, String) {
+        let db = Database::open_in_memory().unwrap();
+        let project_id = db.add_project("Projeto", "/tmp/projeto", None).unwrap();
+        let task_id = db.add_task(&project_id, "Tarefa", 2, "feature").unwrap();
+        (db, task_id)
+    }
+
+    #[test]
+    fn test_lifecycle_writes_task_executions() {
+        let (db, task_id) = db_with_task();
+
+        let started = update(
+            "execution.start",
+            serde_json::json!({ "task_id": task_id, "total_steps": 3, "pid": 42 }),
+        )
+        .apply(&db)
+        .unwrap();
+        assert_eq!(started.status, "running");
+        assert_eq!(started.pid, Some(42));
+
+        let progressed = update(
+            "execution.progress",
+            serde_json::json!({ "task_id": task_id, "current_step": 2, "description": "Testes" }),
+        )
+        .apply(&db)
+        .unwrap();
+        assert_eq!(progressed.current_step, 2);
+        assert_eq!(
+            progressed.current_step_description.as_deref(),
+            Some("Testes")
+        );
+
+        let waiting = update(
+            "execution.waiting",
+            serde_json::json!({ "task_id": task_id }),
+        )
+        .apply(&db)
+        .unwrap();
+        assert!(waiting.waiting_for_input);
+
+        let ended = update(
+            "execution.end",
+            serde_json::json!({ "task_id": task_id, "status": "completed" }),
+        )
+        .apply(&db)
+        .unwrap();
+        assert_eq!(ended.id, started.id);
+        assert_eq!(ended.status, "completed");
+        assert!(ended.ended_at.is_some());
+    }
+
+    #[test]
+    fn test_rejects_updates_without_running_execution() {
+        let (db, task_id) = db_with_task();
+
+        let heartbeat = update(
+            "execution.heartbeat",
+            serde_json::json!({ "task_id": task_id }),
+        );
+        assert!(matches!(
+            heartbeat.apply(&db),
+            Err(SidecarError::NotFound(_))
+        ));
+
+        let start = update("execution.start", serde_json::json!({ "task_id": task_id }));
+        start.apply(&db).unwrap();
+        assert!(matches!(start.apply(&db), Err(SidecarError::Validation(_))));
+
+        let bad_end = update(
+            "execution.end",
+            serde_json::json!({ "task_id": task_id, "status": "running" }),
+        );
+        assert!(matches!(
+            bad_end.apply(&db),
+            Err(SidecarError::Validation(_))
+        ));
+    }
+}
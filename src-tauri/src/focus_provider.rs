@@ -0,0 +1,542 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+const IPC_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The terminal window to raise.
+pub struct FocusTarget<'a> {
+    /// Wayland app_ids / X11 classes of the emulator, compared case-insensitively.
+    pub classes: &'a [&'a str],
+    /// The title must contain one of these, case-insensitively.
+    pub title_hints: &'a [&'a str],
+}
+
+impl FocusTarget<'_> {
+    pub fn matches(&self, class: &str, title: &str) -> bool {
+        let title = title.to_lowercase();
+        self.classes.iter().any(|c| c.eq_ignore_ascii_case(class))
+            && self
+                .title_hints
+                .iter()
+                .any(|hint| title.contains(&hint.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FocusedWindow {
+    /// Backend-specific id: sway con_id, Hyprland address or X11 window id.
+    pub id: String,
+    pub class: String,
+    pub title: String,
+}
+
+/// Which backend was asked to focus and the window it focused, if any.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FocusStatus {
+    pub backend: String,
+    pub window: Option<FocusedWindow>,
+}
+
+impl FocusStatus {
+    pub fn focused(&self) -> bool {
+        self.window.is_some()
+    }
+}
+
+impl std::fmt::Display for FocusStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.window {
+            Some(w) => write!(
+                f,
+                "{} focused window {} ({}: {})",
+                self.backend, w.id, w.class, w.title
+            ),
+            None => write!(f, "{} found no matching window", self.backend),
+        }
+    }
+}
+
+/// Raises windows through the compositor or X server in use.
+pub trait FocusProvider {
+    fn id(&self) -> &'static str;
+    /// Focuses the first window matching `target`; `Ok(None)` when there is none.
+    fn focus_window(&self, target: &FocusTarget) -> Result<Option<FocusedWindow>, String>;
+
+    fn focus(&self, target: &FocusTarget) -> Result<FocusStatus, String> {
+        Ok(FocusStatus {
+            backend: self.id().to_string(),
+            window: self.focus_window(target)?,
+        })
+    }
+}
+
+/// Picks the backend for the running session: Sway, then Hyprland, then X11
+/// (also used under XWayland when neither compositor is detected).
+pub fn detect(env: impl Fn(&str) -> Option<String>) -> Result<Box<dyn FocusProvider>, String> {
+    let env = |key: &str| env(key).filter(|v| !v.is_empty());
+
+    if let Some(socket) = env("SWAYSOCK") {
+        return Ok(Box::new(Sway::new(socket)));
+    }
+    if let Some(signature) = env("HYPRLAND_INSTANCE_SIGNATURE") {
+        return Ok(Box::new(Hyprland::new(Hyprland::socket_path(
+            env("XDG_RUNTIME_DIR").as_deref(),
+            &signature,
+        ))));
+    }
+    if env("DISPLAY").is_some() {
+        return Ok(Box::new(X11));
+    }
+    Err(
+        "No window focus backend: SWAYSOCK, HYPRLAND_INSTANCE_SIGNATURE and DISPLAY are unset"
+            .to_string(),
+    )
+}
+
+pub fn from_env() -> Result<Box<dyn FocusProvider>, String> {
+    detect(|key| std::env::var(key).ok())
+}
+
+fn connect(socket: &Path) -> Result<UnixStream, String> {
+    let stream = UnixStream::connect(socket)
+        .map_err(|e| format!("Failed to connect to {}: {}", socket.display(), e))?;
+    stream
+        .set_read_timeout(Some(IPC_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(IPC_TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+    Ok(stream)
+}
+
+// ============================================================================
+// Sway (i3 IPC)
+// ============================================================================
+
+const I3_MAGIC: &[u8; 6] = b"i3-ipc";
+const I3_RUN_COMMAND: u32 = 0;
+const I3_GET_TREE: u32 = 4;
+
+pub struct Sway {
+    socket: PathBuf,
+}
+
+impl Sway {
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// One i3-ipc message: magic, payload length, type, payload.
+    fn request(&self, kind: u32, payload: &str) -> Result<Value, String> {
+        let mut stream = connect(&self.socket)?;
+
+        let mut message = Vec::with_capacity(14 + payload.len());
+        message.extend_from_slice(I3_MAGIC);
+        message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        message.extend_from_slice(&kind.to_ne_bytes());
+        message.extend_from_slice(payload.as_bytes());
+        stream
+            .write_all(&message)
+            .map_err(|e| format!("Failed to write to sway IPC: {}", e))?;
+
+        let mut header = [0u8; 14];
+        stream
+            .read_exact(&mut header)
+            .map_err(|e| format!("Failed to read sway IPC reply: {}", e))?;
+        if &header[..6] != I3_MAGIC {
+            return Err("Invalid sway IPC reply".to_string());
+        }
+        let len = u32::from_ne_bytes([header[6], header[7], header[8], header[9]]) as usize;
+        let mut body = vec![0u8; len];
+        stream
+            .read_exact(&mut body)
+            .map_err(|e| format!("Failed to read sway IPC reply: {}", e))?;
+
+        serde_json::from_slice(&body).map_err(|e| format!("Invalid sway IPC reply: {}", e))
+    }
+}
+
+/// Depth-first search of the layout tree for a matching view.
+fn find_sway_window(node: &Value, target: &FocusTarget) -> Option<FocusedWindow> {
+    // Native Wayland views have an app_id, XWayland ones a window class.
+    let class = node["app_id"]
+        .as_str()
+        .or_else(|| node["window_properties"]["class"].as_str());
+    if let (Some(id), Some(class)) = (node["id"].as_i64(), class) {
+        let title = node["name"].as_str().unwrap_or_default();
+        if target.matches(class, title) {
+            return Some(FocusedWindow {
+                id: id.to_string(),
+                class: class.to_string(),
+                title: title.to_string(),
+            });
+        }
+    }
+
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[key].as_array())
+        .flatten()
+        .find_map(|child| find_sway_window(child, target))
+}
+
+impl FocusProvider for Sway {
+    fn id(&self) -> &'static str {
+        "sway"
+    }
+
+    fn focus_window(&self, target: &FocusTarget) -> Result<Option<FocusedWindow>, String> {
+        let tree = self.request(I3_GET_TREE, "")?;
+        let Some(window) = find_sway_window(&tree, target) else {
+            return Ok(None);
+        };
+
+        let reply = self.request(I3_RUN_COMMAND, &format!("[con_id={}] focus", window.id))?;
+        let outcome = &reply[0];
+        if outcome["success"].as_bool() != Some(true) {
+            return Err(format!(
+                "sway could not focus window {}: {}",
+                window.id,
+                outcome["error"].as_str().unwrap_or("unknown error")
+            ));
+        }
+        Ok(Some(window))
+    }
+}
+
+// ============================================================================
+// Hyprland
+// ============================================================================
+
+pub struct Hyprland {
+    socket: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct HyprClient {
+    address: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default)]
+    title: String,
+}
+
+impl Hyprland {
+    pub fn new(socket: impl Into<PathBuf>) -> Self {
+        Self {
+            socket: socket.into(),
+        }
+    }
+
+    /// Request socket of an instance; releases before 0.40 kept it in /tmp.
+    pub fn socket_path(runtime_dir: Option<&str>, signature: &str) -> PathBuf {
+        let current = runtime_dir.map(|dir| Path::new(dir).join("hypr").join(signature));
+        current
+            .filter(|dir| dir.exists())
+            .unwrap_or_else(|| Path::new("/tmp/hypr").join(signature))
+            .join(".socket.sock")
+    }
+
+    /// Hyprland answers one command per connection and then closes it.
+    fn request(&self, command: &str) -> Result<String, String> {
+        let mut stream = connect(&self.socket)?;
+        stream
+            .write_all(command.as_bytes())
+            .and_then(|_| stream.shutdown(Shutdown::Write))
+            .map_err(|e| format!("Failed to write to Hyprland IPC: {}", e))?;
+
+        let mut reply = String::new();
+        stream
+            .read_to_string(&mut reply)
+            .map_err(|e| format!("Failed to read Hyprland IPC reply: {}", e))?;
+        Ok(reply)
+    }
+}
+
+impl FocusProvider for Hyprland {
+    fn id(&self) -> &'static str {
+        "hyprland"
+    }
+
+    fn focus_window(&self, target: &FocusTarget) -> Result<Option<FocusedWindow>, String> {
+        let clients: Vec<HyprClient> = serde_json::from_str(&self.request("j/clients")?)
+            .map_err(|e| format!("Invalid Hyprland clients reply: {}", e))?;
+        let Some(client) = clients
+            .into_iter()
+            .find(|c| target.matches(&c.class, &c.title))
+        else {
+            return Ok(None);
+        };
+
+        let reply = self.request(&format!("dispatch focuswindow address:{}", client.address))?;
+        if reply.trim() != "ok" {
+            return Err(format!(
+                "Hyprland could not focus window {}: {}",
+                client.address,
+                reply.trim()
+            ));
+        }
+        Ok(Some(FocusedWindow {
+            id: client.address,
+            class: client.class,
+            title: client.title,
+        }))
+    }
+}
+
+// ============================================================================
+// X11 (xdotool)
+// ============================================================================
+
+pub struct X11;
+
+impl FocusProvider for X11 {
+    fn id(&self) -> &'static str {
+        "x11"
+    }
+
+    fn focus_window(&self, target: &FocusTarget) -> Result<Option<FocusedWindow>, String> {
+        for class in target.classes {
+            let search_output = Command::new("xdotool")
+                .args(["search", "--class", class])
+                .output()
+                .map_err(|e| format!("Failed to search for {} windows: {}", class, e))?;
+
+            if !search_output.status.success() {
+                continue;
+            }
+
+            for window_id in String::from_utf8_lossy(&search_output.stdout).lines() {
+                let window_id = window_id.trim();
+                if window_id.is_empty() {
+                    continue;
+                }
+
+                let Ok(name_output) = Command::new("xdotool")
+                    .args(["getwindowname", window_id])
+                    .output()
+                else {
+                    continue;
+                };
+                let title = String::from_utf8_lossy(&name_output.stdout)
+                    .trim()
+                    .to_string();
+                if !target.matches(class, &title) {
+                    continue;
+                }
+
+                let activated = Command::new("xdotool")
+                    .args(["windowactivate", "--sync", window_id])
+                    .output()
+                    .map(|result| result.status.success())
+                    .unwrap_or(false);
+                if activated {
+                    return Ok(Some(FocusedWindow {
+                        id: window_id.to_string(),
+                        class: class.to_string(),
+                        title,
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    const TARGET: FocusTarget = FocusTarget {
+        classes: &["foot"],
+        title_hints: &["workopilot", "tmux"],
+    };
+
+    /// Fake sway: answers each connection with the next canned reply and
+    /// returns the (type, payload) requests it received.
+    fn fake_sway(socket: &Path, replies: Vec<Value>) -> thread::JoinHandle<Vec<(u32, String)>> {
+        let _ = std::fs::remove_file(socket);
+        let listener = UnixListener::bind(socket).unwrap();
+        thread::spawn(move || {
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut header = [0u8; 14];
+                stream.read_exact(&mut header).unwrap();
+                assert_eq!(&header[..6], I3_MAGIC);
+                let len = u32::from_ne_bytes(header[6..10].try_into().unwrap()) as usize;
+                let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+                let mut payload = vec![0u8; len];
+                stream.read_exact(&mut payload).unwrap();
+                requests.push((kind, String::from_utf8(payload).unwrap()));
+
+                let body = reply.to_string();
+                let mut message = I3_MAGIC.to_vec();
+                message.extend_from_slice(&(body.len() as u32).to_ne_bytes());
+                message.extend_from_slice(&kind.to_ne_bytes());
+                message.extend_from_slice(body.as_bytes());
+                stream.write_all(&message).unwrap();
+            }
+            requests
+        })
+    }
+
+    fn fake_hyprland(socket: &Path, replies: Vec<&'static str>) -> thread::JoinHandle<Vec<String>> {
+        let _ = std::fs::remove_file(socket);
+        let listener = UnixListener::bind(socket).unwrap();
+        thread::spawn(move || {
+            let mut requests = Vec::new();
+            for reply in replies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut command = String::new();
+                stream.read_to_string(&mut command).unwrap();
+                requests.push(command);
+                stream.write_all(reply.as_bytes()).unwrap();
+            }
+            requests
+        })
+    }
+
+    #[test]
+    fn test_sway_focuses_matching_view_by_con_id() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("sway.sock");
+        let tree = json!({
+            "id": 1, "name": "root", "nodes": [{
+                "id": 2, "name": "1", "nodes": [
+                    { "id": 5, "name": "workopilot", "app_id": "firefox", "nodes": [] },
+                    { "id": 6, "name": "~", "app_id": "foot", "nodes": [] }
+                ],
+                "floating_nodes": [
+                    { "id": 7, "name": "workopilot:opencode", "app_id": "foot", "nodes": [] }
+                ]
+            }]
+        });
+        let server = fake_sway(&socket, vec![tree, json!([{ "success": true }])]);
+
+        let status = Sway::new(&socket).focus(&TARGET).unwrap();
+        let requests = server.join().unwrap();
+
+        assert_eq!(
+            status,
+            FocusStatus {
+                backend: "sway".to_string(),
+                window: Some(FocusedWindow {
+                    id: "7".to_string(),
+                    class: "foot".to_string(),
+                    title: "workopilot:opencode".to_string(),
+                }),
+            }
+        );
+        assert_eq!(
+            requests,
+            [
+                (I3_GET_TREE, String::new()),
+                (I3_RUN_COMMAND, "[con_id=7] focus".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sway_matches_xwayland_class_and_reports_no_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("sway.sock");
+        let tree = json!({
+            "id": 1, "nodes": [{
+                "id": 3, "name": "tmux", "app_id": null,
+                "window_properties": { "class": "Foot" }
+            }]
+        });
+        let server = fake_sway(&socket, vec![tree.clone(), json!([{ "success": true }])]);
+        let status = Sway::new(&socket).focus(&TARGET).unwrap();
+        server.join().unwrap();
+        assert_eq!(status.window.unwrap().id, "3");
+
+        // Nothing matches: no focus command is sent.
+        let other = FocusTarget {
+            classes: &["kitty"],
+            title_hints: &["workopilot"],
+        };
+        let server = fake_sway(&socket, vec![tree]);
+        let status = Sway::new(&socket).focus(&other).unwrap();
+        assert_eq!(server.join().unwrap().len(), 1);
+        assert!(!status.focused());
+        assert_eq!(status.to_string(), "sway found no matching window");
+    }
+
+    #[test]
+    fn test_hyprland_dispatches_focuswindow_by_address() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join(".socket.sock");
+        let server = fake_hyprland(
+            &socket,
+            vec![
+                r#"[{"address":"0x1","class":"foot","title":"htop"},
+                    {"address":"0x55","class":"foot","title":"WorkoPilot - tmux"}]"#,
+                "ok",
+            ],
+        );
+
+        let status = Hyprland::new(&socket).focus(&TARGET).unwrap();
+
+        assert_eq!(
+            server.join().unwrap(),
+            ["j/clients", "dispatch focuswindow address:0x55"]
+        );
+        assert_eq!(status.backend, "hyprland");
+        assert_eq!(status.window.as_ref().unwrap().id, "0x55");
+        assert_eq!(
+            status.to_string(),
+            "hyprland focused window 0x55 (foot: WorkoPilot - tmux)"
+        );
+
+        let server = fake_hyprland(
+            &socket,
+            vec![
+                r#"[{"address":"0x55","class":"foot","title":"tmux"}]"#,
+                "No such window",
+            ],
+        );
+        let err = Hyprland::new(&socket).focus(&TARGET).unwrap_err();
+        server.join().unwrap();
+        assert!(err.contains("No such window"));
+    }
+
+    #[test]
+    fn test_detect_prefers_compositor_ipc_over_x11() {
+        fn id(vars: &[(&str, &str)]) -> Result<&'static str, String> {
+            detect(|key| {
+                vars.iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            })
+            .map(|p| p.id())
+        }
+
+        assert_eq!(
+            id(&[("SWAYSOCK", "/run/sway.sock"), ("DISPLAY", ":0")]),
+            Ok("sway")
+        );
+        assert_eq!(
+            id(&[("HYPRLAND_INSTANCE_SIGNATURE", "abc"), ("DISPLAY", ":0")]),
+            Ok("hyprland")
+        );
+        assert_eq!(id(&[("SWAYSOCK", ""), ("DISPLAY", ":1")]), Ok("x11"));
+        assert!(id(&[("WAYLAND_DISPLAY", "wayland-1")]).is_err());
+
+        assert_eq!(
+            Hyprland::socket_path(None, "abc"),
+            Path::new("/tmp/hypr/abc/.socket.sock")
+        );
+    }
+}
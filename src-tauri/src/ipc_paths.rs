@@ -0,0 +1,221 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+
+/// Overrides the socket path for both the app and its clients.
+pub const SOCKET_ENV_VAR: &str = "WORKOPILOT_SOCKET";
+
+const SOCKET_NAME: &str = "workopilot.sock";
+const DEV_SOCKET_NAME: &str = "workopilot-dev.sock";
+const POINTER_NAME: &str = "socket-path";
+const DEV_POINTER_NAME: &str = "socket-path-dev";
+
+fn is_dev() -> bool {
+    std::env::var("WORKOPILOT_DEV").is_ok()
+}
+
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail.
+    unsafe { libc::getuid() }
+}
+
+/// `$XDG_RUNTIME_DIR/workopilot`, or a per-user directory in /tmp when unset.
+pub fn runtime_dir() -> PathBuf {
+    resolve_runtime_dir(
+        std::env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from),
+        current_uid(),
+    )
+}
+
+fn resolve_runtime_dir(xdg_runtime_dir: Option<PathBuf>, uid: u32) -> PathBuf {
+    match xdg_runtime_dir.filter(|dir| !dir.as_os_str().is_empty()) {
+        Some(dir) => dir.join("workopilot"),
+        None => std::env::temp_dir().join(format!("workopilot-{}", uid)),
+    }
+}
+
+pub fn socket_path() -> PathBuf {
+    resolve_socket_path(
+        std::env::var_os(SOCKET_ENV_VAR).map(PathBuf::from),
+        &runtime_dir(),
+        is_dev(),
+    )
+}
+
+fn resolve_socket_path(env_override: Option<PathBuf>, runtime_dir: &Path, dev: bool) -> PathBuf {
+    match env_override.filter(|path| !path.as_os_str().is_empty()) {
+        Some(path) => path,
+        None => runtime_dir.join(if dev { DEV_SOCKET_NAME } else { SOCKET_NAME }),
+    }
+}
+
+/// Well-known file holding the socket path, next to the database, for clients
+/// that don't share the app's environment (tmux panes, cron, other shells).
+pub fn pointer_file() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("workopilot")
+        .join(if is_dev() {
+            DEV_POINTER_NAME
+        } else {
+            POINTER_NAME
+        })
+}
+
+/// Creates `dir` with mode 0700 and refuses it if another user owns it.
+pub fn prepare_private_dir(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Failed to create socket dir {}: {}", dir.display(), e))?;
+
+    let metadata = fs::metadata(dir)
+        .map_err(|e| format!("Failed to stat socket dir {}: {}", dir.display(), e))?;
+    if metadata.uid() != current_uid() {
+        return Err(format!(
+            "Socket dir {} is owned by uid {}, not {}",
+            dir.display(),
+            metadata.uid(),
+            current_uid()
+        ));
+    }
+
+    fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+        .map_err(|e| format!("Failed to restrict socket dir {}: {}", dir.display(), e))
+}
+
+/// Binds a socket that nobody else can reach before it is mode 0600,
+/// wherever `WORKOPILOT_SOCKET` puts it. It is bound in a fresh 0700
+/// directory next to `path`, restricted there, then hard-linked into place,
+/// so like bind(2) this fails if `path` already exists.
+pub fn bind_private(path: &Path) -> io::Result<UnixListener> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let staging = parent.join(format!(".bind-{}", std::process::id()));
+    let staged = staging.join("sock");
+    // Left over from a crashed process that had the same pid.
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&staging);
+
+    fs::DirBuilder::new().mode(0o700).create(&staging)?;
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o600))?;
+        fs::hard_link(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&staged);
+    let _ = fs::remove_dir(&staging);
+    bound
+}
+
+pub fn write_pointer(socket_path: &Path) {
+    let pointer = pointer_file();
+    if let Some(parent) = pointer.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    if let Err(e) = fs::write(&pointer, socket_path.to_string_lossy().as_bytes()) {
+        eprintln!(
+            "[WORKOPILOT] Failed to write socket pointer {}: {}",
+            pointer.display(),
+            e
+        );
+    }
+}
+
+/// Removes the pointer file if it still points at `socket_path`.
+pub fn remove_pointer(socket_path: &Path) {
+    let pointer = pointer_file();
+    if fs::read_to_string(&pointer).is_ok_and(|path| Path::new(path.trim()) == socket_path) {
+        let _ = fs::remove_file(pointer);
+    }
+}
+
+/// UID of the process on the other end of `stream` (SO_PEERCRED).
+pub fn peer_uid(stream: &UnixStream) -> io::Result<u32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+
+    // SAFETY: `cred` and `len` are valid for writes and sized for SO_PEERCRED.
+    let result = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+
+    if result == 0 {
+        Ok(cred.uid)
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_socket_path_resolution() {
+        let runtime = resolve_runtime_dir(Some(PathBuf::from("/run/user/1000")), 1000);
+        assert_eq!(runtime, PathBuf::from("/run/user/1000/workopilot"));
+        assert!(resolve_runtime_dir(None, 1000).ends_with("workopilot-1000"));
+
+        assert_eq!(
+            resolve_socket_path(None, &runtime, false),
+            PathBuf::from("/run/user/1000/workopilot/workopilot.sock")
+        );
+        assert_eq!(
+            resolve_socket_path(None, &runtime, true),
+            PathBuf::from("/run/user/1000/workopilot/workopilot-dev.sock")
+        );
+        assert_eq!(
+            resolve_socket_path(Some(PathBuf::from("/tmp/custom.sock")), &runtime, true),
+            PathBuf::from("/tmp/custom.sock")
+        );
+    }
+
+    #[test]
+    fn test_prepare_private_dir_sets_mode() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("workopilot");
+
+        prepare_private_dir(&dir).unwrap();
+
+        let mode = fs::metadata(&dir).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o700);
+    }
+
+    #[test]
+    fn test_bind_private_creates_owner_only_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("custom.sock");
+
+        let _listener = bind_private(&path).unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        UnixStream::connect(&path).unwrap();
+        // The staging directory is gone.
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let error = bind_private(&path).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_peer_uid_matches_own_uid() {
+        let (a, _b) = UnixStream::pair().unwrap();
+        assert_eq!(peer_uid(&a).unwrap(), current_uid());
+    }
+}
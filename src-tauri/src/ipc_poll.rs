@@ -0,0 +1,162 @@
+use std::io::{self, Read, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Wakes a thread blocked in [`wait_readable`] from another thread.
+#[derive(Clone)]
+pub struct Waker {
+    sender: Arc<UnixStream>,
+}
+
+/// The end of the wake pipe watched by the blocked thread.
+pub struct WakeReceiver {
+    receiver: UnixStream,
+}
+
+pub fn wake_pair() -> io::Result<(Waker, WakeReceiver)> {
+    let (sender, receiver) = UnixStream::pair()?;
+    sender.set_nonblocking(true)?;
+    receiver.set_nonblocking(true)?;
+    Ok((
+        Waker {
+            sender: Arc::new(sender),
+        },
+        WakeReceiver { receiver },
+    ))
+}
+
+impl Waker {
+    pub fn wake(&self) {
+        // A full pipe already has a wake-up pending, so WouldBlock is fine.
+        let _ = (&*self.sender).write(&[1]);
+    }
+}
+
+impl WakeReceiver {
+    fn drain(&self) {
+        let mut buf = [0u8; 64];
+        while matches!((&self.receiver).read(&mut buf), Ok(n) if n > 0) {}
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Readiness {
+    /// `fd` has something to read (for a listener: a pending connection).
+    Ready,
+    Woken,
+}
+
+/// Blocks until `fd` is readable or the waker fires, without a timeout.
+pub fn wait_readable(fd: &impl AsRawFd, wake: &WakeReceiver) -> io::Result<Readiness> {
+    let mut fds = [poll_fd(fd.as_raw_fd()), poll_fd(wake.receiver.as_raw_fd())];
+
+    loop {
+        // SAFETY: `fds` is a valid array of pollfd for the duration of the call.
+        let result = unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) };
+        if result >= 0 {
+            break;
+        }
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted {
+            return Err(error);
+        }
+    }
+
+    if fds[1].revents != 0 {
+        wake.drain();
+        return Ok(Readiness::Woken);
+    }
+    Ok(Readiness::Ready)
+}
+
+fn poll_fd(fd: RawFd) -> libc::pollfd {
+    libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    }
+}
+
+/// Caps how many connections are served at once.
+#[derive(Clone)]
+pub struct ConnectionSlots {
+    active: Arc<AtomicUsize>,
+    max: usize,
+}
+
+/// Frees its slot when the connection ends.
+pub struct SlotGuard {
+    active: Arc<AtomicUsize>,
+}
+
+impl ConnectionSlots {
+    pub fn new(max: usize) -> Self {
+        Self {
+            active: Arc::new(AtomicUsize::new(0)),
+            max,
+        }
+    }
+
+    pub fn max(&self) -> usize {
+        self.max
+    }
+
+    pub fn try_acquire(&self) -> Option<SlotGuard> {
+        self.active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |active| {
+                (active < self.max).then_some(active + 1)
+            })
+            .ok()
+            .map(|_| SlotGuard {
+                active: self.active.clone(),
+            })
+    }
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        self.active.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_wait_returns_when_fd_is_readable() {
+        let (_waker, wake) = wake_pair().unwrap();
+        let (mut a, b) = UnixStream::pair().unwrap();
+
+        a.write_all(b"x").unwrap();
+
+        assert_eq!(wait_readable(&b, &wake).unwrap(), Readiness::Ready);
+    }
+
+    #[test]
+    fn test_waker_interrupts_blocked_wait() {
+        let (waker, wake) = wake_pair().unwrap();
+        let (_a, b) = UnixStream::pair().unwrap();
+
+        let blocked = thread::spawn(move || wait_readable(&b, &wake).unwrap());
+        thread::sleep(Duration::from_millis(20));
+        waker.wake();
+
+        assert_eq!(blocked.join().unwrap(), Readiness::Woken);
+    }
+
+    #[test]
+    fn test_slots_cap_connections() {
+        let slots = ConnectionSlots::new(2);
+        let first = slots.try_acquire().unwrap();
+        let _second = slots.try_acquire().unwrap();
+
+        assert!(slots.try_acquire().is_none());
+        drop(first);
+        assert!(slots.try_acquire().is_some());
+    }
+}
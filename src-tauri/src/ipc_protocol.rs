@@ -0,0 +1,653 @@
+use crate::db_change::DbChangeNotification;
+use crate::event_bus::{AppEvent, SubscriptionFilter};
+use crate::execution_updates::{self, ExecutionUpdate};
+use crate::focus_provider::FocusStatus;
+use crate::sidecar::SidecarError;
+use crate::single_instance::LaunchArgs;
+use crate::terminal::TerminalAction;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::Mutex;
+
+/// Version of the request/response protocol spoken on the IPC socket.
+/// Requests carry it as `v`; replies echo it back.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub const METHODS: &[&str] = &[
+    "ping",
+    "app.activate",
+    "window.show",
+    "terminal.focus",
+    "terminal.action",
+    "executions.active",
+    "diagnostics",
+    "execution.start",
+    "execution.progress",
+    "execution.heartbeat",
+    "execution.waiting",
+    "execution.end",
+    SUBSCRIBE_METHOD,
+];
+
+/// Turns the connection into an NDJSON stream of [`EventLine`]s after the reply.
+pub const SUBSCRIBE_METHOD: &str = "events.subscribe";
+
+/// `{"v":1,"id":1,"method":"terminal.focus","params":{...}}`
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct IpcRequest {
+    pub v: Option<u32>,
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct IpcError {
+    pub code: String,
+    pub message: String,
+}
+
+impl IpcError {
+    fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl From<SidecarError> for IpcError {
+    fn from(error: SidecarError) -> Self {
+        let message = match &error {
+            SidecarError::Server { message, .. } => message.clone(),
+            other => other.to_string(),
+        };
+        Self::new(error.code(), message)
+    }
+}
+
+/// One JSON line written back for every request.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct IpcResponse {
+    pub v: u32,
+    pub id: Value,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<IpcError>,
+}
+
+impl IpcResponse {
+    fn success(id: Value, result: Value) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            id,
+            ok: true,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn failure(id: Value, error: IpcError) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            id,
+            ok: false,
+            result: None,
+            error: Some(error),
+        }
+    }
+}
+
+/// Sent (unsolicited) to a client refused because the server is at capacity.
+pub fn busy(max_connections: usize) -> IpcResponse {
+    IpcResponse::failure(
+        Value::Null,
+        IpcError::new(
+            "BUSY",
+            format!("Too many open connections (max {})", max_connections),
+        ),
+    )
+}
+
+/// Answers `events.subscribe` (request `id`) when every subscriber slot is taken.
+pub fn subscribers_busy(id: Value, max_subscribers: usize) -> IpcResponse {
+    IpcResponse::failure(
+        id,
+        IpcError::new(
+            "BUSY",
+            format!("Too many event subscribers (max {})", max_subscribers),
+        ),
+    )
+}
+
+/// One streamed event, written to subscribers after `events.subscribe`.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct EventLine<'a> {
+    pub v: u32,
+    #[serde(flatten)]
+    pub event: &'a AppEvent,
+}
+
+impl<'a> EventLine<'a> {
+    pub fn new(event: &'a AppEvent) -> Self {
+        Self {
+            v: PROTOCOL_VERSION,
+            event,
+        }
+    }
+}
+
+/// What a line read from the socket turned out to be.
+#[derive(Debug)]
+pub enum IncomingLine {
+    Request(IpcRequest),
+    /// `events.subscribe`; `reply` is sent before streaming starts.
+    Subscribe {
+        reply: IpcResponse,
+        filter: SubscriptionFilter,
+    },
+    /// Fire-and-forget change notification from the CLI; answered only when
+    /// rejected. `v` is optional here for older CLIs.
+    Notification(DbChangeNotification),
+    Invalid(IpcResponse),
+}
+
+pub fn parse_line(line: &str) -> IncomingLine {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return IncomingLine::Invalid(IpcResponse::failure(
+                Value::Null,
+                IpcError::new("PARSE_ERROR", format!("Invalid JSON: {}", e)),
+            ))
+        }
+    };
+
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+
+    if value.get("method").is_some() {
+        return match serde_json::from_value::<IpcRequest>(value) {
+            Ok(request) if request.method == SUBSCRIBE_METHOD => subscription(request),
+            Ok(request) => IncomingLine::Request(request),
+            Err(e) => IncomingLine::Invalid(IpcResponse::failure(
+                id,
+                IpcError::new("INVALID_REQUEST", e.to_string()),
+            )),
+        };
+    }
+
+    if value.get("entity_type").is_none() {
+        return IncomingLine::Invalid(IpcResponse::failure(
+            id,
+            IpcError::new(
+                "INVALID_REQUEST",
+                "Expected a request or change notification",
+            ),
+        ));
+    }
+
+    if let Some(v) = value.get("v") {
+        if v.as_u64() != Some(PROTOCOL_VERSION as u64) {
+            return IncomingLine::Invalid(IpcResponse::failure(
+                id,
+                IpcError::new(
+                    "UNSUPPORTED_VERSION",
+                    format!(
+                        "Protocol version {} not supported, expected {}",
+                        v, PROTOCOL_VERSION
+                    ),
+                ),
+            ));
+        }
+    }
+
+    match serde_json::from_value(value) {
+        Ok(notification) => IncomingLine::Notification(notification),
+        Err(e) => IncomingLine::Invalid(IpcResponse::failure(
+            id,
+            IpcError::new("INVALID_NOTIFICATION", e.to_string()),
+        )),
+    }
+}
+
+fn subscription(request: IpcRequest) -> IncomingLine {
+    let id = request.id.clone();
+    let filter = check_version(request.v).and_then(|_| match request.params {
+        Value::Null => Ok(SubscriptionFilter::default()),
+        params_value => params::<SubscriptionFilter>(params_value),
+    });
+
+    match filter {
+        Ok(filter) => IncomingLine::Subscribe {
+            reply: IpcResponse::success(id, serde_json::json!({ "subscribed": true })),
+            filter,
+        },
+        Err(error) => IncomingLine::Invalid(IpcResponse::failure(id, error)),
+    }
+}
+
+/// Counts what the socket has handled; served by the `diagnostics` method.
+#[derive(Debug, Default)]
+pub struct IpcDiagnostics {
+    requests: AtomicU64,
+    notifications: AtomicU64,
+    rejections: Mutex<Rejections>,
+}
+
+#[derive(Debug, Default)]
+struct Rejections {
+    by_code: BTreeMap<String, u64>,
+    last: Option<IpcError>,
+}
+
+impl IpcDiagnostics {
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_notification(&self) {
+        self.notifications.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rejection(&self, error: &IpcError) {
+        if let Ok(mut rejections) = self.rejections.lock() {
+            *rejections.by_code.entry(error.code.clone()).or_default() += 1;
+            rejections.last = Some(error.clone());
+        }
+    }
+
+    pub fn snapshot(&self) -> Value {
+        let (rejected, last_rejection) = match self.rejections.lock() {
+            Ok(rejections) => (rejections.by_code.clone(), rejections.last.clone()),
+            Err(_) => Default::default(),
+        };
+
+        serde_json::json!({
+            "protocol_version": PROTOCOL_VERSION,
+            "requests": self.requests.load(Ordering::Relaxed),
+            "notifications": self.notifications.load(Ordering::Relaxed),
+            "rejected_total": rejected.values().sum::<u64>(),
+            "rejected": rejected,
+            "last_rejection": last_rejection,
+        })
+    }
+}
+
+/// App operations exposed over the socket, implemented against the running
+/// app in `ipc_socket` and by a fake in tests.
+pub trait IpcActions {
+    /// A second launch handing over its arguments before exiting.
+    fn activate(&self, args: LaunchArgs) -> Result<(), SidecarError>;
+    fn show_window(&self) -> Result<(), SidecarError>;
+    fn terminal_action(
+        &self,
+        action: TerminalAction,
+        project_id: &str,
+        task_id: Option<String>,
+        subtask_id: Option<String>,
+    ) -> Result<Option<FocusStatus>, SidecarError>;
+    fn active_executions(&self) -> Result<Value, SidecarError>;
+    /// Records an execution lifecycle message and returns the updated execution.
+    fn execution_update(&self, update: ExecutionUpdate) -> Result<Value, SidecarError>;
+    fn subscribe(&self, filter: SubscriptionFilter) -> Receiver<AppEvent>;
+    fn diagnostics(&self) -> Value;
+}
+
+#[derive(Debug, Deserialize)]
+struct ActivateParams {
+    #[serde(default)]
+    args: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FocusParams {
+    project_id: String,
+    task_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActionParams {
+    action: String,
+    project_id: String,
+    task_id: Option<String>,
+    subtask_id: Option<String>,
+}
+
+pub fn dispatch(actions: &dyn IpcActions, request: IpcRequest) -> IpcResponse {
+    let id = request.id.clone();
+    match handle(actions, request) {
+        Ok(result) => IpcResponse::success(id, result),
+        Err(error) => IpcResponse::failure(id, error),
+    }
+}
+
+fn check_version(v: Option<u32>) -> Result<(), IpcError> {
+    match v {
+        Some(PROTOCOL_VERSION) => Ok(()),
+        Some(v) => Err(IpcError::new(
+            "UNSUPPORTED_VERSION",
+            format!(
+                "Protocol version {} not supported, expected {}",
+                v, PROTOCOL_VERSION
+            ),
+        )),
+        None => Err(IpcError::new(
+            "INVALID_REQUEST",
+            format!(
+                "Missing protocol version \"v\" (expected {})",
+                PROTOCOL_VERSION
+            ),
+        )),
+    }
+}
+
+fn handle(actions: &dyn IpcActions, request: IpcRequest) -> Result<Value, IpcError> {
+    check_version(request.v)?;
+
+    match request.method.as_str() {
+        "ping" => Ok(serde_json::json!({
+            "protocol_version": PROTOCOL_VERSION,
+            "app_version": env!("CARGO_PKG_VERSION"),
+            "methods": METHODS,
+        })),
+        "app.activate" => {
+            let params: ActivateParams = params(request.params)?;
+            actions.activate(LaunchArgs::parse(params.args))?;
+            Ok(Value::Null)
+        }
+        "window.show" => {
+            actions.show_window()?;
+            Ok(Value::Null)
+        }
+        "terminal.focus" => {
+            let params: FocusParams = params(request.params)?;
+            let status = actions.terminal_action(
+                TerminalAction::FocusSession,
+                &params.project_id,
+                Some(params.task_id),
+                None,
+            )?;
+            Ok(serde_json::to_value(status).unwrap_or(Value::Null))
+        }
+        "terminal.action" => {
+            let params: ActionParams = params(request.params)?;
+            let action = TerminalAction::from_str(&params.action)
+                .map_err(|e| IpcError::new("INVALID_PARAMS", e))?;
+            let status = actions.terminal_action(
+                action,
+                &params.project_id,
+                params.task_id,
+                params.subtask_id,
+            )?;
+            Ok(serde_json::to_value(status).unwrap_or(Value::Null))
+        }
+        "executions.active" => Ok(actions.active_executions()?),
+        "diagnostics" => Ok(actions.diagnostics()),
+        method if execution_updates::METHODS.contains(&method) => {
+            let update = ExecutionUpdate::parse(method, request.params)
+                .map_err(|e| IpcError::new("INVALID_PARAMS", e))?;
+            Ok(actions.execution_update(update)?)
+        }
+        other => Err(IpcError::new(
+            "METHOD_NOT_FOUND",
+            format!("Unknown method: {}", other),
+        )),
+    }
+}
+
+fn params<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, IpcError> {
+    serde_json::from_value(params).map_err(|e| IpcError::new("INVALID_PARAMS", e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::focus_provider::FocusedWindow;
+    use std::cell::RefCell;
+
+    #[derive(Default)]
+    struct FakeActions {
+        calls: RefCell<Vec<String>>,
+    }
+
+    impl IpcActions for FakeActions {
+        fn activate(&self, args: LaunchArgs) -> Result<(), SidecarError> {
+            self.calls
+                .borrow_mut()
+                .push(format!("activate {:?}", args.task_id));
+            Ok(())
+        }
+
+        fn show_window(&self) -> Result<(), SidecarError> {
+            self.calls.borrow_mut().push("show".to_string());
+            Ok(())
+        }
+
+        fn terminal_action(
+            &self,
+            action: TerminalAction,
+            project_id: &str,
+            task_id: Option<String>,
+            _subtask_id: Option<String>,
+        ) -> Result<Option<FocusStatus>, SidecarError> {
+            if project_id == "missing" {
+                return Err(SidecarError::NotFound("Project not found".to_string()));
+            }
+            self.calls
+                .borrow_mut()
+                .push(format!("{:?} {} {:?}", action, project_id, task_id));
+            Ok((action == TerminalAction::FocusSession).then(|| FocusStatus {
+                backend: "sway".to_string(),
+                window: Some(FocusedWindow {
+                    id: "7".to_string(),
+                    class: "foot".to_string(),
+                    title: "workopilot".to_string(),
+                }),
+            }))
+        }
+
+        fn active_executions(&self) -> Result<Value, SidecarError> {
+            Ok(serde_json::json!([{ "id": "exec-1" }]))
+        }
+
+        fn execution_update(&self, update: ExecutionUpdate) -> Result<Value, SidecarError> {
+            self.calls.borrow_mut().push(format!("{:?}", update));
+            Ok(serde_json::json!({ "task_id": update.task_id() }))
+        }
+
+        fn subscribe(&self, _filter: SubscriptionFilter) -> Receiver<AppEvent> {
+            std::sync::mpsc::channel().1
+        }
+
+        fn diagnostics(&self) -> Value {
+            serde_json::json!({ "requests": 1 })
+        }
+    }
+
+    fn request(line: &str) -> IpcRequest {
+        match parse_line(line) {
+            IncomingLine::Request(request) => request,
+            other => panic!("expected request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_line_keeps_legacy_notifications() {
+        let line = r#"{"entity_type":"task","entity_id":"t1","operation":"update"}"#;
+        assert!(matches!(parse_line(line), IncomingLine::Notification(_)));
+
+        match parse_line("not json") {
+            IncomingLine::Invalid(response) => {
+                assert_eq!(response.error.unwrap().code, "PARSE_ERROR")
+            }
+            other => panic!("expected invalid, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_line_rejects_unknown_notifications() {
+        let error = |line: &str| match parse_line(line) {
+            IncomingLine::Invalid(response) => response.error.unwrap(),
+            other => panic!("expected invalid, got {:?}", other),
+        };
+
+        let rejected =
+            error(r#"{"v":1,"entity_type":"tsak","entity_id":"t1","operation":"update"}"#);
+        assert_eq!(rejected.code, "INVALID_NOTIFICATION");
+        assert!(rejected.message.contains("tsak"));
+        assert_eq!(
+            error(r#"{"v":1,"entity_type":"task","entity_id":"t1","operation":"upsert"}"#).code,
+            "INVALID_NOTIFICATION"
+        );
+        assert_eq!(
+            error(r#"{"v":2,"entity_type":"task","entity_id":"t1","operation":"update"}"#).code,
+            "UNSUPPORTED_VERSION"
+        );
+        assert_eq!(error(r#"{"id":4,"hello":true}"#).code, "INVALID_REQUEST");
+
+        let line = r#"{"v":1,"entity_type":"category","entity_id":"c1","operation":"create","project_id":"p1"}"#;
+        match parse_line(line) {
+            IncomingLine::Notification(change) => {
+                assert_eq!(change.project_id.as_deref(), Some("p1"))
+            }
+            other => panic!("expected notification, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_diagnostics_count_rejections() {
+        let diagnostics = IpcDiagnostics::default();
+        diagnostics.record_request();
+        diagnostics.record_notification();
+        diagnostics.record_rejection(&IpcError::new("INVALID_NOTIFICATION", "tsak"));
+        diagnostics.record_rejection(&IpcError::new("INVALID_NOTIFICATION", "upsert"));
+
+        let snapshot = diagnostics.snapshot();
+        assert_eq!(snapshot["requests"], 1);
+        assert_eq!(snapshot["rejected_total"], 2);
+        assert_eq!(snapshot["rejected"]["INVALID_NOTIFICATION"], 2);
+        assert_eq!(snapshot["last_rejection"]["message"], "upsert");
+    }
+
+    #[test]
+    fn test_parse_subscription() {
+        let line = r#"{"v":1,"id":3,"method":"events.subscribe","params":{"entity_types":["task"],"project_id":"p1"}}"#;
+        match parse_line(line) {
+            IncomingLine::Subscribe { reply, filter } => {
+                assert!(reply.ok);
+                assert_eq!(filter.entity_types, vec!["task".to_string()]);
+                assert_eq!(filter.project_id.as_deref(), Some("p1"));
+            }
+            other => panic!("expected subscription, got {:?}", other),
+        }
+
+        let busy = subscribers_busy(serde_json::json!(3), 32);
+        assert_eq!(busy.id, 3);
+        assert_eq!(busy.error.unwrap().code, "BUSY");
+
+        let event =
+            AppEvent::new("db-changed", &serde_json::json!({ "entity_id": "t1" })).entity("task");
+        let line = serde_json::to_value(EventLine::new(&event)).unwrap();
+        assert_eq!(line["v"], 1);
+        assert_eq!(line["event"], "db-changed");
+        assert_eq!(line["payload"]["entity_id"], "t1");
+
+        assert!(matches!(
+            parse_line(r#"{"id":3,"method":"events.subscribe"}"#),
+            IncomingLine::Invalid(_)
+        ));
+    }
+
+    #[test]
+    fn test_dispatch_runs_actions_and_echoes_id() {
+        let actions = FakeActions::default();
+
+        let response = dispatch(
+            &actions,
+            request(
+                r#"{"v":1,"id":7,"method":"terminal.focus","params":{"project_id":"p1","task_id":"t1"}}"#,
+            ),
+        );
+        assert!(response.ok);
+        assert_eq!(response.id, serde_json::json!(7));
+        let result = response.result.unwrap();
+        assert_eq!(result["backend"], "sway");
+        assert_eq!(result["window"]["id"], "7");
+
+        let response = dispatch(
+            &actions,
+            request(r#"{"v":1,"id":"a","method":"executions.active"}"#),
+        );
+        assert_eq!(response.result.unwrap()[0]["id"], "exec-1");
+
+        dispatch(
+            &actions,
+            request(r#"{"v":1,"id":8,"method":"app.activate","params":{"args":["--task","t2"]}}"#),
+        );
+
+        assert_eq!(
+            actions.calls.borrow().as_slice(),
+            ["FocusSession p1 Some(\"t1\")", "activate Some(\"t2\")"]
+        );
+    }
+
+    #[test]
+    fn test_dispatch_reports_structured_errors() {
+        let actions = FakeActions::default();
+        let code = |line: &str| dispatch(&actions, request(line)).error.unwrap().code;
+
+        assert_eq!(
+            code(r#"{"v":2,"id":1,"method":"ping"}"#),
+            "UNSUPPORTED_VERSION"
+        );
+        assert_eq!(code(r#"{"id":1,"method":"ping"}"#), "INVALID_REQUEST");
+        assert_eq!(
+            code(r#"{"v":1,"id":1,"method":"window.close"}"#),
+            "METHOD_NOT_FOUND"
+        );
+        assert_eq!(
+            code(
+                r#"{"v":1,"id":1,"method":"terminal.action","params":{"action":"dance","project_id":"p1"}}"#
+            ),
+            "INVALID_PARAMS"
+        );
+        assert_eq!(
+            code(
+                r#"{"v":1,"id":1,"method":"terminal.action","params":{"action":"launch_project","project_id":"missing"}}"#
+            ),
+            "NOT_FOUND"
+        );
+        assert_eq!(
+            code(r#"{"v":1,"id":1,"method":"execution.end","params":{"status":"completed"}}"#),
+            "INVALID_PARAMS"
+        );
+        assert_eq!(
+            code(r#"{"v":1,"id":1,"method":"execution.pause","params":{"task_id":"t1"}}"#),
+            "METHOD_NOT_FOUND"
+        );
+        assert!(actions.calls.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_dispatch_execution_updates() {
+        let actions = FakeActions::default();
+
+        let response = dispatch(
+            &actions,
+            request(
+                r#"{"v":1,"id":1,"method":"execution.progress","params":{"task_id":"t1","current_step":2}}"#,
+            ),
+        );
+
+        assert!(response.ok);
+        assert_eq!(response.result.unwrap()["task_id"], "t1");
+        assert!(actions.calls.borrow()[0].starts_with("Progress"));
+    }
+}
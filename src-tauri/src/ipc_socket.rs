@@ -1,108 +1,181 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader};
+use crate::app_events;
+use crate::change_coalescer::{self, ChangeQueue};
+use crate::db_change::DbChangeNotification;
+use crate::event_bus::{AppEvent, SubscriptionFilter};
+use crate::execution_updates::ExecutionUpdate;
+use crate::focus_provider::FocusStatus;
+use crate::ipc_paths;
+use crate::ipc_poll::{self, ConnectionSlots, Readiness, SlotGuard, WakeReceiver, Waker};
+use crate::ipc_protocol::{self, EventLine, IncomingLine, IpcActions, IpcDiagnostics};
+use crate::sidecar::SidecarError;
+use crate::single_instance::LaunchArgs;
+use crate::terminal::{self, SessionLauncher, TerminalAction};
+use crate::AppState;
+use serde::Serialize;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
 use std::os::unix::net::{UnixListener, UnixStream};
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
 use std::thread;
-use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
-
-const SOCKET_PATH: &str = "/tmp/workopilot.sock";
-const DEBOUNCE_MS: u64 = 300;
-
-#[derive(Debug, Deserialize, Clone)]
-pub struct DbChangeNotification {
-    pub entity_type: String,
-    pub entity_id: String,
-    pub operation: String,
-    #[serde(default)]
-    pub project_id: Option<String>,
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+const MAX_CONNECTIONS: usize = 32;
+/// Subscribers stay connected, so they are counted apart from `MAX_CONNECTIONS`.
+const MAX_SUBSCRIBERS: usize = 32;
+/// Idle request connections are closed after this long without a line.
+const CONNECTION_READ_TIMEOUT: Duration = Duration::from_secs(30);
+const CONNECTION_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const SUBSCRIBER_POLL: Duration = Duration::from_secs(1);
+
+/// Writes `notification` to the event journal and stamps it with its sequence number.
+pub fn journal(app_handle: &AppHandle, notification: &mut DbChangeNotification) {
+    let state = app_handle.state::<AppState>();
+    let result = match state.db.lock() {
+        Ok(db) => db
+            .append_journal_entry(
+                notification.entity_type.as_str(),
+                &notification.entity_id,
+                notification.operation.as_str(),
+                notification.project_id.as_deref(),
+            )
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    match result {
+        Ok(seq) => notification.seq = Some(seq),
+        Err(e) => eprintln!("[WORKOPILOT] Failed to journal change notification: {}", e),
+    }
 }
 
-#[derive(Debug, Serialize, Clone)]
-pub struct DbChangedPayload {
-    pub entity_type: String,
-    pub entity_id: String,
-    pub operation: String,
-    pub project_id: Option<String>,
-}
+type DebounceState = Arc<ChangeQueue>;
 
 pub struct IpcSocketServer {
-    shutdown_flag: Arc<AtomicBool>,
+    waker: Waker,
     listener_thread: Option<thread::JoinHandle<()>>,
+    emitter_thread: Option<thread::JoinHandle<()>>,
+    debounce_state: DebounceState,
+    socket_path: PathBuf,
+    app_handle: AppHandle,
 }
 
 impl IpcSocketServer {
-    pub fn new(app_handle: AppHandle) -> Result<Self, String> {
-        let socket_path = Path::new(SOCKET_PATH);
-        if socket_path.exists() {
-            std::fs::remove_file(socket_path)
-                .map_err(|e| format!("Failed to remove existing socket: {}", e))?;
-        }
-
-        let listener = UnixListener::bind(SOCKET_PATH)
-            .map_err(|e| format!("Failed to bind socket at {}: {}", SOCKET_PATH, e))?;
-
+    /// Serves `listener`, bound at `socket_path` by [`crate::single_instance::claim`].
+    pub fn new(
+        app_handle: AppHandle,
+        socket_path: PathBuf,
+        listener: UnixListener,
+    ) -> Result<Self, String> {
         listener
             .set_nonblocking(true)
             .map_err(|e| format!("Failed to set non-blocking: {}", e))?;
 
-        let shutdown_flag = Arc::new(AtomicBool::new(false));
-        let shutdown_flag_clone = shutdown_flag.clone();
+        let (waker, wake_receiver) =
+            ipc_poll::wake_pair().map_err(|e| format!("Failed to create wake pipe: {}", e))?;
 
-        let debounce_state: Arc<Mutex<HashMap<String, (Instant, DbChangeNotification)>>> =
-            Arc::new(Mutex::new(HashMap::new()));
+        let window = Self::coalescing_window(&app_handle);
+        let debounce_state: DebounceState = Arc::new(ChangeQueue::new(window));
 
+        let emitter_thread = {
+            let app_handle = app_handle.clone();
+            let debounce_state = debounce_state.clone();
+            thread::spawn(move || Self::run_debounce_emitter(app_handle, debounce_state))
+        };
+
+        let debounce_state_clone = debounce_state.clone();
+        let listener_app_handle = app_handle.clone();
+        let diagnostics = Arc::new(IpcDiagnostics::default());
         let listener_thread = thread::spawn(move || {
-            Self::run_listener(listener, app_handle, shutdown_flag_clone, debounce_state);
+            Self::run_listener(
+                listener,
+                listener_app_handle,
+                wake_receiver,
+                debounce_state_clone,
+                diagnostics,
+            );
         });
 
-        eprintln!("[WORKOPILOT] IPC socket server started at {}", SOCKET_PATH);
+        ipc_paths::write_pointer(&socket_path);
+
+        eprintln!(
+            "[WORKOPILOT] IPC socket server started at {}",
+            socket_path.display()
+        );
 
         Ok(Self {
-            shutdown_flag,
+            waker,
             listener_thread: Some(listener_thread),
+            emitter_thread: Some(emitter_thread),
+            debounce_state,
+            socket_path,
+            app_handle,
         })
     }
 
+    /// Queues a change that arrived through another channel (e.g. the sidecar)
+    /// so it shares the socket's debounce window.
+    pub fn enqueue(&self, notification: DbChangeNotification) {
+        Self::debounce(&self.app_handle, &self.debounce_state, notification);
+    }
+
+    fn debounce(
+        app_handle: &AppHandle,
+        debounce_state: &DebounceState,
+        mut notification: DbChangeNotification,
+    ) {
+        journal(app_handle, &mut notification);
+        debounce_state.push(notification);
+    }
+
+    fn coalescing_window(app_handle: &AppHandle) -> Duration {
+        let state = app_handle.state::<AppState>();
+        let setting = match state.db.lock() {
+            Ok(db) => db.get_setting(change_coalescer::WINDOW_SETTING).ok().flatten(),
+            Err(_) => None,
+        };
+        change_coalescer::window_from_setting(setting.as_deref())
+    }
+
+    /// Sleeps in poll(2) until a client connects or `shutdown` wakes it.
     fn run_listener(
         listener: UnixListener,
         app_handle: AppHandle,
-        shutdown_flag: Arc<AtomicBool>,
-        debounce_state: Arc<Mutex<HashMap<String, (Instant, DbChangeNotification)>>>,
+        wake_receiver: WakeReceiver,
+        debounce_state: DebounceState,
+        diagnostics: Arc<IpcDiagnostics>,
     ) {
-        let app_handle_debounce = app_handle.clone();
-        let debounce_state_emitter = debounce_state.clone();
-        let shutdown_flag_emitter = shutdown_flag.clone();
-
-        thread::spawn(move || {
-            Self::run_debounce_emitter(
-                app_handle_debounce,
-                debounce_state_emitter,
-                shutdown_flag_emitter,
-            );
-        });
+        let slots = ConnectionSlots::new(MAX_CONNECTIONS);
+        let subscriber_slots = ConnectionSlots::new(MAX_SUBSCRIBERS);
 
         loop {
-            if shutdown_flag.load(Ordering::Relaxed) {
-                break;
+            match ipc_poll::wait_readable(&listener, &wake_receiver) {
+                Ok(Readiness::Ready) => {}
+                Ok(Readiness::Woken) => break,
+                Err(e) => {
+                    eprintln!("[WORKOPILOT] Socket poll error: {}", e);
+                    break;
+                }
             }
 
-            match listener.accept() {
-                Ok((stream, _)) => {
-                    let debounce_state = debounce_state.clone();
-                    thread::spawn(move || {
-                        Self::handle_connection(stream, debounce_state);
-                    });
-                }
-                Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                    thread::sleep(Duration::from_millis(100));
-                }
-                Err(e) => {
-                    eprintln!("[WORKOPILOT] Socket accept error: {}", e);
-                    thread::sleep(Duration::from_millis(100));
+            // The listener is non-blocking: take every pending connection.
+            loop {
+                match listener.accept() {
+                    Ok((stream, _)) => Self::spawn_connection(
+                        stream,
+                        &slots,
+                        &subscriber_slots,
+                        &app_handle,
+                        &debounce_state,
+                        &diagnostics,
+                    ),
+                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                    Err(e) => {
+                        eprintln!("[WORKOPILOT] Socket accept error: {}", e);
+                        break;
+                    }
                 }
             }
         }
@@ -110,10 +183,88 @@ impl IpcSocketServer {
         eprintln!("[WORKOPILOT] IPC socket listener stopped");
     }
 
+    fn spawn_connection(
+        mut stream: UnixStream,
+        slots: &ConnectionSlots,
+        subscriber_slots: &ConnectionSlots,
+        app_handle: &AppHandle,
+        debounce_state: &DebounceState,
+        diagnostics: &Arc<IpcDiagnostics>,
+    ) {
+        if !Self::is_same_user(&stream) {
+            return;
+        }
+
+        let _ = stream.set_nonblocking(false);
+        let _ = stream.set_read_timeout(Some(CONNECTION_READ_TIMEOUT));
+        let _ = stream.set_write_timeout(Some(CONNECTION_WRITE_TIMEOUT));
+
+        let Some(slot) = slots.try_acquire() else {
+            eprintln!(
+                "[WORKOPILOT] Rejected socket connection: {} connections already open",
+                slots.max()
+            );
+            let busy = ipc_protocol::busy(slots.max());
+            if let Some(error) = &busy.error {
+                diagnostics.record_rejection(error);
+            }
+            let _ = Self::write_line(&mut stream, &busy);
+            return;
+        };
+
+        let subscriber_slots = subscriber_slots.clone();
+        let debounce_state = debounce_state.clone();
+        let app_handle = app_handle.clone();
+        let diagnostics = diagnostics.clone();
+        thread::spawn(move || {
+            Self::handle_connection(
+                stream,
+                slot,
+                subscriber_slots,
+                app_handle,
+                debounce_state,
+                diagnostics,
+            );
+        });
+    }
+
+    /// Rejects connections from other local users (SO_PEERCRED).
+    fn is_same_user(stream: &UnixStream) -> bool {
+        match ipc_paths::peer_uid(stream) {
+            Ok(uid) if uid == ipc_paths::current_uid() => true,
+            Ok(uid) => {
+                eprintln!("[WORKOPILOT] Rejected socket connection from uid {}", uid);
+                false
+            }
+            Err(e) => {
+                eprintln!("[WORKOPILOT] Failed to read socket peer credentials: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Reads one JSON message per line: change notifications are debounced as
+    /// before, requests are dispatched and answered with one JSON line each.
+    /// A subscription trades `slot` for one of `subscriber_slots`.
     fn handle_connection(
         stream: UnixStream,
-        debounce_state: Arc<Mutex<HashMap<String, (Instant, DbChangeNotification)>>>,
+        slot: SlotGuard,
+        subscriber_slots: ConnectionSlots,
+        app_handle: AppHandle,
+        debounce_state: DebounceState,
+        diagnostics: Arc<IpcDiagnostics>,
     ) {
+        let actions = AppActions {
+            app_handle: app_handle.clone(),
+            diagnostics: diagnostics.clone(),
+        };
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(e) => {
+                eprintln!("[WORKOPILOT] Failed to clone socket stream: {}", e);
+                return;
+            }
+        };
         let reader = BufReader::new(stream);
 
         for line in reader.lines() {
@@ -123,27 +274,69 @@ impl IpcSocketServer {
                         continue;
                     }
 
-                    match serde_json::from_str::<DbChangeNotification>(&data) {
-                        Ok(notification) => {
-                            let debounce_key =
-                                format!("{}:{}", notification.entity_type, notification.entity_id);
-
-                            if let Ok(mut state) = debounce_state.lock() {
-                                state.insert(debounce_key, (Instant::now(), notification.clone()));
-                            }
-
+                    let response = match ipc_protocol::parse_line(&data) {
+                        IncomingLine::Notification(notification) => {
                             eprintln!(
                                 "[WORKOPILOT] Received notification: {} {} {}",
                                 notification.operation,
                                 notification.entity_type,
                                 notification.entity_id
                             );
+
+                            diagnostics.record_notification();
+                            Self::debounce(&app_handle, &debounce_state, notification);
+                            continue;
                         }
-                        Err(e) => {
-                            eprintln!("[WORKOPILOT] Failed to parse notification: {}", e);
+                        IncomingLine::Request(request) => {
+                            eprintln!("[WORKOPILOT] Socket request: {}", request.method);
+                            diagnostics.record_request();
+                            ipc_protocol::dispatch(&actions, request)
                         }
+                        IncomingLine::Subscribe { reply, filter } => {
+                            eprintln!("[WORKOPILOT] Socket subscription: {:?}", filter);
+                            diagnostics.record_request();
+                            let Some(_subscriber_slot) = subscriber_slots.try_acquire() else {
+                                let busy = ipc_protocol::subscribers_busy(
+                                    reply.id,
+                                    subscriber_slots.max(),
+                                );
+                                if let Some(error) = &busy.error {
+                                    diagnostics.record_rejection(error);
+                                }
+                                let _ = Self::write_line(&mut writer, &busy);
+                                return;
+                            };
+                            drop(slot);
+                            if Self::write_line(&mut writer, &reply).is_ok() {
+                                Self::stream_events(&mut writer, actions.subscribe(filter));
+                            }
+                            return;
+                        }
+                        IncomingLine::Invalid(response) => response,
+                    };
+
+                    if let Some(error) = &response.error {
+                        eprintln!(
+                            "[WORKOPILOT] Rejected socket message ({}): {}",
+                            error.code, error.message
+                        );
+                        diagnostics.record_rejection(error);
+                    }
+
+                    if let Err(e) = Self::write_line(&mut writer, &response) {
+                        eprintln!("[WORKOPILOT] Failed to write socket response: {}", e);
+                        break;
                     }
                 }
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    eprintln!("[WORKOPILOT] Closing idle socket connection");
+                    break;
+                }
                 Err(e) => {
                     eprintln!("[WORKOPILOT] Error reading from socket: {}", e);
                     break;
@@ -152,55 +345,81 @@ impl IpcSocketServer {
         }
     }
 
-    fn run_debounce_emitter(
-        app_handle: AppHandle,
-        debounce_state: Arc<Mutex<HashMap<String, (Instant, DbChangeNotification)>>>,
-        shutdown_flag: Arc<AtomicBool>,
-    ) {
-        let debounce_duration = Duration::from_millis(DEBOUNCE_MS);
-
+    /// Writes matching events until the subscriber disconnects.
+    fn stream_events(writer: &mut UnixStream, events: Receiver<AppEvent>) {
         loop {
-            if shutdown_flag.load(Ordering::Relaxed) {
-                break;
+            match events.recv_timeout(SUBSCRIBER_POLL) {
+                Ok(event) => {
+                    if Self::write_line(writer, &EventLine::new(&event)).is_err() {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if Self::peer_closed(writer) {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
             }
+        }
 
-            thread::sleep(Duration::from_millis(50));
-
-            let mut notifications_to_emit: Vec<DbChangeNotification> = Vec::new();
+        eprintln!("[WORKOPILOT] Socket subscriber disconnected");
+    }
 
-            if let Ok(mut state) = debounce_state.lock() {
-                let now = Instant::now();
-                let mut keys_to_remove: Vec<String> = Vec::new();
+    /// Subscribers never write after subscribing, so a read of 0 bytes means
+    /// they hung up. Anything they do send is discarded.
+    fn peer_closed(stream: &mut UnixStream) -> bool {
+        if stream.set_nonblocking(true).is_err() {
+            return true;
+        }
+        let mut buf = [0u8; 256];
+        let closed = match stream.read(&mut buf) {
+            Ok(0) => true,
+            Ok(_) => false,
+            Err(e) => e.kind() != std::io::ErrorKind::WouldBlock,
+        };
+        let _ = stream.set_nonblocking(false);
+        closed
+    }
 
-                for (key, (timestamp, notification)) in state.iter() {
-                    if now.duration_since(*timestamp) >= debounce_duration {
-                        notifications_to_emit.push(notification.clone());
-                        keys_to_remove.push(key.clone());
-                    }
-                }
+    fn write_line<T: Serialize>(writer: &mut UnixStream, message: &T) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes())?;
+        writer.flush()
+    }
 
-                for key in keys_to_remove {
-                    state.remove(&key);
-                }
+    fn run_debounce_emitter(app_handle: AppHandle, debounce_state: DebounceState) {
+        while let Some(changes) = debounce_state.next_batch() {
+            for notification in &changes {
+                let payload = notification.payload();
+
+                app_events::emit(
+                    &app_handle,
+                    AppEvent::new("db-changed", &payload)
+                        .entity(notification.entity_type.as_str())
+                        .project(notification.project_id.clone()),
+                );
             }
 
-            for notification in notifications_to_emit {
-                let payload = DbChangedPayload {
-                    entity_type: notification.entity_type.clone(),
-                    entity_id: notification.entity_id.clone(),
-                    operation: notification.operation.clone(),
-                    project_id: notification.project_id.clone(),
-                };
-
-                if let Err(e) = app_handle.emit("db-changed", payload) {
-                    eprintln!("[WORKOPILOT] Failed to emit db-changed event: {}", e);
-                } else {
-                    eprintln!(
-                        "[WORKOPILOT] Emitted db-changed: {} {} {}",
-                        notification.operation, notification.entity_type, notification.entity_id
-                    );
+            let batch = change_coalescer::batch_payload(&changes);
+            // Batches spanning several projects carry no project; subscribers
+            // filtering by project still get the per-entity events.
+            let project_id = match batch.groups.as_slice() {
+                [first, rest @ ..] if rest.iter().all(|g| g.project_id == first.project_id) => {
+                    first.project_id.clone()
                 }
-            }
+                _ => None,
+            };
+            app_events::emit(
+                &app_handle,
+                AppEvent::new("db-changed-batch", &batch).project(project_id),
+            );
+            eprintln!(
+                "[WORKOPILOT] Emitted db-changed-batch: {} changes in {} groups",
+                batch.count,
+                batch.groups.len()
+            );
         }
 
         eprintln!("[WORKOPILOT] Debounce emitter stopped");
@@ -209,15 +428,19 @@ impl IpcSocketServer {
     pub fn shutdown(&mut self) {
         eprintln!("[WORKOPILOT] Shutting down IPC socket server...");
 
-        self.shutdown_flag.store(true, Ordering::Relaxed);
+        self.waker.wake();
+        self.debounce_state.close();
 
         if let Some(handle) = self.listener_thread.take() {
             let _ = handle.join();
         }
+        if let Some(handle) = self.emitter_thread.take() {
+            let _ = handle.join();
+        }
 
-        let socket_path = Path::new(SOCKET_PATH);
-        if socket_path.exists() {
-            if let Err(e) = std::fs::remove_file(socket_path) {
+        ipc_paths::remove_pointer(&self.socket_path);
+        if self.socket_path.exists() {
+            if let Err(e) = std::fs::remove_file(&self.socket_path) {
                 eprintln!("[WORKOPILOT] Failed to remove socket file: {}", e);
             }
         }
@@ -226,6 +449,89 @@ impl IpcSocketServer {
     }
 }
 
+/// Socket requests served by the running app.
+struct AppActions {
+    app_handle: AppHandle,
+    diagnostics: Arc<IpcDiagnostics>,
+}
+
+impl IpcActions for AppActions {
+    fn activate(&self, args: LaunchArgs) -> Result<(), SidecarError> {
+        eprintln!("[WORKOPILOT] Activated by second instance: {:?}", args);
+        crate::window::show(&self.app_handle);
+
+        if let Err(e) = self.app_handle.emit("second-instance", &args) {
+            eprintln!("[WORKOPILOT] Failed to emit second-instance event: {}", e);
+        }
+
+        match (&args.action, &args.project_id) {
+            (Some(action), Some(project_id)) => {
+                let action = TerminalAction::from_str(action).map_err(SidecarError::Validation)?;
+                self.terminal_action(action, project_id, args.task_id, args.subtask_id)
+                    .map(|_| ())
+            }
+            (Some(_), None) => Err(SidecarError::Validation(
+                "--action requires --project".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn show_window(&self) -> Result<(), SidecarError> {
+        crate::window::show(&self.app_handle);
+        Ok(())
+    }
+
+    fn terminal_action(
+        &self,
+        action: TerminalAction,
+        project_id: &str,
+        task_id: Option<String>,
+        subtask_id: Option<String>,
+    ) -> Result<Option<FocusStatus>, SidecarError> {
+        let state = self.app_handle.state::<AppState>();
+        let trpc = state.sidecar.sidecar.trpc().map_err(SidecarError::Transport)?;
+        let launcher = SessionLauncher::new(&self.app_handle);
+        terminal::run_terminal_action(&trpc, &launcher, action, project_id, task_id, subtask_id)
+    }
+
+    fn active_executions(&self) -> Result<Value, SidecarError> {
+        let state = self.app_handle.state::<AppState>();
+        let trpc = state.sidecar.sidecar.trpc().map_err(SidecarError::Transport)?;
+        trpc.executions().list_all_active()
+    }
+
+    fn execution_update(&self, update: ExecutionUpdate) -> Result<Value, SidecarError> {
+        let state = self.app_handle.state::<AppState>();
+        let (execution, project_id) = {
+            let db = state
+                .db
+                .lock()
+                .map_err(|e| SidecarError::Failed(format!("Database lock poisoned: {}", e)))?;
+            let execution = update.apply(&db)?;
+            let project_id = db.get_task_project_id(&execution.task_id).ok().flatten();
+            (execution, project_id)
+        };
+
+        app_events::emit(
+            &self.app_handle,
+            AppEvent::new("execution-changed", &execution)
+                .entity("execution")
+                .project(project_id),
+        );
+
+        serde_json::to_value(&execution).map_err(|e| SidecarError::Failed(e.to_string()))
+    }
+
+    fn subscribe(&self, filter: SubscriptionFilter) -> Receiver<AppEvent> {
+        self.app_handle.state::<AppState>().events.subscribe(filter)
+    }
+
+    fn diagnostics(&self) -> Value {
+        self.diagnostics.snapshot()
+    }
+}
+
 impl Drop for IpcSocketServer {
     fn drop(&mut self) {
         self.shutdown();
@@ -1,37 +1,84 @@
 mod activity_logger;
+mod app_events;
+mod change_coalescer;
 mod commands;
 mod database;
+mod db_change;
+mod db_watcher;
+mod event_bus;
+mod execution_updates;
+mod focus_provider;
+mod ipc_paths;
+mod ipc_poll;
+mod ipc_protocol;
 mod ipc_socket;
+mod multiplexer;
+mod readiness;
 mod settings;
+mod shell_script;
+mod single_instance;
 mod sidecar;
 mod sidecar_commands;
+mod sidecar_discovery;
+mod sidecar_error;
+mod sidecar_events;
+mod sidecar_handshake;
+mod sidecar_logs;
+mod sidecar_supervisor;
+mod sidecar_transport;
 mod terminal;
+mod terminal_emulator;
 mod token_tracker;
 mod tray;
+mod trpc_client;
 mod window;
 
 use activity_logger::ActivityLogger;
 use database::Database;
+use event_bus::EventBus;
 use ipc_socket::IpcSocketServer;
 use sidecar::SidecarState;
-use std::sync::Mutex;
+use sidecar_supervisor::SidecarSupervisor;
+use std::sync::{Arc, Mutex};
 use tauri::Manager;
 
 pub struct AppState {
     pub db: Mutex<Database>,
     pub activity_logger: ActivityLogger,
     pub ipc_socket: Mutex<Option<IpcSocketServer>>,
+    pub db_watcher: Mutex<Option<db_watcher::WatcherHandle>>,
     pub sidecar: SidecarState,
+    pub events: EventBus,
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Only affects our own window: terminals are focused through the
+    // compositor's IPC (focus_provider) when running under Sway or Hyprland.
     #[cfg(target_os = "linux")]
     {
         std::env::set_var("GDK_BACKEND", "x11");
         std::env::set_var("WEBKIT_DISABLE_COMPOSITING_MODE", "1");
     }
 
+    // Children (tmux panes, the CLI they run) find the socket through this.
+    // Exported here, before any thread exists, since set_var is unsound later.
+    let socket_path = ipc_paths::socket_path();
+    std::env::set_var(ipc_paths::SOCKET_ENV_VAR, &socket_path);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let listener = match single_instance::claim(&socket_path, &args) {
+        Ok(single_instance::Claim::Forwarded) => {
+            eprintln!("[WORKOPILOT] Another instance is running; forwarded arguments and exiting");
+            return;
+        }
+        Ok(single_instance::Claim::Bound(listener)) => Some(listener),
+        Err(e) => {
+            eprintln!("[WORKOPILOT] Single-instance check failed: {}", e);
+            None
+        }
+    };
+
     let db = Database::new().expect("Failed to initialize database");
     let activity_logger = ActivityLogger::new();
 
@@ -43,11 +90,17 @@ pub fn run() {
             db: Mutex::new(db),
             activity_logger,
             ipc_socket: Mutex::new(None),
+            db_watcher: Mutex::new(None),
             sidecar: SidecarState::new(),
+            events: EventBus::new(),
         })
         .invoke_handler(tauri::generate_handler![
             // Terminal actions (centralized)
             terminal::terminal_action,
+            terminal::get_terminal_emulators,
+            terminal::set_terminal_emulator,
+            terminal::get_terminal_multiplexers,
+            terminal::set_terminal_multiplexer,
             // File system commands
             commands::open_env_file,
             commands::detect_project_structure,
@@ -56,6 +109,8 @@ pub fn run() {
             commands::launch_quickfix_background,
             // User sessions
             commands::get_user_sessions,
+            // Event journal
+            commands::get_events_since,
             // Skills sync
             commands::sync_skills,
             // Settings
@@ -67,9 +122,12 @@ pub fn run() {
             sidecar_commands::sidecar_call,
             sidecar_commands::sidecar_status,
             sidecar_commands::sidecar_restart,
+            sidecar_commands::sidecar_logs,
+            sidecar_commands::sidecar_capabilities,
+            sidecar_commands::sidecar_discovery,
             sidecar_commands::get_trpc_url,
         ])
-        .setup(|app| {
+        .setup(move |app| {
             settings::register_initial_shortcut(app.handle())?;
             tray::setup_tray(app)?;
             
@@ -86,20 +144,46 @@ pub fn run() {
                 Err(e) => eprintln!("[WORKOPILOT] Failed to sync skills on startup: {}", e),
             }
             
-            match IpcSocketServer::new(app.handle().clone()) {
-                Ok(server) => {
-                    if let Ok(mut ipc_socket) = state.ipc_socket.lock() {
-                        *ipc_socket = Some(server);
+            if let Some(listener) = listener {
+                match IpcSocketServer::new(app.handle().clone(), socket_path, listener) {
+                    Ok(server) => {
+                        if let Ok(mut ipc_socket) = state.ipc_socket.lock() {
+                            *ipc_socket = Some(server);
+                        }
                     }
+                    Err(e) => eprintln!("[WORKOPILOT] Failed to start IPC socket server: {}", e),
                 }
-                Err(e) => eprintln!("[WORKOPILOT] Failed to start IPC socket server: {}", e),
             }
             
-            if let Ok(mut sidecar) = state.sidecar.sidecar.lock() {
-                match sidecar.start() {
-                    Ok(_) => eprintln!("[WORKOPILOT] Sidecar started"),
-                    Err(e) => eprintln!("[WORKOPILOT] Failed to start sidecar: {}", e),
-                }
+            let watcher_app = app.handle().clone();
+            let own_writes = state.db.lock().map(|db| db.own_writes()).unwrap_or_default();
+            let watcher = db_watcher::watch(Database::get_db_path(), own_writes, move |change| {
+                sidecar_events::forward_entity_change(&watcher_app, change);
+            });
+            if let Ok(mut db_watcher) = state.db_watcher.lock() {
+                *db_watcher = Some(watcher);
+            }
+
+            let notification_app = app.handle().clone();
+            state
+                .sidecar
+                .sidecar
+                .set_notification_handler(Arc::new(move |notification| {
+                    sidecar_events::forward_notification(&notification_app, notification);
+                }));
+            state
+                .sidecar
+                .sidecar
+                .set_status_handler(sidecar_supervisor::status_handler(app.handle().clone()));
+            sidecar_commands::load_discovery_overrides(&state);
+
+            match state.sidecar.sidecar.start() {
+                Ok(_) => eprintln!("[WORKOPILOT] Sidecar started"),
+                Err(e) => eprintln!("[WORKOPILOT] Failed to start sidecar: {}", e),
+            }
+
+            if let Ok(mut supervisor) = state.sidecar.supervisor.lock() {
+                *supervisor = Some(SidecarSupervisor::start(app.handle().clone()));
             }
             
             if std::env::var("WORKOPILOT_DEV").is_ok() {
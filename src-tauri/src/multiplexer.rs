@@ -0,0 +1,775 @@
+use crate::shell_script::{command_line, shell_quote};
+use crate::terminal_emulator::{self, BackendInfo};
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+pub const ENV_VAR: &str = "WORKOPILOT_MULTIPLEXER";
+pub const SETTING: &str = "terminal_multiplexer";
+/// Setting value that picks the first installed multiplexer.
+pub const AUTO: &str = "auto";
+
+/// One tab of a session layout, with its route resolved to a directory.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabSpec {
+    pub name: String,
+    pub cwd: String,
+    pub startup_command: Option<String>,
+}
+
+/// A terminal multiplexer holding the task tabs.
+///
+/// The `script_*` methods return bash snippets for the scripts run inside a
+/// new terminal window; their arguments are plain values and get quoted.
+pub trait Multiplexer: Sync {
+    /// Value stored in the `terminal_multiplexer` setting.
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    fn binary(&self) -> &'static str;
+
+    fn session_exists(&self, session: &str) -> bool;
+    /// Tab names in `session`; empty when it does not exist.
+    fn list_tabs(&self, session: &str) -> Result<Vec<String>, String>;
+    fn has_attached_client(&self, session: &str) -> bool;
+    /// Replaces any tab called `tab` with a fresh one in `cwd`.
+    fn open_tab(&self, session: &str, tab: &str, cwd: &str) -> Result<(), String>;
+    /// Makes `tab` active without bringing the terminal window to front.
+    fn select_tab(&self, session: &str, tab: &str) -> Result<(), String>;
+    /// Program running in the tab's active pane (e.g. `node`), empty if unknown.
+    /// Zellij makes `tab` the visible tab to answer.
+    fn pane_command(&self, session: &str, tab: &str) -> Result<String, String>;
+    /// Visible text of the tab's active pane; `None` if the backend cannot capture it.
+    fn capture_pane(&self, session: &str, tab: &str) -> Result<Option<String>, String>;
+    /// Types `text` into the tab's active pane and presses Enter.
+    fn send_keys(&self, session: &str, tab: &str, text: &str) -> Result<(), String>;
+    /// Pastes `text` as-is into the tab's active pane and presses Enter. Used for
+    /// prompts, which may hold anything a task title does.
+    fn paste_text(&self, session: &str, tab: &str, text: &str) -> Result<(), String>;
+    /// Whether [`Multiplexer::script_pane_command`] works before a client attaches.
+    fn can_probe_detached(&self) -> bool;
+
+    /// Creates `tab` in `cwd`, starting `session` in the background if needed.
+    fn script_open_tab(&self, session: &str, tab: &str, cwd: &str) -> String;
+    /// Prints the program running in the tab's active pane.
+    fn script_pane_command(&self, session: &str, tab: &str) -> String;
+    /// Prints the visible text of the tab's active pane, if the backend can.
+    fn script_capture_pane(&self, session: &str, tab: &str) -> Option<String>;
+    fn script_send_keys(&self, session: &str, tab: &str, text: &str) -> String;
+    /// Like [`Multiplexer::paste_text`] with the contents of the file at `path`.
+    fn script_paste_file(&self, session: &str, tab: &str, path: &Path) -> String;
+    /// Selects `tab` and attaches the terminal to `session`.
+    fn script_attach(&self, session: &str, tab: &str) -> String;
+    /// Attaches to `session`, creating it from `tabs` first if it does not exist.
+    fn script_layout(&self, session: &str, tabs: &[TabSpec]) -> String;
+}
+
+fn run(mut command: Command, what: &str) -> Result<Output, String> {
+    command
+        .output()
+        .map_err(|e| format!("Failed to {}: {}", what, e))
+}
+
+fn check(output: Output, what: &str) -> Result<(), String> {
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "Failed to {}: {}",
+            what,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn lines(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+pub struct Tmux;
+
+impl Tmux {
+    fn target(session: &str, tab: &str) -> String {
+        format!("{}:{}", session, tab)
+    }
+
+    /// Paste buffer used for prompts sent to `tab`.
+    fn buffer(tab: &str) -> String {
+        format!("workopilot-{}", tab)
+    }
+
+    fn command(args: &[&str]) -> Command {
+        let mut command = Command::new("tmux");
+        command.args(args);
+        command
+    }
+}
+
+impl Multiplexer for Tmux {
+    fn id(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn name(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn binary(&self) -> &'static str {
+        "tmux"
+    }
+
+    fn session_exists(&self, session: &str) -> bool {
+        Self::command(&["has-session", "-t", session])
+            .output()
+            .map(|o| o.status.success())
+            .unwrap_or(false)
+    }
+
+    fn list_tabs(&self, session: &str) -> Result<Vec<String>, String> {
+        if !self.session_exists(session) {
+            return Ok(vec![]);
+        }
+
+        let output = run(
+            Self::command(&["list-windows", "-t", session, "-F", "#{window_name}"]),
+            "list tmux windows",
+        )?;
+        Ok(if output.status.success() {
+            lines(&output)
+        } else {
+            vec![]
+        })
+    }
+
+    fn has_attached_client(&self, session: &str) -> bool {
+        Self::command(&["list-clients", "-t", session])
+            .output()
+            .map(|o| o.status.success() && !o.stdout.is_empty())
+            .unwrap_or(false)
+    }
+
+    fn open_tab(&self, session: &str, tab: &str, cwd: &str) -> Result<(), String> {
+        let _ = Self::command(&["kill-window", "-t", &Self::target(session, tab)]).output();
+
+        let output = run(
+            Self::command(&["new-window", "-t", session, "-n", tab, "-c", cwd]),
+            "create tmux window",
+        )?;
+        check(output, "create tmux window")
+    }
+
+    fn select_tab(&self, session: &str, tab: &str) -> Result<(), String> {
+        let output = run(
+            Self::command(&["select-window", "-t", &Self::target(session, tab)]),
+            "select tmux window",
+        )?;
+        check(output, "select tmux window")
+    }
+
+    fn pane_command(&self, session: &str, tab: &str) -> Result<String, String> {
+        let output = run(
+            Self::command(&[
+                "display-message",
+                "-p",
+                "-t",
+                &Self::target(session, tab),
+                "#{pane_current_command}",
+            ]),
+            "get pane command",
+        )?;
+        Ok(if output.status.success() {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        } else {
+            String::new()
+        })
+    }
+
+    fn capture_pane(&self, session: &str, tab: &str) -> Result<Option<String>, String> {
+        let output = run(
+            Self::command(&["capture-pane", "-p", "-t", &Self::target(session, tab)]),
+            "capture tmux pane",
+        )?;
+        Ok(Some(if output.status.success() {
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        } else {
+            String::new()
+        }))
+    }
+
+    fn send_keys(&self, session: &str, tab: &str, text: &str) -> Result<(), String> {
+        let output = run(
+            Self::command(&[
+                "send-keys",
+                "-t",
+                &Self::target(session, tab),
+                text,
+                "Enter",
+            ]),
+            "send keys to tmux",
+        )?;
+        check(output, "send keys to tmux")
+    }
+
+    fn paste_text(&self, session: &str, tab: &str, text: &str) -> Result<(), String> {
+        let buffer = Self::buffer(tab);
+        let mut child = Self::command(&["load-buffer", "-b", &buffer, "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to load tmux buffer: {}", e))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| format!("Failed to load tmux buffer: {}", e))?;
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to load tmux buffer: {}", e))?;
+        check(output, "load tmux buffer")?;
+
+        let target = Self::target(session, tab);
+        let output = run(
+            Self::command(&["paste-buffer", "-d", "-p", "-b", &buffer, "-t", &target]),
+            "paste tmux buffer",
+        )?;
+        check(output, "paste tmux buffer")?;
+        let output = run(
+            Self::command(&["send-keys", "-t", &target, "Enter"]),
+            "send keys to tmux",
+        )?;
+        check(output, "send keys to tmux")
+    }
+
+    fn can_probe_detached(&self) -> bool {
+        true
+    }
+
+    fn script_open_tab(&self, session: &str, tab: &str, cwd: &str) -> String {
+        let (s, t, c) = (shell_quote(session), shell_quote(tab), shell_quote(cwd));
+        let target = shell_quote(&Self::target(session, tab));
+        format!(
+            r#"if tmux has-session -t {s} 2>/dev/null; then
+    tmux kill-window -t {target} 2>/dev/null
+    tmux new-window -t {s} -n {t} -c {c}
+else
+    tmux new-session -d -s {s} -n {t} -c {c}
+fi"#
+        )
+    }
+
+    fn script_pane_command(&self, session: &str, tab: &str) -> String {
+        format!(
+            "tmux display-message -p -t {} '#{{pane_current_command}}' 2>/dev/null",
+            shell_quote(&Self::target(session, tab))
+        )
+    }
+
+    fn script_capture_pane(&self, session: &str, tab: &str) -> Option<String> {
+        Some(format!(
+            "tmux capture-pane -p -t {} 2>/dev/null",
+            shell_quote(&Self::target(session, tab))
+        ))
+    }
+
+    fn script_send_keys(&self, session: &str, tab: &str, text: &str) -> String {
+        format!(
+            "tmux send-keys -t {} {} Enter",
+            shell_quote(&Self::target(session, tab)),
+            shell_quote(text)
+        )
+    }
+
+    fn script_paste_file(&self, session: &str, tab: &str, path: &Path) -> String {
+        let buffer = Self::buffer(tab);
+        let target = Self::target(session, tab);
+        format!(
+            "{} && {} && {}",
+            command_line("tmux", &["load-buffer", "-b", &buffer, &path.to_string_lossy()]),
+            command_line("tmux", &["paste-buffer", "-d", "-p", "-b", &buffer, "-t", &target]),
+            command_line("tmux", &["send-keys", "-t", &target, "Enter"]),
+        )
+    }
+
+    fn script_attach(&self, session: &str, tab: &str) -> String {
+        format!(
+            "tmux select-window -t {}\ntmux attach-session -t {}",
+            shell_quote(&Self::target(session, tab)),
+            shell_quote(session)
+        )
+    }
+
+    fn script_layout(&self, session: &str, tabs: &[TabSpec]) -> String {
+        let s = shell_quote(session);
+        let mut script = format!(
+            r#"if tmux has-session -t {s} 2>/dev/null; then
+    tmux attach-session -t {s}
+    exit 0
+fi
+
+"#
+        );
+
+        for (i, tab) in tabs.iter().enumerate() {
+            let (t, c) = (shell_quote(&tab.name), shell_quote(&tab.cwd));
+            if i == 0 {
+                script.push_str(&format!("tmux new-session -d -s {s} -n {t} -c {c}\n"));
+            } else {
+                script.push_str(&format!("tmux new-window -t {s} -n {t} -c {c}\n"));
+            }
+            if let Some(cmd) = &tab.startup_command {
+                script.push_str(&self.script_send_keys(session, &tab.name, cmd));
+                script.push('\n');
+            }
+        }
+
+        if let Some(first_tab) = tabs.first() {
+            script.push('\n');
+            script.push_str(&self.script_attach(session, &first_tab.name));
+            script.push('\n');
+        }
+        script
+    }
+}
+
+/// Zellij 0.40+, driven through `zellij --session <name> action ...`.
+pub struct Zellij;
+
+impl Zellij {
+    fn action(session: &str, args: &[&str]) -> Command {
+        let mut command = Command::new("zellij");
+        command.args(["--session", session, "action"]).args(args);
+        command
+    }
+
+    fn go_to_tab(session: &str, tab: &str) -> Result<(), String> {
+        let output = run(
+            Self::action(session, &["go-to-tab-name", tab]),
+            "select zellij tab",
+        )?;
+        check(output, "select zellij tab")
+    }
+
+    fn script_action(session: &str, args: &str) -> String {
+        format!("zellij --session {} action {}", shell_quote(session), args)
+    }
+
+    fn script_session_exists(session: &str) -> String {
+        format!(
+            "zellij list-sessions --short --no-formatting 2>/dev/null | grep -qxF {}",
+            shell_quote(session)
+        )
+    }
+}
+
+/// `RUNNING_COMMAND` of the first client in `zellij action list-clients`,
+/// reduced to the program name.
+fn parse_list_clients(output: &str) -> String {
+    output
+        .lines()
+        .skip(1)
+        .find_map(|line| line.split_whitespace().nth(2))
+        .map(|program| program.rsplit('/').next().unwrap_or(program).to_string())
+        .unwrap_or_default()
+}
+
+/// KDL string literal, kept on one line so it cannot end the layout heredoc.
+fn kdl_string(value: &str) -> String {
+    let mut literal = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => literal.push_str(r"\\"),
+            '"' => literal.push_str("\\\""),
+            '\n' => literal.push_str(r"\n"),
+            '\r' => literal.push_str(r"\r"),
+            '\t' => literal.push_str(r"\t"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Zellij layout with one tab per [`TabSpec`] and the default tab and status bars.
+pub fn zellij_layout(tabs: &[TabSpec]) -> String {
+    let mut layout = String::from(
+        r#"layout {
+    default_tab_template {
+        pane size=1 borderless=true {
+            plugin location="zellij:tab-bar"
+        }
+        children
+        pane size=2 borderless=true {
+            plugin location="zellij:status-bar"
+        }
+    }
+"#,
+    );
+
+    for tab in tabs {
+        layout.push_str(&format!(
+            "    tab name={} cwd={} {{\n",
+            kdl_string(&tab.name),
+            kdl_string(&tab.cwd)
+        ));
+        match &tab.startup_command {
+            Some(cmd) => layout.push_str(&format!(
+                "        pane command=\"bash\" {{\n            args \"-c\" {}\n        }}\n",
+                kdl_string(&format!("{}; exec bash", cmd))
+            )),
+            None => layout.push_str("        pane\n"),
+        }
+        layout.push_str("    }\n");
+    }
+
+    layout.push_str("}\n");
+    layout
+}
+
+impl Multiplexer for Zellij {
+    fn id(&self) -> &'static str {
+        "zellij"
+    }
+
+    fn name(&self) -> &'static str {
+        "Zellij"
+    }
+
+    fn binary(&self) -> &'static str {
+        "zellij"
+    }
+
+    fn session_exists(&self, session: &str) -> bool {
+        Command::new("zellij")
+            .args(["list-sessions", "--short", "--no-formatting"])
+            .output()
+            .map(|o| lines(&o).iter().any(|name| name == session))
+            .unwrap_or(false)
+    }
+
+    fn list_tabs(&self, session: &str) -> Result<Vec<String>, String> {
+        if !self.session_exists(session) {
+            return Ok(vec![]);
+        }
+
+        let output = run(
+            Self::action(session, &["query-tab-names"]),
+            "list zellij tabs",
+        )?;
+        Ok(if output.status.success() {
+            lines(&output)
+        } else {
+            vec![]
+        })
+    }
+
+    fn has_attached_client(&self, session: &str) -> bool {
+        Self::action(session, &["list-clients"])
+            .output()
+            .map(|o| o.status.success() && lines(&o).len() > 1)
+            .unwrap_or(false)
+    }
+
+    fn open_tab(&self, session: &str, tab: &str, cwd: &str) -> Result<(), String> {
+        if self.list_tabs(session)?.iter().any(|t| t == tab) {
+            Self::go_to_tab(session, tab)?;
+            let _ = Self::action(session, &["close-tab"]).output();
+        }
+
+        let output = run(
+            Self::action(session, &["new-tab", "--name", tab, "--cwd", cwd]),
+            "create zellij tab",
+        )?;
+        check(output, "create zellij tab")
+    }
+
+    fn select_tab(&self, session: &str, tab: &str) -> Result<(), String> {
+        Self::go_to_tab(session, tab)
+    }
+
+    /// Zellij only reports the focused pane of attached clients, so this
+    /// switches to `tab` first. Callers only pass the tab they are about to
+    /// select anyway, but while a readiness wait polls (up to 30 s), switching
+    /// to another tab is undone on the next poll.
+    fn pane_command(&self, session: &str, tab: &str) -> Result<String, String> {
+        Self::go_to_tab(session, tab)?;
+        let output = run(
+            Self::action(session, &["list-clients"]),
+            "list zellij clients",
+        )?;
+        Ok(parse_list_clients(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// `dump-screen` only reads the focused pane of an attached client.
+    fn capture_pane(&self, _session: &str, _tab: &str) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    fn send_keys(&self, session: &str, tab: &str, text: &str) -> Result<(), String> {
+        Self::go_to_tab(session, tab)?;
+        let output = run(
+            Self::action(session, &["write-chars", text]),
+            "write to zellij pane",
+        )?;
+        check(output, "write to zellij pane")?;
+        let output = run(
+            Self::action(session, &["write", "13"]),
+            "write to zellij pane",
+        )?;
+        check(output, "write to zellij pane")
+    }
+
+    /// Zellij has no paste buffer: line breaks become spaces so the prompt is
+    /// not submitted early.
+    fn paste_text(&self, session: &str, tab: &str, text: &str) -> Result<(), String> {
+        self.send_keys(session, tab, &text.replace(['\r', '\n'], " "))
+    }
+
+    fn can_probe_detached(&self) -> bool {
+        false
+    }
+
+    fn script_open_tab(&self, session: &str, tab: &str, cwd: &str) -> String {
+        let t = shell_quote(tab);
+        format!(
+            r#"if {exists}; then
+    if {query} | grep -qxF {t}; then
+        {go_to} && {close}
+    fi
+else
+    zellij attach --create-background {s}
+fi
+{new_tab}"#,
+            exists = Self::script_session_exists(session),
+            query = Self::script_action(session, "query-tab-names"),
+            go_to = Self::script_action(session, &format!("go-to-tab-name {}", t)),
+            close = Self::script_action(session, "close-tab"),
+            s = shell_quote(session),
+            new_tab = Self::script_action(
+                session,
+                &format!("new-tab --name {} --cwd {}", t, shell_quote(cwd))
+            ),
+        )
+    }
+
+    fn script_pane_command(&self, session: &str, tab: &str) -> String {
+        format!(
+            "{{ {} && {} | awk 'NR == 2 {{ n = split($3, p, \"/\"); print p[n] }}'; }} 2>/dev/null",
+            Self::script_action(session, &format!("go-to-tab-name {}", shell_quote(tab))),
+            Self::script_action(session, "list-clients"),
+        )
+    }
+
+    fn script_capture_pane(&self, _session: &str, _tab: &str) -> Option<String> {
+        None
+    }
+
+    fn script_send_keys(&self, session: &str, tab: &str, text: &str) -> String {
+        format!(
+            "{} && {} && {}",
+            Self::script_action(session, &format!("go-to-tab-name {}", shell_quote(tab))),
+            Self::script_action(session, &format!("write-chars {}", shell_quote(text))),
+            Self::script_action(session, "write 13"),
+        )
+    }
+
+    fn script_paste_file(&self, session: &str, tab: &str, path: &Path) -> String {
+        format!(
+            "{} && {} && {}",
+            Self::script_action(session, &format!("go-to-tab-name {}", shell_quote(tab))),
+            Self::script_action(
+                session,
+                &format!(
+                    "write-chars \"$(tr '\\r\\n' '  ' < {})\"",
+                    shell_quote(&path.to_string_lossy())
+                )
+            ),
+            Self::script_action(session, "write 13"),
+        )
+    }
+
+    fn script_attach(&self, session: &str, tab: &str) -> String {
+        format!(
+            "{}\nzellij attach {}",
+            Self::script_action(session, &format!("go-to-tab-name {}", shell_quote(tab))),
+            shell_quote(session)
+        )
+    }
+
+    fn script_layout(&self, session: &str, tabs: &[TabSpec]) -> String {
+        let s = shell_quote(session);
+        format!(
+            r#"if {exists}; then
+    exec zellij attach {s}
+fi
+
+LAYOUT=$(mktemp --suffix=.kdl)
+cat > "$LAYOUT" <<'WORKOPILOT_LAYOUT'
+{layout}WORKOPILOT_LAYOUT
+zellij --session {s} --new-session-with-layout "$LAYOUT"
+"#,
+            exists = Self::script_session_exists(session),
+            layout = zellij_layout(tabs),
+        )
+    }
+}
+
+/// Supported multiplexers, in auto-detection order.
+pub const MULTIPLEXERS: &[&dyn Multiplexer] = &[&Tmux, &Zellij];
+
+pub fn from_id(id: &str) -> Option<&'static dyn Multiplexer> {
+    let id = id.trim().to_lowercase();
+    MULTIPLEXERS.iter().copied().find(|m| m.id() == id)
+}
+
+/// The multiplexer chosen by the user: `WORKOPILOT_MULTIPLEXER` over the setting.
+pub fn preference(setting: Option<String>) -> Option<String> {
+    std::env::var(ENV_VAR)
+        .ok()
+        .into_iter()
+        .chain(setting)
+        .map(|v| v.trim().to_string())
+        .find(|v| !v.is_empty())
+}
+
+/// Resolves `preference` (a multiplexer id, `auto` or nothing) to an installed one.
+pub fn select(
+    preference: Option<&str>,
+    is_installed: impl Fn(&str) -> bool,
+) -> Result<&'static dyn Multiplexer, String> {
+    let ids = || {
+        MULTIPLEXERS
+            .iter()
+            .map(|m| m.id())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match preference.filter(|p| *p != AUTO) {
+        Some(id) => {
+            let multiplexer = from_id(id).ok_or_else(|| {
+                format!(
+                    "Unknown terminal multiplexer: {}. Valid values: {}, {}",
+                    id,
+                    AUTO,
+                    ids()
+                )
+            })?;
+            if !is_installed(multiplexer.binary()) {
+                return Err(format!(
+                    "Terminal multiplexer {} is not installed ({} not found in PATH)",
+                    multiplexer.name(),
+                    multiplexer.binary()
+                ));
+            }
+            Ok(multiplexer)
+        }
+        None => MULTIPLEXERS
+            .iter()
+            .copied()
+            .find(|m| is_installed(m.binary()))
+            .ok_or_else(|| format!("No supported terminal multiplexer found (tried {})", ids())),
+    }
+}
+
+pub fn detect() -> Vec<BackendInfo> {
+    MULTIPLEXERS
+        .iter()
+        .map(|m| BackendInfo {
+            id: m.id().to_string(),
+            name: m.name().to_string(),
+            installed: terminal_emulator::is_installed(m.binary()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tabs() -> Vec<TabSpec> {
+        vec![
+            TabSpec {
+                name: "api".to_string(),
+                cwd: "/srv/my app".to_string(),
+                startup_command: Some("npm run dev".to_string()),
+            },
+            TabSpec {
+                name: "web".to_string(),
+                cwd: "/srv/web".to_string(),
+                startup_command: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_tmux_layout_script() {
+        let script = Tmux.script_layout("proj", &tabs());
+
+        assert!(script.contains("tmux new-session -d -s 'proj' -n 'api' -c '/srv/my app'\n"));
+        assert!(script.contains("tmux send-keys -t 'proj:api' 'npm run dev' Enter\n"));
+        assert!(script.contains("tmux new-window -t 'proj' -n 'web' -c '/srv/web'\n"));
+        assert!(
+            script.ends_with("tmux select-window -t 'proj:api'\ntmux attach-session -t 'proj'\n")
+        );
+    }
+
+    #[test]
+    fn test_zellij_layout() {
+        let mut tabs = tabs();
+        tabs[1].name = r#"we"b"#.to_string();
+        let layout = zellij_layout(&tabs);
+
+        assert!(layout.contains(
+            "    tab name=\"api\" cwd=\"/srv/my app\" {\n        pane command=\"bash\" {\n            args \"-c\" \"npm run dev; exec bash\"\n"
+        ));
+        assert!(layout.contains(r#"tab name="we\"b" cwd="/srv/web" {"#));
+
+        // A line break cannot end the heredoc the layout is written through.
+        tabs[1].cwd = "/srv\nWORKOPILOT_LAYOUT\ntouch /tmp/pwned".to_string();
+        let layout = zellij_layout(&tabs);
+        assert!(layout.contains(r#"cwd="/srv\nWORKOPILOT_LAYOUT\ntouch /tmp/pwned""#));
+        assert!(!layout.lines().any(|l| l == "WORKOPILOT_LAYOUT"));
+        assert!(Zellij
+            .script_layout("proj", &tabs)
+            .contains("zellij --session 'proj' --new-session-with-layout \"$LAYOUT\""));
+    }
+
+    #[test]
+    fn test_paste_file_quotes_path() {
+        let path = Path::new("/run/user/1000/workopilot/prompt-it's $(id).txt");
+        let quoted = "'/run/user/1000/workopilot/prompt-it'\\''s $(id).txt'";
+
+        assert_eq!(
+            Tmux.script_paste_file("workopilot", "proj-t1", path),
+            format!(
+                "tmux 'load-buffer' '-b' 'workopilot-proj-t1' {quoted} && \
+                 tmux 'paste-buffer' '-d' '-p' '-b' 'workopilot-proj-t1' '-t' 'workopilot:proj-t1' && \
+                 tmux 'send-keys' '-t' 'workopilot:proj-t1' 'Enter'"
+            )
+        );
+        assert!(Zellij
+            .script_paste_file("workopilot", "proj-t1", path)
+            .contains(&format!("write-chars \"$(tr '\\r\\n' '  ' < {quoted})\"")));
+    }
+
+    #[test]
+    fn test_parse_list_clients() {
+        let output = "CLIENT_ID ZELLIJ_PANE_ID RUNNING_COMMAND\n1         terminal_2     /usr/bin/node /opt/opencode\n";
+        assert_eq!(parse_list_clients(output), "node");
+        assert_eq!(
+            parse_list_clients("CLIENT_ID ZELLIJ_PANE_ID RUNNING_COMMAND\n"),
+            ""
+        );
+    }
+
+    #[test]
+    fn test_select_multiplexer() {
+        assert_eq!(select(None, |b| b == "zellij").unwrap().id(), "zellij");
+        assert_eq!(select(Some(AUTO), |_| true).unwrap().id(), "tmux");
+        assert!(select(Some("screen"), |_| true).is_err());
+        assert!(select(Some("zellij"), |b| b == "tmux").is_err());
+    }
+}
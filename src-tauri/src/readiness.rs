@@ -0,0 +1,277 @@
+use crate::multiplexer::Multiplexer;
+use std::time::{Duration, Instant};
+
+/// How long a new tab's shell gets to draw its prompt.
+pub const SHELL_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long opencode gets to start and draw its UI.
+pub const AGENT_TIMEOUT: Duration = Duration::from_secs(30);
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Programs a pane reports while opencode runs in it.
+pub const AGENT_COMMANDS: &[&str] = &["node", "opencode"];
+
+/// Repeats of the same agent screen before its UI counts as settled.
+const SETTLED_POLLS: u32 = 2;
+/// Polls the agent must keep running for when the pane cannot be captured.
+const BLIND_POLLS: u32 = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    /// The shell has drawn a prompt and can take the agent command.
+    Shell,
+    /// opencode runs in the pane, has drawn over what the shell showed when it
+    /// started, and its screen has stopped changing.
+    Agent,
+}
+
+impl Stage {
+    pub fn timeout(self) -> Duration {
+        match self {
+            Stage::Shell => SHELL_TIMEOUT,
+            Stage::Agent => AGENT_TIMEOUT,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Stage::Shell => "shell prompt",
+            Stage::Agent => "opencode",
+        }
+    }
+}
+
+/// One look at a pane.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PaneSnapshot {
+    /// Foreground program, empty if unknown.
+    pub command: String,
+    /// Visible text; `None` when the multiplexer cannot capture panes.
+    pub screen: Option<String>,
+}
+
+/// Decides from successive snapshots whether a pane has reached a [`Stage`].
+pub struct ReadinessProbe {
+    stage: Stage,
+    /// Screen when the agent was first seen running.
+    baseline: Option<Option<String>>,
+    last_screen: Option<Option<String>>,
+    settled: u32,
+}
+
+impl ReadinessProbe {
+    pub fn new(stage: Stage) -> Self {
+        Self {
+            stage,
+            baseline: None,
+            last_screen: None,
+            settled: 0,
+        }
+    }
+
+    pub fn observe(&mut self, snapshot: &PaneSnapshot) -> bool {
+        if self.stage == Stage::Shell {
+            return match &snapshot.screen {
+                Some(screen) => !screen.trim().is_empty(),
+                None => !snapshot.command.is_empty(),
+            };
+        }
+
+        if !AGENT_COMMANDS.contains(&snapshot.command.as_str()) {
+            self.baseline = None;
+            self.last_screen = None;
+            self.settled = 0;
+            return false;
+        }
+
+        let baseline = self.baseline.get_or_insert_with(|| snapshot.screen.clone());
+        let drawn = match &snapshot.screen {
+            Some(screen) => !screen.trim().is_empty() && baseline.as_ref() != Some(screen),
+            None => true,
+        };
+        if drawn && self.last_screen.as_ref() == Some(&snapshot.screen) {
+            self.settled += 1;
+        } else {
+            self.settled = 0;
+        }
+        self.last_screen = Some(snapshot.screen.clone());
+
+        let required = match snapshot.screen {
+            Some(_) => SETTLED_POLLS,
+            None => BLIND_POLLS,
+        };
+        self.settled >= required
+    }
+}
+
+/// Takes snapshots every `interval` until `stage` is reached or `timeout`
+/// passes; returns how long it took.
+pub fn poll(
+    stage: Stage,
+    timeout: Duration,
+    interval: Duration,
+    mut snapshot: impl FnMut() -> PaneSnapshot,
+) -> Result<Duration, String> {
+    let mut probe = ReadinessProbe::new(stage);
+    let started = Instant::now();
+    loop {
+        if probe.observe(&snapshot()) {
+            return Ok(started.elapsed());
+        }
+        if started.elapsed() >= timeout {
+            return Err(format!("{} not ready after {:?}", stage.label(), timeout));
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+/// Waits for `tab` to reach `stage`, polling the multiplexer.
+pub fn wait_for(
+    mux: &dyn Multiplexer,
+    session: &str,
+    tab: &str,
+    stage: Stage,
+) -> Result<Duration, String> {
+    poll(stage, stage.timeout(), POLL_INTERVAL, || PaneSnapshot {
+        command: mux.pane_command(session, tab).unwrap_or_default(),
+        // A failed capture reads as a blank screen, not as an unsupported one.
+        screen: mux
+            .capture_pane(session, tab)
+            .unwrap_or_else(|_| Some(String::new())),
+    })
+    .map_err(|e| format!("{} in tab {}", e, tab))
+}
+
+/// Bash version of the probe for scripts run in a new terminal. Defines
+/// `shell_ready` and `agent_ready`, and `wait_until <check> <seconds>`.
+pub fn script_probe(mux: &dyn Multiplexer, session: &str, tab: &str) -> String {
+    let pane_command = mux.script_pane_command(session, tab);
+    let (shell_check, screen, drawn, settled) = match mux.script_capture_pane(session, tab) {
+        Some(capture) => (
+            format!("[ -n \"$({} | tr -d '[:space:]')\" ]", capture),
+            format!("$({})", capture),
+            "[ -n \"${screen//[[:space:]]/}\" ] && [ \"$screen\" != \"$BASELINE\" ]",
+            SETTLED_POLLS,
+        ),
+        None => (
+            format!("[ -n \"$({})\" ]", pane_command),
+            "running".to_string(),
+            "true",
+            BLIND_POLLS,
+        ),
+    };
+
+    format!(
+        r#"STARTED=
+BASELINE=
+LAST_SCREEN=
+SETTLED=0
+shell_ready() {{
+    {shell_check}
+}}
+agent_ready() {{
+    case "$({pane_command})" in
+        {agent_commands}) ;;
+        *) STARTED=; LAST_SCREEN=; SETTLED=0; return 1 ;;
+    esac
+    local screen
+    screen={screen}
+    [ -z "$STARTED" ] && STARTED=1 && BASELINE=$screen
+    if {drawn} && [ "$screen" = "$LAST_SCREEN" ]; then
+        SETTLED=$((SETTLED + 1))
+    else
+        SETTLED=0
+    fi
+    LAST_SCREEN=$screen
+    [ $SETTLED -ge {settled} ]
+}}
+wait_until() {{
+    local deadline=$((SECONDS + $2))
+    until "$1"; do
+        [ $SECONDS -ge $deadline ] && return 1
+        sleep {interval}
+    done
+}}"#,
+        agent_commands = AGENT_COMMANDS.join("|"),
+        interval = POLL_INTERVAL.as_secs_f32(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(command: &str, screen: Option<&str>) -> PaneSnapshot {
+        PaneSnapshot {
+            command: command.to_string(),
+            screen: screen.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_shell_ready_once_prompt_is_drawn() {
+        let mut probe = ReadinessProbe::new(Stage::Shell);
+
+        assert!(!probe.observe(&snapshot("bash", Some("\n\n"))));
+        assert!(probe.observe(&snapshot("bash", Some("~/project $ \n"))));
+        // Without capture, a known foreground program is all there is.
+        assert!(!probe.observe(&snapshot("", None)));
+        assert!(probe.observe(&snapshot("zsh", None)));
+    }
+
+    #[test]
+    fn test_agent_ready_once_its_screen_settles() {
+        let mut probe = ReadinessProbe::new(Stage::Agent);
+        let seen: Vec<bool> = [
+            snapshot("bash", Some("$ opencode")),
+            snapshot("node", Some("")),
+            snapshot("node", Some("loading")),
+            snapshot("node", Some("> ")),
+            snapshot("node", Some("> ")),
+            snapshot("node", Some("> ")),
+        ]
+        .iter()
+        .map(|s| probe.observe(s))
+        .collect();
+        assert_eq!(seen, [false, false, false, false, false, true]);
+
+        // Falling back to the shell starts over.
+        assert!(!probe.observe(&snapshot("bash", Some("> "))));
+        assert!(!probe.observe(&snapshot("opencode", Some("> "))));
+
+        // Still silent: the screen never moved past the typed command.
+        let mut silent = ReadinessProbe::new(Stage::Agent);
+        assert!((0..10).all(|_| !silent.observe(&snapshot("node", Some("$ opencode")))));
+
+        let mut blind = ReadinessProbe::new(Stage::Agent);
+        let polls = (0..=BLIND_POLLS)
+            .take_while(|_| !blind.observe(&snapshot("opencode", None)))
+            .count();
+        assert_eq!(polls as u32, BLIND_POLLS);
+    }
+
+    #[test]
+    fn test_poll_times_out_when_agent_never_starts() {
+        let mut polls = 0;
+        let result = poll(
+            Stage::Agent,
+            Duration::from_millis(20),
+            Duration::from_millis(1),
+            || {
+                polls += 1;
+                snapshot("bash", Some("$ opencode\ncommand not found"))
+            },
+        );
+
+        assert_eq!(result, Err("opencode not ready after 20ms".to_string()));
+        assert!(polls > 1);
+
+        let mut screens = ["", "starting", "ready", "ready", "ready"].into_iter();
+        let result = poll(
+            Stage::Agent,
+            Duration::from_secs(5),
+            Duration::from_millis(1),
+            || snapshot("node", screens.next()),
+        );
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,117 @@
+/// Quotes `value` as a single bash word.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// `program` followed by each of `args` as a quoted word.
+pub fn command_line(program: &str, args: &[&str]) -> String {
+    std::iter::once(program.to_string())
+        .chain(args.iter().map(|arg| shell_quote(arg)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A bash script put together from quoted commands and snippets.
+///
+/// Values from the database (task titles, project names, paths) only ever go
+/// in through [`ShellScript::command`] or a snippet built with [`shell_quote`];
+/// prompts are passed as files, never as script text.
+pub struct ShellScript {
+    lines: Vec<String>,
+}
+
+impl Default for ShellScript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShellScript {
+    pub fn new() -> Self {
+        Self {
+            lines: vec!["#!/usr/bin/env bash".to_string()],
+        }
+    }
+
+    /// Runs `program` (a fixed name, not data) with each of `args` quoted.
+    pub fn command(mut self, program: &str, args: &[&str]) -> Self {
+        self.lines.push(command_line(program, args));
+        self
+    }
+
+    /// Appends code whose values are already quoted, such as the
+    /// `Multiplexer::script_*` snippets.
+    pub fn snippet(mut self, code: impl Into<String>) -> Self {
+        self.lines.push(code.into());
+        self
+    }
+
+    pub fn blank(mut self) -> Self {
+        self.lines.push(String::new());
+        self
+    }
+
+    pub fn build(self) -> String {
+        let mut script = self.lines.join("\n");
+        script.push('\n');
+        script
+    }
+}
+
+/// Tests that run bash skip themselves, with a note, where it is not installed.
+#[cfg(test)]
+pub fn bash_missing() -> bool {
+    let missing = which::which("bash").is_err();
+    if missing {
+        eprintln!("bash not installed, skipping");
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Words bash actually parses out of `script` for `printf '%s\0'`.
+    fn parsed_words(script: &str) -> Vec<String> {
+        let output = Command::new("bash")
+            .args(["-c", script])
+            .output()
+            .expect("bash");
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .split_terminator('\0')
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's $HOME"), r"'it'\''s $HOME'");
+    }
+
+    #[test]
+    fn test_adversarial_values_stay_single_words() {
+        if bash_missing() {
+            return;
+        }
+        let values = [
+            "$(touch /tmp/workopilot-pwned)",
+            "`id`",
+            "it's; rm -rf ~ #",
+            "!!",
+            "line one\nline two",
+            "\"quoted\" \\ back",
+            "",
+        ];
+
+        let script = ShellScript::new()
+            .command("printf", &[&["%s\\0"], &values[..]].concat())
+            .build();
+
+        assert_eq!(parsed_words(&script), values);
+    }
+}
@@ -1,8 +1,19 @@
+use crate::sidecar_discovery::{self, Discovery, DiscoveryOverrides};
+use crate::sidecar_handshake::{self, Handshake, SidecarCapabilities};
+use crate::sidecar_logs::{self, SidecarLogBuffer};
+use crate::sidecar_supervisor::SidecarSupervisor;
+use crate::sidecar_transport::{CallKind, CallTimeouts, SidecarTransport};
+pub use crate::sidecar_error::SidecarError;
+use crate::trpc_client::{HttpTransport, TrpcClient, TrpcResult};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Write};
-use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::process::{Child, ChildStdin, ChildStdout, Stdio};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
 #[derive(Debug, Serialize)]
 struct JsonRpcRequest {
@@ -17,176 +28,855 @@ struct JsonRpcRequest {
 struct JsonRpcResponse {
     #[allow(dead_code)]
     jsonrpc: String,
-    #[allow(dead_code)]
     id: Option<u64>,
     result: Option<serde_json::Value>,
     error: Option<JsonRpcError>,
 }
 
+impl JsonRpcResponse {
+    fn into_result(self) -> Result<serde_json::Value, SidecarError> {
+        if let Some(error) = self.error {
+            // Procedures called over stdio report tRPC errors as `data: { code, httpStatus }`.
+            let trpc_code = error
+                .data
+                .as_ref()
+                .and_then(|data| data.get("code"))
+                .and_then(|code| code.as_str());
+            return Err(match trpc_code {
+                Some(code) => SidecarError::from_trpc_code(code, error.message),
+                None => SidecarError::from_json_rpc_code(error.code, error.message),
+            });
+        }
+
+        self.result
+            .ok_or_else(|| SidecarError::Transport("No result in response".to_string()))
+    }
+}
+
+/// A JSON-RPC message without an `id`, pushed by the sidecar on its own
+/// (entity changes, log lines, progress updates).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SidecarNotification {
+    pub method: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+pub type NotificationHandler = Arc<dyn Fn(SidecarNotification) + Send + Sync>;
+pub type StatusHandler = Arc<dyn Fn(SidecarStatus) + Send + Sync>;
+
 #[derive(Debug, Deserialize)]
 struct JsonRpcError {
     code: i32,
     message: String,
-    #[allow(dead_code)]
     data: Option<serde_json::Value>,
 }
 
-const TRPC_URL_PREFIX: &str = "TRPC_URL=";
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(15);
+/// How long a failed startup waits for the sidecar's last stderr lines.
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_millis(500);
+const TRANSPORT_ENV_VAR: &str = "WORKOPILOT_SIDECAR_TRANSPORT";
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+const CANCEL_METHOD: &str = "$/cancelRequest";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SidecarHealth {
+    Stopped,
+    Starting,
+    Healthy,
+    Degraded,
+    Crashed,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SidecarStatus {
+    pub status: SidecarHealth,
+    pub running: bool,
+    pub restart_count: u32,
+    pub uptime_secs: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+struct Lifecycle {
+    health: SidecarHealth,
+    last_error: Option<String>,
+    started_at: Option<Instant>,
+    restart_count: u32,
+    has_started: bool,
+}
+
+type ResponseSender = Sender<Result<serde_json::Value, SidecarError>>;
+
+struct PendingRequest {
+    method: String,
+    sender: ResponseSender,
+}
+
+/// Handle to an in-flight request, returned by [`Sidecar::begin_call`].
+///
+/// Dropping it without calling [`PendingCall::wait`] leaves the request
+/// registered until its response arrives or the connection closes.
+pub struct PendingCall {
+    id: u64,
+    method: String,
+    timeout: Duration,
+    receiver: Receiver<Result<serde_json::Value, SidecarError>>,
+    connection: Arc<Connection>,
+}
+
+impl PendingCall {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn wait(self) -> Result<serde_json::Value, SidecarError> {
+        match self.receiver.recv_timeout(self.timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                // Forget the id so a late response is dropped by the reader
+                // instead of being delivered to anyone else.
+                self.connection.forget(self.id);
+                eprintln!(
+                    "[SIDECAR] Request {} ({}) timed out after {}ms",
+                    self.id,
+                    self.method,
+                    self.timeout.as_millis()
+                );
+                Err(SidecarError::Timeout {
+                    method: self.method,
+                    timeout: self.timeout,
+                })
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(SidecarError::Transport(
+                "Sidecar closed connection".to_string(),
+            )),
+        }
+    }
+}
+
+/// One live stdio channel to a sidecar process.
+///
+/// Requests register a sender under their JSON-RPC id before writing, and the
+/// reader thread routes each response line back by id, so any number of calls
+/// can be in flight at once.
+struct Connection {
+    stdin: Mutex<Option<ChildStdin>>,
+    pending: Mutex<HashMap<u64, PendingRequest>>,
+    notification_handler: Option<NotificationHandler>,
+}
+
+impl Connection {
+    fn new(stdin: ChildStdin, notification_handler: Option<NotificationHandler>) -> Self {
+        Self {
+            stdin: Mutex::new(Some(stdin)),
+            pending: Mutex::new(HashMap::new()),
+            notification_handler,
+        }
+    }
+
+    fn send(&self, id: u64, line: &str, request: PendingRequest) -> Result<(), String> {
+        let mut stdin_guard = self
+            .stdin
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+        let stdin = stdin_guard
+            .as_mut()
+            .ok_or("Sidecar closed connection")?;
+
+        // Registered while holding the stdin lock so `close` can never miss it.
+        self.pending
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?
+            .insert(id, request);
+
+        let written = writeln!(stdin, "{}", line)
+            .map_err(|e| format!("Write error: {}", e))
+            .and_then(|_| stdin.flush().map_err(|e| format!("Flush error: {}", e)));
+
+        if written.is_err() {
+            if let Ok(mut pending) = self.pending.lock() {
+                pending.remove(&id);
+            }
+        }
+
+        written
+    }
+
+    fn dispatch(&self, response: JsonRpcResponse) {
+        let Some(id) = response.id else {
+            eprintln!("[SIDECAR] Ignoring response without id");
+            return;
+        };
+
+        match self.forget(id) {
+            Some(request) => {
+                let _ = request.sender.send(response.into_result());
+            }
+            None => eprintln!(
+                "[SIDECAR] Dropping response for id {} (timed out, cancelled or unknown)",
+                id
+            ),
+        }
+    }
+
+    fn notify(&self, notification: SidecarNotification) {
+        match &self.notification_handler {
+            Some(handler) => handler(notification),
+            None => eprintln!(
+                "[SIDECAR] Ignoring notification {} (no handler registered)",
+                notification.method
+            ),
+        }
+    }
+
+    fn forget(&self, id: u64) -> Option<PendingRequest> {
+        self.pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(&id))
+    }
+
+    fn cancel(&self, id: u64) -> bool {
+        match self.forget(id) {
+            Some(request) => {
+                self.send_cancel(id);
+                let _ = request.sender.send(Err(SidecarError::Cancelled {
+                    method: request.method,
+                }));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Asks the sidecar to drop request `id` (`$/cancelRequest`, as in LSP).
+    /// Best effort: a closed stdin only means there is nothing left to cancel.
+    fn send_cancel(&self, id: u64) {
+        let notification = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": CANCEL_METHOD,
+            "params": { "id": id },
+        });
+        let Ok(mut stdin) = self.stdin.lock() else {
+            return;
+        };
+        if let Some(stdin) = stdin.as_mut() {
+            if let Err(e) = writeln!(stdin, "{}", notification).and_then(|_| stdin.flush()) {
+                eprintln!("[SIDECAR] Failed to send cancel for request {}: {}", id, e);
+            }
+        }
+    }
+
+    fn close(&self, reason: &str) {
+        if let Ok(mut stdin) = self.stdin.lock() {
+            *stdin = None;
+        }
+
+        if let Ok(mut pending) = self.pending.lock() {
+            for (_, request) in pending.drain() {
+                let _ = request
+                    .sender
+                    .send(Err(SidecarError::Transport(reason.to_string())));
+            }
+        }
+    }
+}
+
+fn send_request(
+    connection: Arc<Connection>,
+    id: u64,
+    method: &str,
+    params: Option<serde_json::Value>,
+    timeout: Duration,
+) -> Result<PendingCall, SidecarError> {
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0",
+        id,
+        method: method.to_string(),
+        params,
+    };
+
+    let request_json =
+        serde_json::to_string(&request).map_err(|e| format!("Serialize error: {}", e))?;
+
+    let (sender, receiver) = mpsc::channel();
+    connection
+        .send(
+            id,
+            &request_json,
+            PendingRequest {
+                method: method.to_string(),
+                sender,
+            },
+        )
+        .map_err(SidecarError::Transport)?;
+
+    Ok(PendingCall {
+        id,
+        method: method.to_string(),
+        timeout,
+        receiver,
+        connection,
+    })
+}
+
+/// tRPC procedures over the stdio JSON-RPC channel; the sidecar treats any
+/// method it has no native handler for as a procedure path.
+pub struct StdioTransport {
+    connection: Arc<Connection>,
+    request_id: Arc<AtomicU64>,
+    timeouts: CallTimeouts,
+}
+
+impl SidecarTransport for StdioTransport {
+    fn call(
+        &self,
+        _kind: CallKind,
+        path: &str,
+        input: Option<serde_json::Value>,
+    ) -> TrpcResult<serde_json::Value> {
+        let id = self.request_id.fetch_add(1, Ordering::SeqCst);
+        let timeout = self.timeouts.for_method(path);
+        send_request(self.connection.clone(), id, path, input, timeout)?.wait()
+    }
+}
+
+fn run_reader<R: BufRead>(mut reader: R, connection: Arc<Connection>) {
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("[SIDECAR] Read error: {}", e);
+                break;
+            }
+        }
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let message = match serde_json::from_str::<serde_json::Value>(&line) {
+            Ok(message) => message,
+            Err(e) => {
+                eprintln!("[SIDECAR] Parse error: {} - line: {}", e, line.trim());
+                continue;
+            }
+        };
+
+        if is_notification(&message) {
+            match serde_json::from_value::<SidecarNotification>(message) {
+                Ok(notification) => connection.notify(notification),
+                Err(e) => eprintln!("[SIDECAR] Invalid notification: {}", e),
+            }
+        } else {
+            match serde_json::from_value::<JsonRpcResponse>(message) {
+                Ok(response) => connection.dispatch(response),
+                Err(e) => eprintln!("[SIDECAR] Parse error: {} - line: {}", e, line.trim()),
+            }
+        }
+    }
+
+    connection.close("Sidecar closed connection");
+    eprintln!("[SIDECAR] Reader thread stopped");
+}
+
+/// Requests carry an `id`; notifications carry a `method` and no (or a null) `id`.
+fn is_notification(message: &serde_json::Value) -> bool {
+    message.get("method").is_some() && message.get("id").is_none_or(|id| id.is_null())
+}
+
+/// A sidecar process that has completed its handshake.
+struct Started {
+    child: Child,
+    stdin: ChildStdin,
+    stdout_reader: BufReader<ChildStdout>,
+    capabilities: SidecarCapabilities,
+    url: String,
+}
 
 pub struct Sidecar {
-    process: Option<Child>,
-    stdin: Option<ChildStdin>,
-    stdout_reader: Option<BufReader<ChildStdout>>,
-    request_id: AtomicU64,
-    trpc_url: Option<String>,
+    process: Mutex<Option<Child>>,
+    /// Held for a whole `start`, handshake included, so concurrent starts
+    /// spawn one process; `process` itself is only locked briefly.
+    start_lock: Mutex<()>,
+    connection: Mutex<Option<Arc<Connection>>>,
+    request_id: Arc<AtomicU64>,
+    trpc_url: Mutex<Option<String>>,
+    timeouts: Mutex<CallTimeouts>,
+    lifecycle: Mutex<Lifecycle>,
+    notification_handler: Mutex<Option<NotificationHandler>>,
+    status_handler: Mutex<Option<StatusHandler>>,
+    logs: Arc<SidecarLogBuffer>,
+    capabilities: Mutex<Option<SidecarCapabilities>>,
+    discovery_overrides: Mutex<DiscoveryOverrides>,
 }
 
 impl Sidecar {
     pub fn new() -> Self {
+        let mut timeouts = CallTimeouts::new(DEFAULT_CALL_TIMEOUT);
+        timeouts
+            .methods
+            .insert("system.ping".to_string(), PING_TIMEOUT);
+
         Self {
-            process: None,
-            stdin: None,
-            stdout_reader: None,
-            request_id: AtomicU64::new(1),
-            trpc_url: None,
+            process: Mutex::new(None),
+            start_lock: Mutex::new(()),
+            connection: Mutex::new(None),
+            request_id: Arc::new(AtomicU64::new(1)),
+            trpc_url: Mutex::new(None),
+            timeouts: Mutex::new(timeouts),
+            lifecycle: Mutex::new(Lifecycle {
+                health: SidecarHealth::Stopped,
+                last_error: None,
+                started_at: None,
+                restart_count: 0,
+                has_started: false,
+            }),
+            notification_handler: Mutex::new(None),
+            status_handler: Mutex::new(None),
+            logs: Arc::new(SidecarLogBuffer::new()),
+            capabilities: Mutex::new(None),
+            discovery_overrides: Mutex::new(DiscoveryOverrides::default()),
+        }
+    }
+
+    /// What the running sidecar advertised in its startup handshake.
+    pub fn capabilities(&self) -> Option<SidecarCapabilities> {
+        self.capabilities.lock().ok().and_then(|c| c.clone())
+    }
+
+    /// Path override from the setting or env var, used on the next (re)start.
+    pub fn set_discovery_overrides(&self, overrides: DiscoveryOverrides) {
+        if let Ok(mut current) = self.discovery_overrides.lock() {
+            *current = overrides;
+        }
+    }
+
+    /// Runs sidecar discovery without starting anything, for diagnostics.
+    pub fn discovery(&self) -> Discovery {
+        let overrides = self
+            .discovery_overrides
+            .lock()
+            .map(|o| o.clone())
+            .unwrap_or_default();
+        sidecar_discovery::discover(overrides)
+    }
+
+    pub fn logs(&self) -> &SidecarLogBuffer {
+        &self.logs
+    }
+
+    /// Receives notifications on the reader thread. Takes effect on the next
+    /// (re)start, so register it before the sidecar is first started.
+    pub fn set_notification_handler(&self, handler: NotificationHandler) {
+        if let Ok(mut notification_handler) = self.notification_handler.lock() {
+            *notification_handler = Some(handler);
         }
     }
 
-    pub fn start(&mut self) -> Result<(), String> {
-        if self.process.is_some() {
+    /// Called with the new status after every health transition, on whichever
+    /// thread made it (including `Starting` inside [`Sidecar::start`]).
+    pub fn set_status_handler(&self, handler: StatusHandler) {
+        if let Ok(mut status_handler) = self.status_handler.lock() {
+            *status_handler = Some(handler);
+        }
+    }
+
+    /// Must be called with no sidecar lock held: the handler reads `status`.
+    fn health_changed(&self) {
+        let handler = self
+            .status_handler
+            .lock()
+            .ok()
+            .and_then(|handler| handler.clone());
+        if let Some(handler) = handler {
+            handler(self.status());
+        }
+    }
+
+    /// Timeout used for methods without an entry in the per-method table.
+    pub fn set_default_timeout(&self, timeout: Duration) {
+        if let Ok(mut timeouts) = self.timeouts.lock() {
+            timeouts.default = timeout;
+        }
+    }
+
+    pub fn set_method_timeout(&self, method: &str, timeout: Duration) {
+        if let Ok(mut timeouts) = self.timeouts.lock() {
+            timeouts.methods.insert(method.to_string(), timeout);
+        }
+    }
+
+    pub fn timeout_for(&self, method: &str) -> Duration {
+        self.timeouts().for_method(method)
+    }
+
+    pub fn start(&self) -> Result<(), String> {
+        let _starting = self
+            .start_lock
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?;
+
+        if self.is_running() {
             return Ok(());
         }
 
-        let sidecar_info = get_sidecar_path()?;
+        self.set_health(SidecarHealth::Starting, None);
 
-        let mut child = if sidecar_info.is_compiled {
-            eprintln!("[SIDECAR] Starting compiled sidecar: {}", sidecar_info.path);
-            Command::new(&sidecar_info.path)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::inherit())
-                .spawn()
-                .map_err(|e| format!("Failed to spawn sidecar: {}", e))?
-        } else {
-            let bun_path = which::which("bun").map_err(|e| format!("Bun not found: {}", e))?;
-            eprintln!(
-                "[SIDECAR] Starting sidecar via bun: {} run {}",
-                bun_path.display(),
-                sidecar_info.path
-            );
-            Command::new(bun_path)
-                .arg("run")
-                .arg(&sidecar_info.path)
-                .stdin(Stdio::piped())
-                .stdout(Stdio::piped())
-                .stderr(Stdio::inherit())
-                .spawn()
-                .map_err(|e| format!("Failed to spawn sidecar: {}", e))?
-        };
+        match self.spawn() {
+            Ok(started) => {
+                let mut process = self
+                    .process
+                    .lock()
+                    .map_err(|e| format!("Lock error: {}", e))?;
+
+                // `stop` ran while the handshake was in flight.
+                if self.health() != SidecarHealth::Starting {
+                    let mut child = started.child;
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err("Sidecar stopped during startup".to_string());
+                }
+
+                *process = Some(self.connect(started)?);
+                drop(process);
+
+                if let Ok(mut lifecycle) = self.lifecycle.lock() {
+                    if lifecycle.has_started {
+                        lifecycle.restart_count += 1;
+                    }
+                    lifecycle.has_started = true;
+                    lifecycle.started_at = Some(Instant::now());
+                    lifecycle.health = SidecarHealth::Healthy;
+                }
+                self.health_changed();
+
+                eprintln!("[SIDECAR] Sidecar started successfully");
+                Ok(())
+            }
+            Err(e) => {
+                self.set_health(SidecarHealth::Crashed, Some(e.clone()));
+                Err(e)
+            }
+        }
+    }
+
+    /// Spawns the sidecar and waits for its handshake without holding
+    /// `process`.
+    fn spawn(&self) -> Result<Started, String> {
+        let launch = self.discovery().into_launch()?;
+        eprintln!("[SIDECAR] Starting sidecar: {}", launch.describe());
+
+        let mut child = launch
+            .command()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to spawn sidecar ({}): {}", launch.describe(), e))?;
 
         let stdin = child.stdin.take().ok_or("Failed to get stdin")?;
         let stdout = child.stdout.take().ok_or("Failed to get stdout")?;
         let stdout_reader = BufReader::new(stdout);
 
-        self.process = Some(child);
-        self.stdin = Some(stdin);
-        self.stdout_reader = Some(stdout_reader);
+        let (stderr_done_tx, stderr_done_rx) = mpsc::channel::<()>();
+        if let Some(stderr) = child.stderr.take() {
+            let logs = self.logs.clone();
+            thread::spawn(move || sidecar_logs::pump(BufReader::new(stderr), logs, stderr_done_tx));
+        }
+
+        let (capabilities, url, stdout_reader) = match wait_for_handshake(stdout_reader, STARTUP_TIMEOUT)
+            .and_then(|(handshake, reader)| {
+                let capabilities =
+                    sidecar_handshake::negotiate(&handshake, env!("CARGO_PKG_VERSION"))?;
+                Ok((capabilities, handshake.trpc_url, reader))
+            }) {
+            Ok(started) => started,
+            Err(e) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let _ = stderr_done_rx.recv_timeout(STDERR_DRAIN_TIMEOUT);
+                return Err(e);
+            }
+        };
 
-        self.wait_for_trpc_url()?;
+        eprintln!(
+            "[SIDECAR] Handshake ok: sidecar {} (protocol {}), tRPC URL {}",
+            capabilities.sidecar_version, capabilities.protocol_version, url
+        );
 
-        eprintln!("[SIDECAR] Sidecar started successfully");
-        Ok(())
+        Ok(Started {
+            child,
+            stdin,
+            stdout_reader,
+            capabilities,
+            url,
+        })
     }
 
-    fn wait_for_trpc_url(&mut self) -> Result<(), String> {
-        let reader = self
-            .stdout_reader
-            .as_mut()
-            .ok_or("No stdout reader available")?;
+    /// Starts reading a handshaken sidecar's stdout and publishes its
+    /// connection details.
+    fn connect(&self, started: Started) -> Result<Child, String> {
+        let Started {
+            child,
+            stdin,
+            stdout_reader,
+            capabilities,
+            url,
+        } = started;
 
-        let mut line = String::new();
-        reader
-            .read_line(&mut line)
-            .map_err(|e| format!("Read error waiting for tRPC URL: {}", e))?;
+        let notification_handler = self
+            .notification_handler
+            .lock()
+            .ok()
+            .and_then(|handler| handler.clone());
+        let connection = Arc::new(Connection::new(stdin, notification_handler));
+        let reader_connection = connection.clone();
+        thread::spawn(move || run_reader(stdout_reader, reader_connection));
 
-        if line.starts_with(TRPC_URL_PREFIX) {
-            let url = line.trim_start_matches(TRPC_URL_PREFIX).trim().to_string();
-            eprintln!("[SIDECAR] tRPC URL captured: {}", url);
-            self.trpc_url = Some(url);
-            Ok(())
-        } else {
-            Err(format!("Expected tRPC URL, got: {}", line.trim()))
+        *self
+            .connection
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))? = Some(connection);
+        *self
+            .trpc_url
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))? = Some(url);
+        *self
+            .capabilities
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))? = Some(capabilities);
+
+        Ok(child)
+    }
+
+    pub fn get_trpc_url(&self) -> Option<String> {
+        self.trpc_url.lock().ok().and_then(|url| url.clone())
+    }
+
+    /// tRPC client for the running sidecar, starting it first if needed.
+    ///
+    /// Uses HTTP unless `WORKOPILOT_SIDECAR_TRANSPORT=stdio` is set.
+    pub fn trpc(&self) -> Result<TrpcClient, String> {
+        if !self.is_running() {
+            self.start()?;
+        }
+
+        if std::env::var(TRANSPORT_ENV_VAR).is_ok_and(|transport| transport == "stdio") {
+            return Ok(TrpcClient::new(Arc::new(self.stdio_transport()?)));
         }
+
+        let url = self
+            .get_trpc_url()
+            .ok_or_else(|| "tRPC URL not available".to_string())?;
+        let transport = HttpTransport::new(&url)?.with_timeouts(self.timeouts());
+        Ok(TrpcClient::new(Arc::new(transport)))
     }
 
-    pub fn get_trpc_url(&self) -> Option<&str> {
-        self.trpc_url.as_deref()
+    fn timeouts(&self) -> CallTimeouts {
+        self.timeouts
+            .lock()
+            .map(|timeouts| timeouts.clone())
+            .unwrap_or_else(|_| CallTimeouts::new(DEFAULT_CALL_TIMEOUT))
     }
 
-    pub fn stop(&mut self) {
-        self.stdin = None;
-        self.stdout_reader = None;
-        self.trpc_url = None;
+    fn stdio_transport(&self) -> Result<StdioTransport, String> {
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?
+            .clone()
+            .ok_or("Sidecar not running")?;
 
-        if let Some(mut child) = self.process.take() {
-            let _ = child.kill();
-            let _ = child.wait();
-            eprintln!("[SIDECAR] Sidecar stopped");
+        Ok(StdioTransport {
+            connection,
+            request_id: self.request_id.clone(),
+            timeouts: self.timeouts(),
+        })
+    }
+
+    pub fn stop(&self) {
+        self.disconnect("Sidecar stopped");
+
+        if let Ok(mut process) = self.process.lock() {
+            if let Some(mut child) = process.take() {
+                let _ = child.kill();
+                let _ = child.wait();
+                eprintln!("[SIDECAR] Sidecar stopped");
+            }
+        }
+
+        if let Ok(mut lifecycle) = self.lifecycle.lock() {
+            lifecycle.health = SidecarHealth::Stopped;
+            lifecycle.started_at = None;
+        }
+        self.health_changed();
+    }
+
+    fn disconnect(&self, reason: &str) {
+        if let Ok(mut connection) = self.connection.lock() {
+            if let Some(connection) = connection.take() {
+                connection.close(reason);
+            }
+        }
+
+        if let Ok(mut url) = self.trpc_url.lock() {
+            *url = None;
+        }
+
+        if let Ok(mut capabilities) = self.capabilities.lock() {
+            *capabilities = None;
         }
     }
 
     pub fn call(
-        &mut self,
+        &self,
         method: &str,
         params: Option<serde_json::Value>,
-    ) -> Result<serde_json::Value, String> {
-        if self.process.is_none() {
-            self.start()?;
+    ) -> Result<serde_json::Value, SidecarError> {
+        self.call_with_timeout(method, params, self.timeout_for(method))
+    }
+
+    pub fn call_with_timeout(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        timeout: Duration,
+    ) -> Result<serde_json::Value, SidecarError> {
+        self.begin_call(method, params, timeout)?.wait()
+    }
+
+    /// Sends a request without waiting for it, so another thread can
+    /// [`Sidecar::cancel`] it by id while the caller blocks in `wait`.
+    pub fn begin_call(
+        &self,
+        method: &str,
+        params: Option<serde_json::Value>,
+        timeout: Duration,
+    ) -> Result<PendingCall, SidecarError> {
+        if !self.is_running() {
+            self.start().map_err(SidecarError::Transport)?;
+        }
+
+        if let Some(capabilities) = self.capabilities() {
+            if !capabilities.supports_method(method) && !capabilities.supports_procedure(method) {
+                return Err(SidecarError::Server {
+                    code: "METHOD_NOT_FOUND".to_string(),
+                    message: format!(
+                        "Sidecar {} does not support method: {}",
+                        capabilities.sidecar_version, method
+                    ),
+                });
+            }
         }
 
+        let connection = self
+            .connection
+            .lock()
+            .map_err(|e| format!("Lock error: {}", e))?
+            .clone()
+            .ok_or_else(|| SidecarError::Transport("Sidecar not running".to_string()))?;
+
         let id = self.request_id.fetch_add(1, Ordering::SeqCst);
+        send_request(connection, id, method, params, timeout)
+    }
 
-        let request = JsonRpcRequest {
-            jsonrpc: "2.0",
-            id,
-            method: method.to_string(),
-            params,
-        };
+    /// Fails an in-flight request with [`SidecarError::Cancelled`] and tells
+    /// the sidecar, which then sends no response for it. A procedure that
+    /// already started still runs to completion there.
+    pub fn cancel(&self, request_id: u64) -> bool {
+        let connection = self
+            .connection
+            .lock()
+            .ok()
+            .and_then(|connection| connection.clone());
 
-        let request_json =
-            serde_json::to_string(&request).map_err(|e| format!("Serialize error: {}", e))?;
+        connection
+            .map(|connection| connection.cancel(request_id))
+            .unwrap_or(false)
+    }
 
-        let stdin = self.stdin.as_mut().ok_or("No stdin available")?;
-        writeln!(stdin, "{}", request_json).map_err(|e| format!("Write error: {}", e))?;
-        stdin.flush().map_err(|e| format!("Flush error: {}", e))?;
+    /// Polls the child with `try_wait`, so a crashed process is reported
+    /// (and cleaned up) here rather than on the next failed write.
+    pub fn is_running(&self) -> bool {
+        let exit_reason = {
+            let Ok(mut process) = self.process.lock() else {
+                return false;
+            };
+            let Some(child) = process.as_mut() else {
+                return false;
+            };
 
-        let reader = self
-            .stdout_reader
-            .as_mut()
-            .ok_or("No stdout reader available")?;
-        let mut line = String::new();
-        reader
-            .read_line(&mut line)
-            .map_err(|e| format!("Read error: {}", e))?;
+            let exit_reason = match child.try_wait() {
+                Ok(None) => return true,
+                Ok(Some(status)) => format!("Sidecar exited ({})", status),
+                Err(e) => format!("Failed to poll sidecar process: {}", e),
+            };
+            *process = None;
+            exit_reason
+        };
 
-        if line.is_empty() {
-            return Err("Sidecar closed connection".to_string());
+        eprintln!("[SIDECAR] {}", exit_reason);
+        self.disconnect(&exit_reason);
+        if let Ok(mut lifecycle) = self.lifecycle.lock() {
+            lifecycle.health = SidecarHealth::Crashed;
+            lifecycle.last_error = Some(exit_reason);
+            lifecycle.started_at = None;
         }
+        self.health_changed();
+        false
+    }
 
-        let response: JsonRpcResponse = serde_json::from_str(&line)
-            .map_err(|e| format!("Parse error: {} - line: {}", e, line.trim()))?;
+    pub fn health(&self) -> SidecarHealth {
+        self.lifecycle
+            .lock()
+            .map(|lifecycle| lifecycle.health)
+            .unwrap_or(SidecarHealth::Crashed)
+    }
 
-        if let Some(error) = response.error {
-            return Err(format!("[{}] {}", error.code, error.message));
+    pub fn set_health(&self, health: SidecarHealth, error: Option<String>) {
+        let changed = match self.lifecycle.lock() {
+            Ok(mut lifecycle) => {
+                let changed = lifecycle.health != health
+                    || (error.is_some() && lifecycle.last_error != error);
+                lifecycle.health = health;
+                if error.is_some() {
+                    lifecycle.last_error = error;
+                }
+                changed
+            }
+            Err(_) => false,
+        };
+        if changed {
+            self.health_changed();
         }
-
-        response
-            .result
-            .ok_or_else(|| "No result in response".to_string())
     }
 
-    pub fn is_running(&self) -> bool {
-        self.process.is_some()
+    pub fn status(&self) -> SidecarStatus {
+        let running = self.is_running();
+
+        match self.lifecycle.lock() {
+            Ok(lifecycle) => SidecarStatus {
+                status: lifecycle.health,
+                running,
+                restart_count: lifecycle.restart_count,
+                uptime_secs: lifecycle.started_at.map(|t| t.elapsed().as_secs()),
+                last_error: lifecycle.last_error.clone(),
+            },
+            Err(e) => SidecarStatus {
+                status: SidecarHealth::Crashed,
+                running,
+                restart_count: 0,
+                uptime_secs: None,
+                last_error: Some(format!("Lock error: {}", e)),
+            },
+        }
     }
 }
 
@@ -196,77 +886,289 @@ impl Drop for Sidecar {
     }
 }
 
-struct SidecarInfo {
-    path: String,
-    is_compiled: bool,
+/// Reads the handshake line on a helper thread so a sidecar that never
+/// prints it cannot block startup forever. The reader is handed back on success.
+fn wait_for_handshake<R: BufRead + Send + 'static>(
+    reader: R,
+    timeout: Duration,
+) -> Result<(Handshake, R), String> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut reader = reader;
+        let result = read_handshake(&mut reader);
+        let _ = sender.send((result, reader));
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok((Ok(handshake), reader)) => Ok((handshake, reader)),
+        Ok((Err(e), _)) => Err(e),
+        Err(_) => Err(format!(
+            "Timed out after {}ms waiting for sidecar handshake",
+            timeout.as_millis()
+        )),
+    }
 }
 
-fn get_sidecar_path() -> Result<SidecarInfo, String> {
-    if cfg!(debug_assertions) {
-        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
-        if !manifest_dir.is_empty() {
-            let path = std::path::Path::new(&manifest_dir)
-                .parent()
-                .unwrap()
-                .join("packages/sidecar/src/index.ts");
-            if path.exists() {
-                return Ok(SidecarInfo {
-                    path: path.to_string_lossy().to_string(),
-                    is_compiled: false,
-                });
-            }
+fn read_handshake<R: BufRead>(reader: &mut R) -> Result<Handshake, String> {
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|e| format!("Read error waiting for sidecar handshake: {}", e))?;
+
+    sidecar_handshake::parse_handshake_line(&line)
+}
+
+pub struct SidecarState {
+    pub sidecar: Sidecar,
+    pub supervisor: Mutex<Option<SidecarSupervisor>>,
+}
+
+impl SidecarState {
+    pub fn new() -> Self {
+        Self {
+            sidecar: Sidecar::new(),
+            supervisor: Mutex::new(None),
         }
+    }
+}
 
-        let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
-        let dev_path = cwd.join("packages/sidecar/src/index.ts");
-        if dev_path.exists() {
-            return Ok(SidecarInfo {
-                path: dev_path.to_string_lossy().to_string(),
-                is_compiled: false,
-            });
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn test_connection() -> Arc<Connection> {
+        Arc::new(Connection {
+            stdin: Mutex::new(None),
+            pending: Mutex::new(HashMap::new()),
+            notification_handler: None,
+        })
+    }
+
+    fn register(connection: &Arc<Connection>, id: u64, timeout: Duration) -> PendingCall {
+        let (sender, receiver) = mpsc::channel();
+        connection.pending.lock().unwrap().insert(
+            id,
+            PendingRequest {
+                method: "tasks.getFull".to_string(),
+                sender,
+            },
+        );
+        PendingCall {
+            id,
+            method: "tasks.getFull".to_string(),
+            timeout,
+            receiver,
+            connection: connection.clone(),
         }
+    }
 
-        let tauri_dev_path = cwd
-            .parent()
-            .map(|p| p.join("packages/sidecar/src/index.ts"));
-        if let Some(path) = tauri_dev_path {
-            if path.exists() {
-                return Ok(SidecarInfo {
-                    path: path.to_string_lossy().to_string(),
-                    is_compiled: false,
-                });
-            }
+    struct StalledReader;
+
+    impl std::io::Read for StalledReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            thread::sleep(Duration::from_millis(500));
+            Ok(0)
         }
     }
 
-    let exe_path = std::env::current_exe().map_err(|e| e.to_string())?;
-    let exe_dir = exe_path.parent().ok_or("No exe dir")?;
+    #[test]
+    fn test_health_transitions_reach_status_handler() {
+        let sidecar = Sidecar::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorder = seen.clone();
+        sidecar.set_status_handler(Arc::new(move |status| {
+            recorder.lock().unwrap().push(status.status);
+        }));
 
-    let sidecar_name = if cfg!(target_os = "windows") {
-        "workopilot-sidecar.exe"
-    } else {
-        "workopilot-sidecar"
-    };
+        sidecar.set_health(SidecarHealth::Starting, None);
+        sidecar.set_health(SidecarHealth::Healthy, None);
+        sidecar.set_health(SidecarHealth::Healthy, None);
+        sidecar.stop();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            [
+                SidecarHealth::Starting,
+                SidecarHealth::Healthy,
+                SidecarHealth::Stopped
+            ]
+        );
+    }
+
+    #[test]
+    fn test_method_timeouts_override_default() {
+        let sidecar = Sidecar::new();
+        sidecar.set_method_timeout("tasks.listFull", Duration::from_secs(60));
+        let timeouts = sidecar.timeouts();
+
+        assert_eq!(timeouts.for_method("system.ping"), PING_TIMEOUT);
+        assert_eq!(timeouts.for_method("tasks.listFull"), Duration::from_secs(60));
+        assert_eq!(timeouts.for_method("tasks.list"), DEFAULT_CALL_TIMEOUT);
+    }
+
+    #[test]
+    fn test_reader_routes_responses_by_id() {
+        let connection = test_connection();
+        let timeout = Duration::from_secs(1);
+        let first = register(&connection, 1, timeout);
+        let second = register(&connection, 2, timeout);
+        let third = register(&connection, 3, timeout);
+
+        let output = concat!(
+            "{\"jsonrpc\":\"2.0\",\"id\":2,\"result\":\"two\"}\n",
+            "{\"jsonrpc\":\"2.0\",\"id\":3,\"error\":{\"code\":-32601,\"message\":\"Method not found\"}}\n",
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":\"one\"}\n",
+        );
+        run_reader(Cursor::new(output), connection.clone());
 
-    let prod_path = exe_dir.join(sidecar_name);
-    if prod_path.exists() {
-        return Ok(SidecarInfo {
-            path: prod_path.to_string_lossy().to_string(),
-            is_compiled: true,
+        assert_eq!(first.wait().unwrap(), "one");
+        assert_eq!(second.wait().unwrap(), "two");
+        let error = third.wait().unwrap_err();
+        assert_eq!(error.code(), "METHOD_NOT_FOUND");
+        assert_eq!(error.to_string(), "[METHOD_NOT_FOUND] Method not found");
+    }
+
+    #[test]
+    fn test_procedure_errors_keep_trpc_code() {
+        let connection = test_connection();
+        let timeout = Duration::from_secs(1);
+        let missing = register(&connection, 1, timeout);
+        let invalid = register(&connection, 2, timeout);
+
+        let output = concat!(
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"error\":{\"code\":-32603,\"message\":\"Task not found\",\"data\":{\"code\":\"NOT_FOUND\",\"httpStatus\":404}}}\n",
+            "{\"jsonrpc\":\"2.0\",\"id\":2,\"error\":{\"code\":-32603,\"message\":\"Invalid input\",\"data\":{\"code\":\"BAD_REQUEST\",\"httpStatus\":400}}}\n",
+        );
+        run_reader(Cursor::new(output), connection.clone());
+
+        assert_eq!(
+            missing.wait().unwrap_err(),
+            SidecarError::NotFound("Task not found".to_string())
+        );
+        assert_eq!(
+            invalid.wait().unwrap_err(),
+            SidecarError::Validation("Invalid input".to_string())
+        );
+    }
+
+    #[test]
+    fn test_reader_forwards_notifications_between_responses() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let sink = received.clone();
+        let connection = Arc::new(Connection {
+            stdin: Mutex::new(None),
+            pending: Mutex::new(HashMap::new()),
+            notification_handler: Some(Arc::new(move |notification| {
+                sink.lock().unwrap().push(notification);
+            })),
         });
+        let call = register(&connection, 1, Duration::from_secs(1));
+
+        let output = concat!(
+            "{\"jsonrpc\":\"2.0\",\"method\":\"entity.changed\",\"params\":{\"entity_type\":\"task\",\"entity_id\":\"t1\",\"operation\":\"update\"}}\n",
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"ok\":true}}\n",
+            "{\"jsonrpc\":\"2.0\",\"id\":null,\"method\":\"log\",\"params\":{\"message\":\"hi\"}}\n",
+        );
+        run_reader(Cursor::new(output), connection.clone());
+
+        assert_eq!(call.wait().unwrap(), serde_json::json!({ "ok": true }));
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0].method, "entity.changed");
+        assert_eq!(received[0].params["entity_id"], "t1");
+        assert_eq!(received[1].method, "log");
     }
 
-    Err("Sidecar not found".to_string())
-}
+    #[test]
+    fn test_reader_fails_pending_requests_on_eof() {
+        let connection = test_connection();
+        let orphan = register(&connection, 7, Duration::from_secs(1));
 
-pub struct SidecarState {
-    pub sidecar: Mutex<Sidecar>,
-}
+        run_reader(Cursor::new(""), connection.clone());
 
-impl SidecarState {
-    pub fn new() -> Self {
-        Self {
-            sidecar: Mutex::new(Sidecar::new()),
-        }
+        assert_eq!(
+            orphan.wait().unwrap_err(),
+            SidecarError::Transport("Sidecar closed connection".to_string())
+        );
+        assert!(connection.pending.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_timed_out_response_is_not_delivered_to_next_caller() {
+        let connection = test_connection();
+        let slow = register(&connection, 1, Duration::from_millis(10));
+
+        assert!(matches!(slow.wait(), Err(SidecarError::Timeout { .. })));
+        assert!(connection.pending.lock().unwrap().is_empty());
+
+        let next = register(&connection, 2, Duration::from_secs(1));
+        let output = concat!(
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"result\":\"late\"}\n",
+            "{\"jsonrpc\":\"2.0\",\"id\":2,\"result\":\"fresh\"}\n",
+        );
+        run_reader(Cursor::new(output), connection.clone());
+
+        assert_eq!(next.wait().unwrap(), "fresh");
+    }
+
+    #[test]
+    fn test_cancel_fails_pending_call() {
+        let connection = test_connection();
+        let call = register(&connection, 4, Duration::from_secs(1));
+
+        assert!(connection.cancel(call.id()));
+        assert!(!connection.cancel(call.id()));
+        assert_eq!(
+            call.wait().unwrap_err(),
+            SidecarError::Cancelled {
+                method: "tasks.getFull".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_cancel_is_sent_to_sidecar() {
+        let mut cat = std::process::Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let connection = Arc::new(Connection::new(cat.stdin.take().unwrap(), None));
+        let call = register(&connection, 5, Duration::from_secs(1));
+
+        assert!(connection.cancel(call.id()));
+        connection.close("done");
+
+        let mut line = String::new();
+        BufReader::new(cat.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let _ = cat.wait();
+        let sent: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(sent["method"], CANCEL_METHOD);
+        assert_eq!(sent["params"]["id"], 5);
+        assert!(sent.get("id").is_none());
+    }
+
+    #[test]
+    fn test_wait_for_handshake() {
+        let timeout = Duration::from_secs(1);
+
+        let ok = Cursor::new(
+            "WORKOPILOT_HANDSHAKE={\"protocol_version\":1,\"sidecar_version\":\"0.7.1\",\"trpc_url\":\"http://localhost:4321\"}\n",
+        );
+        let (handshake, _) = wait_for_handshake(ok, timeout).unwrap();
+        assert_eq!(handshake.trpc_url, "http://localhost:4321");
+
+        let bad = Cursor::new("{\"jsonrpc\":\"2.0\"}\n");
+        assert!(wait_for_handshake(bad, timeout).is_err());
+
+        let stalled = BufReader::new(StalledReader);
+        let err = wait_for_handshake(stalled, Duration::from_millis(20))
+            .map(|(handshake, _)| handshake.trpc_url)
+            .unwrap_err();
+        assert!(err.contains("Timed out"));
     }
 }
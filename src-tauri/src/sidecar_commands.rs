@@ -1,58 +1,79 @@
+use crate::sidecar::{SidecarError, SidecarStatus};
+use crate::sidecar_discovery::{self, Discovery, DiscoveryOverrides};
+use crate::sidecar_handshake::SidecarCapabilities;
+use crate::sidecar_logs::{LogLevel, SidecarLogLine};
 use crate::AppState;
 use serde_json::Value;
 use tauri::State;
 
 #[tauri::command]
-pub fn sidecar_call(
-    state: State<AppState>,
+pub async fn sidecar_call(
+    state: State<'_, AppState>,
     method: String,
     params: Option<Value>,
-) -> Result<Value, String> {
-    let mut sidecar = state
-        .sidecar
-        .sidecar
-        .lock()
-        .map_err(|e| format!("Lock error: {}", e))?;
+) -> Result<Value, SidecarError> {
+    state.sidecar.sidecar.call(&method, params)
+}
 
-    if !sidecar.is_running() {
-        sidecar.start()?;
-    }
+#[tauri::command]
+pub fn sidecar_status(state: State<AppState>) -> Result<SidecarStatus, String> {
+    Ok(state.sidecar.sidecar.status())
+}
 
-    sidecar.call(&method, params)
+const DEFAULT_LOG_LIMIT: usize = 200;
+const STARTUP_LOG_LINES: usize = 20;
+
+#[tauri::command]
+pub fn sidecar_restart(state: State<AppState>) -> Result<(), String> {
+    let sidecar = &state.sidecar.sidecar;
+    load_discovery_overrides(&state);
+    sidecar.stop();
+    sidecar.start().map_err(|e| {
+        let log = sidecar.logs().tail(STARTUP_LOG_LINES);
+        if log.is_empty() {
+            e
+        } else {
+            format!("{}\n\nSidecar log:\n{}", e, log)
+        }
+    })
 }
 
 #[tauri::command]
-pub fn sidecar_status(state: State<AppState>) -> Result<Value, String> {
-    let sidecar = state
+pub fn sidecar_logs(
+    state: State<AppState>,
+    level: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<SidecarLogLine>, String> {
+    let min_level = match level {
+        Some(level) => {
+            Some(LogLevel::from_str(&level).ok_or_else(|| format!("Invalid log level: {}", level))?)
+        }
+        None => None,
+    };
+
+    Ok(state
         .sidecar
         .sidecar
-        .lock()
-        .map_err(|e| format!("Lock error: {}", e))?;
-
-    Ok(serde_json::json!({
-        "running": sidecar.is_running()
-    }))
+        .logs()
+        .recent(min_level, limit.unwrap_or(DEFAULT_LOG_LIMIT)))
 }
 
 #[tauri::command]
-pub fn sidecar_restart(state: State<AppState>) -> Result<(), String> {
-    let mut sidecar = state
-        .sidecar
-        .sidecar
-        .lock()
-        .map_err(|e| format!("Lock error: {}", e))?;
+pub fn sidecar_capabilities(state: State<AppState>) -> Result<SidecarCapabilities, String> {
+    let sidecar = &state.sidecar.sidecar;
 
-    sidecar.stop();
-    sidecar.start()
+    if !sidecar.is_running() {
+        sidecar.start()?;
+    }
+
+    sidecar
+        .capabilities()
+        .ok_or_else(|| "Sidecar capabilities not available".to_string())
 }
 
 #[tauri::command]
 pub fn get_trpc_url(state: State<AppState>) -> Result<String, String> {
-    let mut sidecar = state
-        .sidecar
-        .sidecar
-        .lock()
-        .map_err(|e| format!("Lock error: {}", e))?;
+    let sidecar = &state.sidecar.sidecar;
 
     if !sidecar.is_running() {
         sidecar.start()?;
@@ -60,6 +81,25 @@ pub fn get_trpc_url(state: State<AppState>) -> Result<String, String> {
 
     sidecar
         .get_trpc_url()
-        .map(|s| s.to_string())
         .ok_or_else(|| "tRPC URL not available".to_string())
 }
+
+/// Every path tried to locate the sidecar, and what would be launched.
+#[tauri::command]
+pub fn sidecar_discovery(state: State<AppState>) -> Result<Discovery, String> {
+    load_discovery_overrides(&state);
+    Ok(state.sidecar.sidecar.discovery())
+}
+
+/// Reads the `sidecar_path` setting; the env var still wins.
+pub fn load_discovery_overrides(state: &AppState) {
+    let path = match state.db.lock() {
+        Ok(db) => db.get_setting(sidecar_discovery::PATH_SETTING).ok().flatten(),
+        Err(_) => None,
+    };
+
+    state
+        .sidecar
+        .sidecar
+        .set_discovery_overrides(DiscoveryOverrides::resolve(path));
+}
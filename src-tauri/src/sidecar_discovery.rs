@@ -0,0 +1,345 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const PATH_ENV_VAR: &str = "WORKOPILOT_SIDECAR_PATH";
+pub const PATH_SETTING: &str = "sidecar_path";
+
+const DEV_ENTRY: &str = "packages/sidecar/src/index.ts";
+const SCRIPT_EXTENSIONS: &[&str] = &["ts", "mts", "js", "mjs", "cjs"];
+/// Runs script entries. The sidecar uses `Bun.serve`, `Bun.stdin` and
+/// `bun:sqlite`, so Node and Deno are not an option.
+const BUN: &str = "bun";
+
+/// Sidecar path chosen by the user; the env var takes precedence over the setting.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiscoveryOverrides {
+    pub path: Option<String>,
+}
+
+impl DiscoveryOverrides {
+    pub fn resolve(setting_path: Option<String>) -> Self {
+        Self {
+            path: non_empty(std::env::var(PATH_ENV_VAR).ok()).or(non_empty(setting_path)),
+        }
+    }
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct DiscoveryAttempt {
+    pub source: String,
+    pub path: String,
+    pub result: String,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SidecarLaunch {
+    Compiled {
+        path: String,
+    },
+    Script {
+        bun_path: String,
+        entry: String,
+    },
+}
+
+impl SidecarLaunch {
+    pub fn command(&self) -> Command {
+        match self {
+            Self::Compiled { path } => Command::new(path),
+            Self::Script { bun_path, entry } => {
+                let mut command = Command::new(bun_path);
+                command.args(["run", entry]);
+                command
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Compiled { path } => format!("compiled sidecar {}", path),
+            Self::Script { bun_path, entry } => format!("{} run {}", bun_path, entry),
+        }
+    }
+}
+
+/// Outcome of a discovery run, including every path looked at.
+#[derive(Debug, Clone, Serialize)]
+pub struct Discovery {
+    pub launch: Option<SidecarLaunch>,
+    pub attempts: Vec<DiscoveryAttempt>,
+}
+
+impl Discovery {
+    pub fn into_launch(self) -> Result<SidecarLaunch, String> {
+        match self.launch {
+            Some(launch) => Ok(launch),
+            None => Err(format!(
+                "Sidecar not found. Set {} or the '{}' setting. Tried:\n{}",
+                PATH_ENV_VAR,
+                PATH_SETTING,
+                self.report()
+            )),
+        }
+    }
+
+    pub fn report(&self) -> String {
+        self.attempts
+            .iter()
+            .map(|a| format!("  [{}] {}: {}", a.source, a.path, a.result))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Where discovery looks, kept separate from the environment so it can be tested.
+pub struct SearchContext {
+    pub overrides: DiscoveryOverrides,
+    pub dev_dirs: Vec<(String, PathBuf)>,
+    pub exe_dir: Option<PathBuf>,
+    pub find_binary: fn(&str) -> Option<PathBuf>,
+}
+
+impl SearchContext {
+    pub fn from_env(overrides: DiscoveryOverrides) -> Self {
+        let mut dev_dirs = Vec::new();
+        if cfg!(debug_assertions) {
+            if let Ok(manifest_dir) = std::env::var("CARGO_MANIFEST_DIR") {
+                if let Some(root) = Path::new(&manifest_dir).parent() {
+                    dev_dirs.push(("manifest".to_string(), root.to_path_buf()));
+                }
+            }
+            if let Ok(cwd) = std::env::current_dir() {
+                dev_dirs.push(("cwd".to_string(), cwd.clone()));
+                if let Some(parent) = cwd.parent() {
+                    dev_dirs.push(("cwd-parent".to_string(), parent.to_path_buf()));
+                }
+            }
+        }
+
+        Self {
+            overrides,
+            dev_dirs,
+            exe_dir: std::env::current_exe()
+                .ok()
+                .and_then(|exe| exe.parent().map(Path::to_path_buf)),
+            find_binary: |name| which::which(name).ok(),
+        }
+    }
+}
+
+pub fn discover(overrides: DiscoveryOverrides) -> Discovery {
+    discover_in(&SearchContext::from_env(overrides))
+}
+
+pub fn discover_in(ctx: &SearchContext) -> Discovery {
+    let mut attempts = Vec::new();
+    let launch = find_launch(ctx, &mut attempts);
+    Discovery { launch, attempts }
+}
+
+fn find_launch(ctx: &SearchContext, attempts: &mut Vec<DiscoveryAttempt>) -> Option<SidecarLaunch> {
+    // An explicit path is authoritative: don't silently fall back to another sidecar.
+    if let Some(path) = ctx.overrides.path.as_deref() {
+        let path = PathBuf::from(path);
+        return try_candidate(ctx, "override", &path, attempts);
+    }
+
+    for (source, dir) in &ctx.dev_dirs {
+        if let Some(launch) = try_candidate(ctx, source, &dir.join(DEV_ENTRY), attempts) {
+            return Some(launch);
+        }
+    }
+
+    if let Some(exe_dir) = &ctx.exe_dir {
+        let path = exe_dir.join(compiled_name());
+        if let Some(launch) = try_candidate(ctx, "exe-dir", &path, attempts) {
+            return Some(launch);
+        }
+    }
+
+    match (ctx.find_binary)(compiled_name()) {
+        Some(path) => try_candidate(ctx, "PATH", &path, attempts),
+        None => {
+            attempts.push(attempt("PATH", compiled_name(), "not found"));
+            None
+        }
+    }
+}
+
+fn try_candidate(
+    ctx: &SearchContext,
+    source: &str,
+    path: &Path,
+    attempts: &mut Vec<DiscoveryAttempt>,
+) -> Option<SidecarLaunch> {
+    let display = path.to_string_lossy().to_string();
+
+    if !path.is_file() {
+        attempts.push(attempt(source, &display, "not found"));
+        return None;
+    }
+
+    if !is_script(path) {
+        attempts.push(attempt(source, &display, "found compiled sidecar"));
+        return Some(SidecarLaunch::Compiled { path: display });
+    }
+
+    match (ctx.find_binary)(BUN) {
+        Some(bun_path) => {
+            attempts.push(attempt(source, &display, "found script, running with bun"));
+            Some(SidecarLaunch::Script {
+                bun_path: bun_path.to_string_lossy().to_string(),
+                entry: display,
+            })
+        }
+        None => {
+            attempts.push(attempt("runtime", BUN, "not found on PATH"));
+            attempts.push(attempt(
+                source,
+                &display,
+                "found script but bun is not installed",
+            ));
+            None
+        }
+    }
+}
+
+fn attempt(source: &str, path: &str, result: &str) -> DiscoveryAttempt {
+    DiscoveryAttempt {
+        source: source.to_string(),
+        path: path.to_string(),
+        result: result.to_string(),
+    }
+}
+
+fn compiled_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "workopilot-sidecar.exe"
+    } else {
+        "workopilot-sidecar"
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+fn is_script(path: &Path) -> bool {
+    extension(path).is_some_and(|ext| SCRIPT_EXTENSIONS.contains(&ext.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn touch(path: &Path) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "").unwrap();
+    }
+
+    fn only_bun(name: &str) -> Option<PathBuf> {
+        (name == "bun").then(|| PathBuf::from("/usr/bin/bun"))
+    }
+
+    fn only_node(name: &str) -> Option<PathBuf> {
+        (name == "node").then(|| PathBuf::from("/usr/bin/node"))
+    }
+
+    fn nothing(_: &str) -> Option<PathBuf> {
+        None
+    }
+
+    fn context(root: &Path, overrides: DiscoveryOverrides) -> SearchContext {
+        SearchContext {
+            overrides,
+            dev_dirs: vec![("cwd".to_string(), root.join("repo"))],
+            exe_dir: Some(root.join("bin")),
+            find_binary: only_bun,
+        }
+    }
+
+    #[test]
+    fn test_dev_entry_runs_with_bun() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        touch(&root.join("repo").join(DEV_ENTRY));
+
+        let discovery = discover_in(&context(root, DiscoveryOverrides::default()));
+        let launch = discovery.launch.clone().unwrap();
+
+        assert_eq!(
+            launch.describe(),
+            format!(
+                "/usr/bin/bun run {}",
+                root.join("repo").join(DEV_ENTRY).display()
+            )
+        );
+    }
+
+    #[test]
+    fn test_script_is_not_run_without_bun() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        touch(&root.join("repo").join(DEV_ENTRY));
+        let mut ctx = context(root, DiscoveryOverrides::default());
+        ctx.find_binary = only_node;
+
+        let error = discover_in(&ctx).into_launch().unwrap_err();
+
+        assert!(error.contains("[runtime] bun: not found on PATH"));
+        assert!(error.contains("found script but bun is not installed"));
+    }
+
+    #[test]
+    fn test_override_is_authoritative() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        touch(&root.join("bin").join(compiled_name()));
+        let overrides = DiscoveryOverrides {
+            path: Some(root.join("custom/sidecar").to_string_lossy().to_string()),
+        };
+
+        let discovery = discover_in(&context(root, overrides));
+
+        assert!(discovery.launch.is_none());
+        assert_eq!(discovery.attempts.len(), 1);
+        assert_eq!(discovery.attempts[0].source, "override");
+    }
+
+    #[test]
+    fn test_error_lists_every_path_tried() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let mut ctx = context(root, DiscoveryOverrides::default());
+        ctx.find_binary = nothing;
+
+        let error = discover_in(&ctx).into_launch().unwrap_err();
+
+        assert!(error.contains(PATH_ENV_VAR));
+        assert!(error.contains(
+            &root
+                .join("repo")
+                .join(DEV_ENTRY)
+                .to_string_lossy()
+                .to_string()
+        ));
+        assert!(error.contains(
+            &root
+                .join("bin")
+                .join(compiled_name())
+                .to_string_lossy()
+                .to_string()
+        ));
+        assert!(error.contains("[PATH] workopilot-sidecar: not found"));
+    }
+}
@@ -0,0 +1,172 @@
+use serde::{Serialize, Serializer};
+use std::time::Duration;
+
+/// Error from any sidecar call, whatever the transport.
+///
+/// Serializes to `{ "code": "NOT_FOUND", "message": "..." }` so the frontend
+/// can branch on `code` instead of parsing the message.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SidecarError {
+    NotFound(String),
+    /// Input rejected by the sidecar (zod validation, bad params).
+    Validation(String),
+    Timeout {
+        method: String,
+        timeout: Duration,
+    },
+    Cancelled {
+        method: String,
+    },
+    /// The sidecar could not be reached: not running, connection closed, bad response.
+    Transport(String),
+    /// Any other error reported by the sidecar, keeping its tRPC code name.
+    Server {
+        code: String,
+        message: String,
+    },
+    /// Failure outside the sidecar in a command that also talks to it.
+    Failed(String),
+}
+
+impl SidecarError {
+    /// Maps a tRPC error code name (`NOT_FOUND`, `BAD_REQUEST`, ...) to a variant.
+    pub fn from_trpc_code(code: &str, message: String) -> Self {
+        match code {
+            "NOT_FOUND" => SidecarError::NotFound(message),
+            "BAD_REQUEST" | "PARSE_ERROR" | "UNPROCESSABLE_CONTENT" => {
+                SidecarError::Validation(message)
+            }
+            _ => SidecarError::Server {
+                code: code.to_string(),
+                message,
+            },
+        }
+    }
+
+    /// Maps a plain JSON-RPC error code from the stdio channel to a variant.
+    pub fn from_json_rpc_code(code: i32, message: String) -> Self {
+        match code {
+            -32700 | -32600 | -32602 => SidecarError::Validation(message),
+            -32601 => SidecarError::Server {
+                code: "METHOD_NOT_FOUND".to_string(),
+                message,
+            },
+            _ => SidecarError::Server {
+                code: "INTERNAL_SERVER_ERROR".to_string(),
+                message,
+            },
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        match self {
+            SidecarError::NotFound(_) => "NOT_FOUND",
+            SidecarError::Validation(_) => "VALIDATION",
+            SidecarError::Timeout { .. } => "TIMEOUT",
+            SidecarError::Cancelled { .. } => "CANCELLED",
+            SidecarError::Transport(_) => "TRANSPORT",
+            SidecarError::Server { code, .. } => code,
+            SidecarError::Failed(_) => "FAILED",
+        }
+    }
+}
+
+impl std::fmt::Display for SidecarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SidecarError::Timeout { method, timeout } => write!(
+                f,
+                "Sidecar call {} timed out after {}ms",
+                method,
+                timeout.as_millis()
+            ),
+            SidecarError::Cancelled { method } => write!(f, "Sidecar call {} was cancelled", method),
+            SidecarError::Server { code, message } => write!(f, "[{}] {}", code, message),
+            SidecarError::NotFound(message)
+            | SidecarError::Validation(message)
+            | SidecarError::Transport(message)
+            | SidecarError::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Serialize for SidecarError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Payload<'a> {
+            code: &'a str,
+            message: String,
+        }
+
+        let message = match self {
+            SidecarError::Server { message, .. } => message.clone(),
+            other => other.to_string(),
+        };
+
+        Payload {
+            code: self.code(),
+            message,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl From<String> for SidecarError {
+    fn from(message: String) -> Self {
+        SidecarError::Failed(message)
+    }
+}
+
+impl From<&str> for SidecarError {
+    fn from(message: &str) -> Self {
+        SidecarError::Failed(message.to_string())
+    }
+}
+
+impl From<SidecarError> for String {
+    fn from(error: SidecarError) -> Self {
+        error.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trpc_codes_map_to_variants() {
+        assert_eq!(
+            SidecarError::from_trpc_code("NOT_FOUND", "Task not found".to_string()),
+            SidecarError::NotFound("Task not found".to_string())
+        );
+        assert_eq!(
+            SidecarError::from_trpc_code("BAD_REQUEST", "Invalid input".to_string()).code(),
+            "VALIDATION"
+        );
+        assert_eq!(
+            SidecarError::from_trpc_code("CONFLICT", "Duplicate path".to_string()).code(),
+            "CONFLICT"
+        );
+    }
+
+    #[test]
+    fn test_serializes_code_and_message() {
+        let timeout = SidecarError::Timeout {
+            method: "tasks.getFull".to_string(),
+            timeout: Duration::from_millis(1500),
+        };
+        assert_eq!(
+            serde_json::to_value(&timeout).unwrap(),
+            serde_json::json!({
+                "code": "TIMEOUT",
+                "message": "Sidecar call tasks.getFull timed out after 1500ms"
+            })
+        );
+
+        let server = SidecarError::from_json_rpc_code(-32603, "boom".to_string());
+        assert_eq!(
+            serde_json::to_value(&server).unwrap(),
+            serde_json::json!({ "code": "INTERNAL_SERVER_ERROR", "message": "boom" })
+        );
+    }
+}
@@ -0,0 +1,50 @@
+use crate::app_events;
+use crate::db_change::DbChangeNotification;
+use crate::event_bus::AppEvent;
+use crate::ipc_socket;
+use crate::sidecar::SidecarNotification;
+use crate::AppState;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Routes a notification pushed by the sidecar to the matching Tauri event.
+pub fn forward_notification(app: &AppHandle, notification: SidecarNotification) {
+    match notification.method.as_str() {
+        "entity.changed" => {
+            match serde_json::from_value::<DbChangeNotification>(notification.params) {
+                Ok(change) => forward_entity_change(app, change),
+                Err(e) => eprintln!("[SIDECAR] Invalid entity.changed notification: {}", e),
+            }
+        }
+        "log" => emit(app, "sidecar-log", notification.params),
+        "progress" => emit(app, "sidecar-progress", notification.params),
+        _ => emit(app, "sidecar-notification", notification),
+    }
+}
+
+/// Queues an entity change on the socket server's coalescer (journaled there),
+/// or emits it directly when the server is not running.
+pub fn forward_entity_change(app: &AppHandle, mut change: DbChangeNotification) {
+    let state = app.state::<AppState>();
+    if let Ok(ipc_socket) = state.ipc_socket.lock() {
+        if let Some(server) = ipc_socket.as_ref() {
+            server.enqueue(change);
+            return;
+        }
+    }
+
+    // No socket server to debounce through; emit straight away.
+    ipc_socket::journal(app, &mut change);
+    let payload = change.payload();
+    app_events::emit(
+        app,
+        AppEvent::new("db-changed", &payload)
+            .entity(change.entity_type.as_str())
+            .project(change.project_id),
+    );
+}
+
+fn emit<S: serde::Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app.emit(event, payload) {
+        eprintln!("[SIDECAR] Failed to emit {} event: {}", event, e);
+    }
+}
@@ -0,0 +1,177 @@
+use serde::{Deserialize, Serialize};
+
+/// Must match `PROTOCOL_VERSION` in packages/sidecar/src/version.ts.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+const HANDSHAKE_PREFIX: &str = "WORKOPILOT_HANDSHAKE=";
+/// First line printed by sidecars built before the handshake existed.
+const LEGACY_TRPC_URL_PREFIX: &str = "TRPC_URL=";
+
+/// Stdio methods and tRPC procedures Rust calls directly; startup is refused without them.
+const REQUIRED_METHODS: &[&str] = &["system.ping"];
+const REQUIRED_PROCEDURES: &[&str] = &["projects.get", "tasks.getFull", "tasks.updateStatus"];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Handshake {
+    pub protocol_version: u32,
+    pub sidecar_version: String,
+    #[serde(default)]
+    pub runtime: String,
+    pub trpc_url: String,
+    #[serde(default)]
+    pub methods: Vec<String>,
+    #[serde(default)]
+    pub procedures: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SidecarCapabilities {
+    pub protocol_version: u32,
+    pub sidecar_version: String,
+    pub runtime: String,
+    pub methods: Vec<String>,
+    pub procedures: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl SidecarCapabilities {
+    pub fn supports_method(&self, method: &str) -> bool {
+        self.methods.iter().any(|m| m == method)
+    }
+
+    pub fn supports_procedure(&self, procedure: &str) -> bool {
+        self.procedures.iter().any(|p| p == procedure)
+    }
+}
+
+pub fn parse_handshake_line(line: &str) -> Result<Handshake, String> {
+    let line = line.trim();
+
+    if let Some(json) = line.strip_prefix(HANDSHAKE_PREFIX) {
+        serde_json::from_str(json).map_err(|e| format!("Invalid sidecar handshake: {}", e))
+    } else if line.starts_with(LEGACY_TRPC_URL_PREFIX) {
+        Err(format!(
+            "Sidecar is too old (no startup handshake, expected protocol {}). Rebuild workopilot-sidecar.",
+            PROTOCOL_VERSION
+        ))
+    } else {
+        Err(format!("Expected sidecar handshake, got: {}", line))
+    }
+}
+
+/// Refuses incompatible sidecars and records softer mismatches as warnings.
+pub fn negotiate(handshake: &Handshake, app_version: &str) -> Result<SidecarCapabilities, String> {
+    if handshake.protocol_version != PROTOCOL_VERSION {
+        return Err(format!(
+            "Sidecar protocol mismatch: sidecar {} speaks protocol {}, app expects {}. Rebuild workopilot-sidecar.",
+            handshake.sidecar_version, handshake.protocol_version, PROTOCOL_VERSION
+        ));
+    }
+
+    let missing: Vec<&str> = REQUIRED_METHODS
+        .iter()
+        .filter(|m| !handshake.methods.iter().any(|have| have == *m))
+        .chain(
+            REQUIRED_PROCEDURES
+                .iter()
+                .filter(|p| !handshake.procedures.iter().any(|have| have == *p)),
+        )
+        .copied()
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Sidecar {} is missing required methods: {}",
+            handshake.sidecar_version,
+            missing.join(", ")
+        ));
+    }
+
+    let mut warnings = Vec::new();
+    if handshake.sidecar_version != app_version {
+        warnings.push(format!(
+            "Sidecar version {} differs from app version {}",
+            handshake.sidecar_version, app_version
+        ));
+    }
+
+    for warning in &warnings {
+        eprintln!("[SIDECAR] Warning: {}", warning);
+    }
+
+    Ok(SidecarCapabilities {
+        protocol_version: handshake.protocol_version,
+        sidecar_version: handshake.sidecar_version.clone(),
+        runtime: handshake.runtime.clone(),
+        methods: handshake.methods.clone(),
+        procedures: handshake.procedures.clone(),
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handshake_line(protocol_version: u32, version: &str) -> String {
+        format!(
+            "{}{}",
+            HANDSHAKE_PREFIX,
+            serde_json::json!({
+                "protocol_version": protocol_version,
+                "sidecar_version": version,
+                "runtime": "bun",
+                "trpc_url": "http://localhost:4321",
+                "methods": ["system.ping", "system.version"],
+                "procedures": ["projects.get", "tasks.getFull", "tasks.updateStatus"],
+            })
+        )
+    }
+
+    #[test]
+    fn test_parse_handshake_line() {
+        let handshake = parse_handshake_line(&handshake_line(1, "0.7.1")).unwrap();
+        assert_eq!(handshake.trpc_url, "http://localhost:4321");
+        assert_eq!(handshake.methods.len(), 2);
+
+        let legacy = parse_handshake_line("TRPC_URL=http://localhost:4321\n").unwrap_err();
+        assert!(legacy.contains("too old"));
+
+        assert!(parse_handshake_line("{\"jsonrpc\":\"2.0\"}").is_err());
+    }
+
+    #[test]
+    fn test_negotiate_accepts_matching_sidecar() {
+        let handshake = parse_handshake_line(&handshake_line(PROTOCOL_VERSION, "0.7.1")).unwrap();
+        let capabilities = negotiate(&handshake, "0.7.1").unwrap();
+
+        assert!(capabilities.warnings.is_empty());
+        assert!(capabilities.supports_method("system.ping"));
+        assert!(!capabilities.supports_method("projects.get"));
+        assert!(capabilities.supports_procedure("tasks.getFull"));
+    }
+
+    #[test]
+    fn test_negotiate_warns_on_version_mismatch() {
+        let handshake = parse_handshake_line(&handshake_line(PROTOCOL_VERSION, "0.6.0")).unwrap();
+        let capabilities = negotiate(&handshake, "0.7.1").unwrap();
+
+        assert_eq!(capabilities.warnings.len(), 1);
+        assert!(capabilities.warnings[0].contains("0.6.0"));
+    }
+
+    #[test]
+    fn test_negotiate_refuses_incompatible_sidecar() {
+        let handshake =
+            parse_handshake_line(&handshake_line(PROTOCOL_VERSION + 1, "0.7.1")).unwrap();
+        assert!(negotiate(&handshake, "0.7.1")
+            .unwrap_err()
+            .contains("protocol mismatch"));
+
+        let mut handshake =
+            parse_handshake_line(&handshake_line(PROTOCOL_VERSION, "0.7.1")).unwrap();
+        handshake.procedures.retain(|p| p != "tasks.updateStatus");
+        assert!(negotiate(&handshake, "0.7.1")
+            .unwrap_err()
+            .contains("tasks.updateStatus"));
+    }
+}
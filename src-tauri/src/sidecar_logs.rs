@@ -0,0 +1,298 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+
+const BUFFER_CAPACITY: usize = 1000;
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+const MAX_ROTATED_FILES: usize = 3;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl LogLevel {
+    pub fn from_str(level: &str) -> Option<Self> {
+        match level.to_lowercase().as_str() {
+            "debug" => Some(Self::Debug),
+            "info" => Some(Self::Info),
+            "warn" | "warning" => Some(Self::Warn),
+            "error" => Some(Self::Error),
+            _ => None,
+        }
+    }
+
+    /// Splits off the `[level] ` the sidecar puts before every stderr line
+    /// (packages/sidecar/src/log.ts). Lines without one, such as runtime
+    /// crashes and stack traces, count as info.
+    fn parse(line: &str) -> (Self, &str) {
+        line.strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+            .and_then(|(level, message)| Some((Self::from_str(level)?, message)))
+            .unwrap_or((Self::Info, line))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SidecarLogLine {
+    pub timestamp: String,
+    pub level: LogLevel,
+    pub message: String,
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: Option<File>,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> Self {
+        let mut rotating = Self {
+            path,
+            file: None,
+            written: 0,
+        };
+        rotating.reopen();
+        rotating
+    }
+
+    fn reopen(&mut self) {
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        match OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+        {
+            Ok(file) => {
+                self.written = file.metadata().map(|m| m.len()).unwrap_or(0);
+                self.file = Some(file);
+            }
+            Err(e) => {
+                eprintln!(
+                    "[SIDECAR] Failed to open log file {}: {}",
+                    self.path.display(),
+                    e
+                );
+                self.file = None;
+            }
+        }
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) {
+        self.file = None;
+
+        for index in (1..MAX_ROTATED_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                let _ = fs::rename(&from, self.rotated_path(index + 1));
+            }
+        }
+        let _ = fs::rename(&self.path, self.rotated_path(1));
+
+        self.reopen();
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.written >= MAX_FILE_BYTES {
+            self.rotate();
+        }
+
+        if let Some(file) = self.file.as_mut() {
+            if writeln!(file, "{}", line).is_ok() {
+                self.written += line.len() as u64 + 1;
+            }
+        }
+    }
+}
+
+/// Recent sidecar stderr lines, kept in memory and mirrored to `sidecar.log`.
+pub struct SidecarLogBuffer {
+    lines: Mutex<VecDeque<SidecarLogLine>>,
+    capacity: usize,
+    file: Mutex<Option<RotatingFile>>,
+}
+
+impl SidecarLogBuffer {
+    pub fn new() -> Self {
+        Self::with_file(BUFFER_CAPACITY, Some(Self::get_log_path()))
+    }
+
+    fn with_file(capacity: usize, path: Option<PathBuf>) -> Self {
+        Self {
+            lines: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity,
+            file: Mutex::new(path.map(RotatingFile::open)),
+        }
+    }
+
+    fn get_log_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("workopilot")
+            .join("logs")
+            .join("sidecar.log")
+    }
+
+    pub fn push(&self, line: &str) {
+        let (level, message) = LogLevel::parse(line);
+        let entry = SidecarLogLine {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            level,
+            message: message.to_string(),
+        };
+
+        if let Ok(mut file) = self.file.lock() {
+            if let Some(file) = file.as_mut() {
+                file.write_line(&format!("{} {}", entry.timestamp, line));
+            }
+        }
+
+        if let Ok(mut lines) = self.lines.lock() {
+            if lines.len() >= self.capacity {
+                lines.pop_front();
+            }
+            lines.push_back(entry);
+        }
+    }
+
+    /// Newest `limit` lines at or above `min_level`, oldest first.
+    pub fn recent(&self, min_level: Option<LogLevel>, limit: usize) -> Vec<SidecarLogLine> {
+        let lines = match self.lines.lock() {
+            Ok(lines) => lines,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut matching: Vec<SidecarLogLine> = lines
+            .iter()
+            .rev()
+            .filter(|line| min_level.is_none_or(|level| line.level >= level))
+            .take(limit)
+            .cloned()
+            .collect();
+        matching.reverse();
+        matching
+    }
+
+    /// Last `limit` lines as plain text, for attaching to error messages.
+    pub fn tail(&self, limit: usize) -> String {
+        self.recent(None, limit)
+            .into_iter()
+            .map(|line| line.message)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Default for SidecarLogBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Copies sidecar stderr into the buffer until EOF. `done` is dropped on exit
+/// so callers can wait for the last lines of a failed startup.
+pub fn pump<R: BufRead>(reader: R, buffer: Arc<SidecarLogBuffer>, done: Sender<()>) {
+    for line in reader.lines() {
+        match line {
+            Ok(line) => {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                eprintln!("{}", line);
+                buffer.push(&line);
+            }
+            Err(e) => {
+                eprintln!("[SIDECAR] Error reading sidecar stderr: {}", e);
+                break;
+            }
+        }
+    }
+
+    drop(done);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_buffer_keeps_only_newest_lines() {
+        let buffer = SidecarLogBuffer::with_file(3, None);
+        for i in 0..5 {
+            buffer.push(&format!("[SIDECAR] line {}", i));
+        }
+
+        let lines = buffer.recent(None, 10);
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].message, "[SIDECAR] line 2");
+        assert_eq!(lines[2].message, "[SIDECAR] line 4");
+    }
+
+    #[test]
+    fn test_recent_filters_by_level() {
+        let buffer = SidecarLogBuffer::with_file(10, None);
+        buffer.push("[info] [SIDECAR] Starting WorkoPilot sidecar...");
+        buffer.push("[error] [TRPC] Error in tasks.get: not found");
+        buffer.push("[warn] [SIDECAR] stdin read error (non-fatal)");
+        buffer.push("[info] [SIDECAR] Migrated with 0 errors, retry failed=false");
+        buffer.push("[error] [SIDECAR] Failed to initialize SDK");
+        buffer.push("    at main (index.ts:12:3)");
+
+        let warnings = buffer.recent(Some(LogLevel::Warn), 10);
+        assert_eq!(warnings.len(), 3);
+
+        let errors = buffer.recent(Some(LogLevel::Error), 1);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "[SIDECAR] Failed to initialize SDK");
+
+        let lines = buffer.recent(None, 10);
+        assert_eq!(lines[3].level, LogLevel::Info);
+        assert_eq!(lines[5].level, LogLevel::Info);
+        assert_eq!(lines[5].message, "    at main (index.ts:12:3)");
+    }
+
+    #[test]
+    fn test_pump_reads_until_eof_and_signals_done() {
+        let buffer = Arc::new(SidecarLogBuffer::with_file(10, None));
+        let (done_tx, done_rx) = mpsc::channel();
+
+        pump(Cursor::new("first\n\nsecond\n"), buffer.clone(), done_tx);
+
+        assert!(done_rx.recv().is_err());
+        assert_eq!(buffer.tail(10), "first\nsecond");
+    }
+
+    #[test]
+    fn test_log_file_rotates() {
+        let dir = tempfile::tempdir().unwrap();
+        // Not created yet: the file makes its own parent directory.
+        let path = dir.path().join("logs").join("sidecar.log");
+        let mut file = RotatingFile::open(path.clone());
+        file.written = MAX_FILE_BYTES;
+
+        file.write_line("after rotation");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "after rotation\n");
+        assert!(file.rotated_path(1).exists());
+    }
+}
@@ -0,0 +1,238 @@
+use crate::sidecar::{SidecarHealth, SidecarStatus, StatusHandler};
+use crate::tray;
+use crate::AppState;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+const PING_INTERVAL: Duration = Duration::from_secs(5);
+const MAX_FAILED_PINGS: u32 = 3;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Exponential restart delay, reset once the sidecar answers a ping again.
+#[derive(Debug)]
+struct Backoff {
+    initial: Duration,
+    max: Duration,
+    current: Duration,
+}
+
+impl Backoff {
+    fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            current: initial,
+        }
+    }
+
+    fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(self.max);
+        delay
+    }
+
+    fn reset(&mut self) {
+        self.current = self.initial;
+    }
+}
+
+/// Emits `sidecar-status` and updates the tray whenever the reported state
+/// changes; installed with [`crate::sidecar::Sidecar::set_status_handler`].
+pub fn status_handler(app_handle: AppHandle) -> StatusHandler {
+    let last_emitted: Mutex<Option<SidecarStatus>> = Mutex::new(None);
+
+    Arc::new(move |status| {
+        let Ok(mut last_emitted) = last_emitted.lock() else {
+            return;
+        };
+        if !SidecarSupervisor::status_changed(last_emitted.as_ref(), &status) {
+            return;
+        }
+        if let Err(e) = app_handle.emit("sidecar-status", &status) {
+            eprintln!("[SIDECAR] Failed to emit sidecar-status event: {}", e);
+        }
+        tray::set_sidecar_status(&app_handle, &status);
+        *last_emitted = Some(status);
+    })
+}
+
+/// Watches the sidecar process: detects crashes with `try_wait`, pings
+/// `system.ping` on an interval and restarts with backoff. Status changes
+/// reach the frontend through [`status_handler`].
+pub struct SidecarSupervisor {
+    shutdown_tx: Option<Sender<()>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl SidecarSupervisor {
+    pub fn start(app_handle: AppHandle) -> Self {
+        let (shutdown_tx, shutdown_rx) = mpsc::channel();
+
+        let thread = thread::spawn(move || {
+            Self::run(app_handle, shutdown_rx);
+        });
+
+        eprintln!("[SIDECAR] Supervisor started");
+
+        Self {
+            shutdown_tx: Some(shutdown_tx),
+            thread: Some(thread),
+        }
+    }
+
+    fn run(app_handle: AppHandle, shutdown_rx: Receiver<()>) {
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        let mut failed_pings = 0u32;
+        let mut next_restart: Option<Instant> = None;
+
+        loop {
+            let wait = next_restart
+                .map(|at| at.saturating_duration_since(Instant::now()).min(PING_INTERVAL))
+                .unwrap_or(PING_INTERVAL);
+
+            match shutdown_rx.recv_timeout(wait) {
+                Err(RecvTimeoutError::Timeout) => {}
+                _ => break,
+            }
+
+            let state = app_handle.state::<AppState>();
+            let sidecar = &state.sidecar.sidecar;
+
+            if sidecar.is_running() {
+                next_restart = None;
+
+                match sidecar.call("system.ping", None) {
+                    Ok(_) => {
+                        failed_pings = 0;
+                        backoff.reset();
+                        sidecar.set_health(SidecarHealth::Healthy, None);
+                    }
+                    Err(e) => {
+                        failed_pings += 1;
+                        eprintln!(
+                            "[SIDECAR] Health ping failed ({}/{}): {}",
+                            failed_pings, MAX_FAILED_PINGS, e
+                        );
+
+                        if failed_pings >= MAX_FAILED_PINGS {
+                            failed_pings = 0;
+                            sidecar.stop();
+                            sidecar.set_health(
+                                SidecarHealth::Crashed,
+                                Some(format!("Sidecar unresponsive: {}", e)),
+                            );
+                        } else {
+                            sidecar.set_health(SidecarHealth::Degraded, Some(e.to_string()));
+                        }
+                    }
+                }
+            }
+
+            if sidecar.health() == SidecarHealth::Crashed {
+                match next_restart {
+                    None => {
+                        let delay = backoff.next_delay();
+                        eprintln!(
+                            "[SIDECAR] Sidecar crashed, restarting in {}ms",
+                            delay.as_millis()
+                        );
+                        next_restart = Some(Instant::now() + delay);
+                    }
+                    Some(at) if Instant::now() >= at => match sidecar.start() {
+                        Ok(_) => {
+                            eprintln!("[SIDECAR] Sidecar restarted by supervisor");
+                            next_restart = None;
+                        }
+                        Err(e) => {
+                            let delay = backoff.next_delay();
+                            eprintln!(
+                                "[SIDECAR] Restart failed: {} (retrying in {}ms)",
+                                e,
+                                delay.as_millis()
+                            );
+                            next_restart = Some(Instant::now() + delay);
+                        }
+                    },
+                    Some(_) => {}
+                }
+            }
+        }
+
+        eprintln!("[SIDECAR] Supervisor stopped");
+    }
+
+    /// Uptime is left out on purpose, otherwise every tick would emit.
+    fn status_changed(previous: Option<&SidecarStatus>, current: &SidecarStatus) -> bool {
+        match previous {
+            None => true,
+            Some(previous) => {
+                previous.status != current.status
+                    || previous.running != current.running
+                    || previous.restart_count != current.restart_count
+                    || previous.last_error != current.last_error
+            }
+        }
+    }
+
+    pub fn shutdown(&mut self) {
+        self.shutdown_tx = None;
+
+        if let Some(handle) = self.thread.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for SidecarSupervisor {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(health: SidecarHealth, uptime_secs: Option<u64>) -> SidecarStatus {
+        SidecarStatus {
+            status: health,
+            running: health == SidecarHealth::Healthy,
+            restart_count: 0,
+            uptime_secs,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_until_max_and_resets() {
+        let mut backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(2));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(4));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+        assert_eq!(backoff.next_delay(), Duration::from_secs(5));
+
+        backoff.reset();
+        assert_eq!(backoff.next_delay(), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_status_changed_ignores_uptime() {
+        let healthy = status(SidecarHealth::Healthy, Some(10));
+
+        assert!(SidecarSupervisor::status_changed(None, &healthy));
+        assert!(!SidecarSupervisor::status_changed(
+            Some(&healthy),
+            &status(SidecarHealth::Healthy, Some(15))
+        ));
+        assert!(SidecarSupervisor::status_changed(
+            Some(&healthy),
+            &status(SidecarHealth::Degraded, Some(15))
+        ));
+    }
+}
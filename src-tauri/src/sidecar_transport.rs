@@ -0,0 +1,118 @@
+use crate::trpc_client::TrpcResult;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallKind {
+    Query,
+    Mutation,
+}
+
+/// Timeout for each procedure path, falling back to `default`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallTimeouts {
+    pub default: Duration,
+    pub methods: HashMap<String, Duration>,
+}
+
+impl CallTimeouts {
+    pub fn new(default: Duration) -> Self {
+        Self {
+            default,
+            methods: HashMap::new(),
+        }
+    }
+
+    pub fn for_method(&self, method: &str) -> Duration {
+        self.methods.get(method).copied().unwrap_or(self.default)
+    }
+}
+
+/// How [`crate::trpc_client::TrpcClient`] reaches a tRPC procedure.
+///
+/// Implemented over HTTP (`trpc_client::HttpTransport`), over the stdio
+/// JSON-RPC channel (`sidecar::StdioTransport`), and in memory for tests.
+pub trait SidecarTransport: Send + Sync {
+    fn call(&self, kind: CallKind, path: &str, input: Option<Value>) -> TrpcResult<Value>;
+}
+
+#[cfg(test)]
+pub use in_memory::InMemoryTransport;
+
+#[cfg(test)]
+mod in_memory {
+    use super::{CallKind, SidecarTransport};
+    use crate::sidecar_error::SidecarError;
+    use crate::trpc_client::TrpcResult;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    type Responder = Box<dyn Fn(Option<&Value>) -> TrpcResult<Value> + Send + Sync>;
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct RecordedCall {
+        pub kind: CallKind,
+        pub path: String,
+        pub input: Option<Value>,
+    }
+
+    /// Scripted in-process sidecar: answers from registered responders and
+    /// records every call it receives.
+    #[derive(Default)]
+    pub struct InMemoryTransport {
+        responders: Mutex<HashMap<String, Responder>>,
+        calls: Mutex<Vec<RecordedCall>>,
+    }
+
+    impl InMemoryTransport {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        pub fn respond(self, path: &str, result: Value) -> Self {
+            self.respond_with(path, move |_| Ok(result.clone()))
+        }
+
+        pub fn respond_with<F>(self, path: &str, responder: F) -> Self
+        where
+            F: Fn(Option<&Value>) -> TrpcResult<Value> + Send + Sync + 'static,
+        {
+            self.responders
+                .lock()
+                .unwrap()
+                .insert(path.to_string(), Box::new(responder));
+            self
+        }
+
+        pub fn calls(&self) -> Vec<RecordedCall> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        pub fn calls_to(&self, path: &str) -> Vec<RecordedCall> {
+            self.calls()
+                .into_iter()
+                .filter(|call| call.path == path)
+                .collect()
+        }
+    }
+
+    impl SidecarTransport for InMemoryTransport {
+        fn call(&self, kind: CallKind, path: &str, input: Option<Value>) -> TrpcResult<Value> {
+            self.calls.lock().unwrap().push(RecordedCall {
+                kind,
+                path: path.to_string(),
+                input: input.clone(),
+            });
+
+            match self.responders.lock().unwrap().get(path) {
+                Some(responder) => responder(input.as_ref()),
+                None => Err(SidecarError::Server {
+                    code: "METHOD_NOT_FOUND".to_string(),
+                    message: format!("No procedure on path \"{}\"", path),
+                }),
+            }
+        }
+    }
+}
@@ -0,0 +1,292 @@
+use crate::ipc_paths;
+use crate::ipc_protocol::PROTOCOL_VERSION;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const FORWARD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Arguments a launch can hand to the running instance:
+/// `workopilot [--project <id>] [--task <id>] [--subtask <id>] [--action <action>]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct LaunchArgs {
+    pub project_id: Option<String>,
+    pub task_id: Option<String>,
+    pub subtask_id: Option<String>,
+    pub action: Option<String>,
+    /// Anything not recognised above, passed through untouched.
+    #[serde(default)]
+    pub extra: Vec<String>,
+}
+
+impl LaunchArgs {
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Self {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+                None => (arg.clone(), None),
+            };
+
+            let slot = match flag.as_str() {
+                "--project" => &mut parsed.project_id,
+                "--task" => &mut parsed.task_id,
+                "--subtask" => &mut parsed.subtask_id,
+                "--action" => &mut parsed.action,
+                _ => {
+                    parsed.extra.push(arg);
+                    continue;
+                }
+            };
+            *slot = inline.or_else(|| args.next());
+        }
+
+        parsed
+    }
+}
+
+/// What happened when looking for an already running instance.
+#[derive(Debug, PartialEq)]
+pub enum Forwarded {
+    /// No live instance; this process should start normally.
+    NoInstance,
+    /// The running instance took the arguments; this process should exit.
+    Delivered,
+}
+
+/// Outcome of [`claim`].
+#[derive(Debug)]
+pub enum Claim {
+    /// This process owns the socket and is the running instance.
+    Bound(UnixListener),
+    /// Another instance took the arguments; this process should exit.
+    Forwarded,
+}
+
+/// Becomes the instance listening on `socket_path`, or hands `args` to the
+/// one that already is. Runs under a lock file, so two launches at once
+/// cannot both decide there is no instance, and a stale socket file is only
+/// removed once nobody answers on it.
+pub fn claim(socket_path: &Path, args: &[String]) -> Result<Claim, String> {
+    let runtime_dir = ipc_paths::runtime_dir();
+    if socket_path.parent() == Some(runtime_dir.as_path()) {
+        ipc_paths::prepare_private_dir(&runtime_dir)?;
+    }
+    let _lock = lock(socket_path)?;
+
+    if forward_to_running_instance(socket_path, args)? == Forwarded::Delivered {
+        return Ok(Claim::Forwarded);
+    }
+
+    if socket_path.exists() {
+        std::fs::remove_file(socket_path)
+            .map_err(|e| format!("Failed to remove stale socket: {}", e))?;
+    }
+
+    match ipc_paths::bind_private(socket_path) {
+        Ok(listener) => Ok(Claim::Bound(listener)),
+        // An instance that does not take the lock bound in the meantime.
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+            match forward_to_running_instance(socket_path, args)? {
+                Forwarded::Delivered => Ok(Claim::Forwarded),
+                Forwarded::NoInstance => Err(format!(
+                    "Socket {} appeared but nobody answers on it",
+                    socket_path.display()
+                )),
+            }
+        }
+        Err(e) => Err(format!(
+            "Failed to bind socket at {}: {}",
+            socket_path.display(),
+            e
+        )),
+    }
+}
+
+/// Exclusive flock on `<socket_path>.lock`, released when the file is dropped.
+fn lock(socket_path: &Path) -> Result<File, String> {
+    let mut path = PathBuf::from(socket_path).into_os_string();
+    path.push(".lock");
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|e| format!("Failed to open {}: {}", Path::new(&path).display(), e))?;
+
+    // SAFETY: the fd belongs to `file`, which outlives the call.
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } != 0 {
+        return Err(format!(
+            "Failed to lock {}: {}",
+            Path::new(&path).display(),
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(file)
+}
+
+/// Hands `args` to the instance listening on `socket_path`, if there is one,
+/// which also shows its window. A socket file nobody listens on is left for
+/// [`claim`] to clean up.
+pub fn forward_to_running_instance(
+    socket_path: &Path,
+    args: &[String],
+) -> Result<Forwarded, String> {
+    let stream = match UnixStream::connect(socket_path) {
+        Ok(stream) => stream,
+        Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+            return Ok(Forwarded::NoInstance)
+        }
+        Err(e) => {
+            return Err(format!(
+                "Failed to connect to {}: {}",
+                socket_path.display(),
+                e
+            ))
+        }
+    };
+
+    if ipc_paths::peer_uid(&stream).map_err(|e| e.to_string())? != ipc_paths::current_uid() {
+        return Err(format!(
+            "Socket {} belongs to another user",
+            socket_path.display()
+        ));
+    }
+
+    let _ = stream.set_read_timeout(Some(FORWARD_TIMEOUT));
+    let mut writer = stream
+        .try_clone()
+        .map_err(|e| format!("Failed to clone socket stream: {}", e))?;
+
+    let request = serde_json::json!({
+        "v": PROTOCOL_VERSION,
+        "id": 1,
+        "method": "app.activate",
+        "params": { "args": args },
+    });
+    writeln!(writer, "{}", request).map_err(|e| format!("Failed to forward arguments: {}", e))?;
+
+    let mut line = String::new();
+    BufReader::new(stream)
+        .read_line(&mut line)
+        .map_err(|e| format!("No reply from running instance: {}", e))?;
+
+    let reply: serde_json::Value = serde_json::from_str(&line)
+        .map_err(|e| format!("Invalid reply from running instance: {}", e))?;
+    // Any reply means a live instance owns the socket, even if it rejected the arguments.
+    if reply["ok"].as_bool() != Some(true) {
+        eprintln!(
+            "[WORKOPILOT] Running instance rejected forwarded arguments: {}",
+            reply["error"]["message"]
+                .as_str()
+                .unwrap_or("unknown error")
+        );
+    }
+    Ok(Forwarded::Delivered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_launch_args() {
+        let parsed = LaunchArgs::parse(args(&[
+            "--project",
+            "p1",
+            "--task=t1",
+            "--action",
+            "execute_all",
+            "--verbose",
+        ]));
+
+        assert_eq!(parsed.project_id.as_deref(), Some("p1"));
+        assert_eq!(parsed.task_id.as_deref(), Some("t1"));
+        assert_eq!(parsed.action.as_deref(), Some("execute_all"));
+        assert_eq!(parsed.subtask_id, None);
+        assert_eq!(parsed.extra, args(&["--verbose"]));
+    }
+
+    #[test]
+    fn test_forward_without_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("missing.sock");
+
+        assert_eq!(
+            forward_to_running_instance(&socket_path, &[]).unwrap(),
+            Forwarded::NoInstance
+        );
+
+        // A stale socket file left by a crashed instance.
+        drop(UnixListener::bind(&socket_path).unwrap());
+        assert_eq!(
+            forward_to_running_instance(&socket_path, &[]).unwrap(),
+            Forwarded::NoInstance
+        );
+    }
+
+    #[test]
+    fn test_claim_replaces_stale_socket_and_defers_to_live_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("instance.sock");
+        drop(UnixListener::bind(&socket_path).unwrap());
+
+        let Claim::Bound(listener) = claim(&socket_path, &[]).unwrap() else {
+            panic!("stale socket was not replaced");
+        };
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            writeln!(writer, r#"{{"v":1,"id":1,"ok":true,"result":null}}"#).unwrap();
+        });
+
+        let second = claim(&socket_path, &args(&["--task", "t1"])).unwrap();
+        server.join().unwrap();
+
+        assert!(matches!(second, Claim::Forwarded));
+        assert!(socket_path.exists());
+    }
+
+    #[test]
+    fn test_forward_to_live_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let socket_path = dir.path().join("live.sock");
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            writeln!(writer, r#"{{"v":1,"id":1,"ok":true,"result":null}}"#).unwrap();
+            serde_json::from_str::<serde_json::Value>(&line).unwrap()
+        });
+
+        let result = forward_to_running_instance(&socket_path, &args(&["--task", "t1"])).unwrap();
+        let request = server.join().unwrap();
+
+        assert_eq!(result, Forwarded::Delivered);
+        assert_eq!(request["method"], "app.activate");
+        assert_eq!(
+            request["params"]["args"],
+            serde_json::json!(["--task", "t1"])
+        );
+    }
+}
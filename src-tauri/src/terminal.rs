@@ -1,8 +1,19 @@
 use crate::commands::{Project, TaskFull};
+use crate::focus_provider::{self, FocusStatus, FocusTarget};
+use crate::ipc_paths;
+use crate::sidecar::SidecarError;
+use crate::multiplexer::{self, Multiplexer, TabSpec};
+use crate::readiness::{self, Stage};
+use crate::shell_script::{command_line, ShellScript};
+use crate::terminal_emulator::{self, BackendInfo, TerminalEmulator};
+use crate::trpc_client::TrpcClient;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use tauri::State;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, State};
 
 pub const SESSION_NAME: &str = "workopilot";
 
@@ -33,39 +44,16 @@ impl TerminalAction {
     }
 }
 
-macro_rules! sidecar_call {
-    ($state:expr, $method:expr) => {{
-        let mut sidecar = $state.sidecar.sidecar.lock().map_err(|e| e.to_string())?;
-        if !sidecar.is_running() {
-            sidecar.start()?;
-        }
-        sidecar.call($method, None)
-    }};
-    ($state:expr, $method:expr, $params:expr) => {{
-        let mut sidecar = $state.sidecar.sidecar.lock().map_err(|e| e.to_string())?;
-        if !sidecar.is_running() {
-            sidecar.start()?;
-        }
-        sidecar.call($method, Some($params))
-    }};
-}
-
 pub fn get_project_and_task(
-    state: &State<AppState>,
+    trpc: &TrpcClient,
     project_id: &str,
     task_id: Option<&str>,
-) -> Result<(Project, Option<TaskFull>), String> {
-    let project_result = sidecar_call!(state, "projects.get", json!({ "id": project_id }))?;
-    let project: Project = serde_json::from_value(project_result)
-        .map_err(|e| format!("Failed to deserialize project: {}", e))?;
-
-    let task = if let Some(tid) = task_id {
-        let task_result = sidecar_call!(state, "tasks.getFull", json!({ "id": tid }))?;
-        let task_full: TaskFull = serde_json::from_value(task_result)
-            .map_err(|e| format!("Failed to deserialize task: {}", e))?;
-        Some(task_full)
-    } else {
-        None
+) -> Result<(Project, Option<TaskFull>), SidecarError> {
+    let project = trpc.projects().get(project_id)?;
+
+    let task = match task_id {
+        Some(tid) => Some(trpc.tasks().get_full(tid)?),
+        None => None,
     };
 
     Ok((project, task))
@@ -89,179 +77,66 @@ pub fn get_tab_name(project: &Project, task_id: &str) -> String {
 }
 
 // ============================================================================
-// Tmux Session Management - Centralized Functions
+// Session Management - Centralized Functions
 // ============================================================================
 
-use std::process::Command;
-
-/// Result type for tmux operations
-pub type TmuxResult<T> = Result<T, String>;
-
-/// Checks if the 'workopilot' session exists in tmux
-pub fn session_exists() -> bool {
-    Command::new("tmux")
-        .args(["has-session", "-t", SESSION_NAME])
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-}
-
-/// Lists all window names in the 'workopilot' session
-pub fn list_windows() -> TmuxResult<Vec<String>> {
-    if !session_exists() {
-        return Ok(vec![]);
-    }
+/// Result type for terminal and multiplexer operations
+pub type TerminalResult<T> = Result<T, String>;
 
-    let output = Command::new("tmux")
-        .args(["list-windows", "-t", SESSION_NAME, "-F", "#{window_name}"])
-        .output()
-        .map_err(|e| format!("Failed to list tmux windows: {}", e))?;
-
-    if output.status.success() {
-        let windows = String::from_utf8_lossy(&output.stdout)
-            .lines()
-            .map(|s| s.to_string())
-            .collect();
-        Ok(windows)
-    } else {
-        Ok(vec![])
-    }
+/// Terminal emulator and multiplexer that task sessions are opened with.
+#[derive(Clone, Copy)]
+pub struct TerminalBackends {
+    pub emulator: &'static dyn TerminalEmulator,
+    pub multiplexer: &'static dyn Multiplexer,
 }
 
-/// Finds a tab (window) by name in the 'workopilot' session.
-/// Returns Some(window_name) if found, None otherwise.
-pub fn find_tab_by_name(tab_name: &str) -> TmuxResult<Option<String>> {
-    let windows = list_windows()?;
-    Ok(windows.into_iter().find(|w| w == tab_name))
+/// Checks if a tab with this name exists in the 'workopilot' session.
+pub fn tab_exists(mux: &dyn Multiplexer, tab_name: &str) -> TerminalResult<bool> {
+    Ok(mux.list_tabs(SESSION_NAME)?.iter().any(|t| t == tab_name))
 }
 
-/// Selects (focuses) a tab within the tmux session.
-/// This makes the tab active but doesn't bring the terminal window to front.
-pub fn select_tab(tab_name: &str) -> TmuxResult<()> {
-    let output = Command::new("tmux")
-        .args([
-            "select-window",
-            "-t",
-            &format!("{}:{}", SESSION_NAME, tab_name),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to select tmux window: {}", e))?;
-
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("Failed to select tmux window: {}", stderr))
-    }
-}
-
-/// Gets the current command running in a tab's pane.
-/// Used to check if opencode (node) is running.
-pub fn get_pane_command(tab_name: &str) -> TmuxResult<String> {
-    let output = Command::new("tmux")
-        .args([
-            "display-message",
-            "-p",
-            "-t",
-            &format!("{}:{}", SESSION_NAME, tab_name),
-            "#{pane_current_command}",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to get pane command: {}", e))?;
-
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
-    } else {
-        Ok(String::new())
+/// Raises the terminal window attached to the session through the focus
+/// backend of the running desktop (Sway, Hyprland or X11).
+pub fn focus_terminal_window(backends: TerminalBackends) -> TerminalResult<FocusStatus> {
+    let TerminalBackends {
+        emulator,
+        multiplexer,
+    } = backends;
+
+    if !multiplexer.has_attached_client(SESSION_NAME) {
+        eprintln!(
+            "[WorkoPilot] No {} clients attached to session",
+            multiplexer.name()
+        );
+        return Ok(FocusStatus {
+            backend: focus_provider::from_env()
+                .map_or("none", |provider| provider.id())
+                .to_string(),
+            window: None,
+        });
     }
-}
 
-/// Sends keys to a specific tab in the 'workopilot' session.
-pub fn send_keys(tab_name: &str, keys: &str) -> TmuxResult<()> {
-    let output = Command::new("tmux")
-        .args([
-            "send-keys",
-            "-t",
-            &format!("{}:{}", SESSION_NAME, tab_name),
-            keys,
-            "Enter",
-        ])
-        .output()
-        .map_err(|e| format!("Failed to send keys to tmux: {}", e))?;
-
-    if output.status.success() {
-        Ok(())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        Err(format!("Failed to send keys to tmux: {}", stderr))
-    }
+    let status = focus_provider::from_env()?.focus(&FocusTarget {
+        classes: &[emulator.app_id(), emulator.window_class()],
+        title_hints: &[SESSION_NAME, multiplexer.id()],
+    })?;
+    eprintln!("[WorkoPilot] {} ({})", status, emulator.name());
+    Ok(status)
 }
 
-pub fn focus_terminal_window() -> TmuxResult<bool> {
-    let clients_output = Command::new("tmux")
-        .args(["list-clients", "-t", SESSION_NAME, "-F", "#{client_tty}"])
-        .output()
-        .map_err(|e| format!("Failed to list tmux clients: {}", e))?;
-
-    if !clients_output.status.success() || clients_output.stdout.is_empty() {
-        eprintln!("[WorkoPilot] No tmux clients attached to session");
-        return Ok(false);
-    }
-
-    let search_output = Command::new("xdotool")
-        .args(["search", "--class", "Alacritty"])
-        .output()
-        .map_err(|e| format!("Failed to search for Alacritty windows: {}", e))?;
-
-    if !search_output.status.success() {
-        eprintln!("[WorkoPilot] xdotool search failed");
-        return Ok(false);
-    }
-
-    let window_ids = String::from_utf8_lossy(&search_output.stdout);
-
-    for window_id in window_ids.lines() {
-        let window_id = window_id.trim();
-        if window_id.is_empty() {
-            continue;
-        }
-
-        let name_output = Command::new("xdotool")
-            .args(["getwindowname", window_id])
-            .output();
-
-        if let Ok(output) = name_output {
-            let window_name = String::from_utf8_lossy(&output.stdout).to_lowercase();
-
-            if window_name.contains(SESSION_NAME) || window_name.contains("tmux") {
-                eprintln!(
-                    "[WorkoPilot] Found window: {} ({})",
-                    window_id,
-                    window_name.trim()
-                );
-
-                let activate_result = Command::new("xdotool")
-                    .args(["windowactivate", "--sync", window_id])
-                    .output();
-
-                if let Ok(result) = activate_result {
-                    if result.status.success() {
-                        eprintln!("[WorkoPilot] Window activated successfully");
-                        return Ok(true);
-                    }
-                }
-            }
-        }
+/// Focuses the terminal once the action went through; a failure is only
+/// logged, since the prompt was already delivered.
+fn focus_after_paste(backends: TerminalBackends) {
+    if let Err(e) = focus_terminal_window(backends) {
+        eprintln!("[WorkoPilot] Failed to focus terminal window: {}", e);
     }
-
-    eprintln!("[WorkoPilot] No matching Alacritty window found");
-    Ok(false)
 }
 
-/// Checks if opencode (node) is running in a specific tab.
-pub fn is_opencode_running(tab_name: &str) -> bool {
-    get_pane_command(tab_name)
-        .map(|cmd| cmd == "node")
+/// Checks if opencode is running in a specific tab. On Zellij this switches
+/// to the tab (see [`Multiplexer::pane_command`]).
+pub fn is_opencode_running(mux: &dyn Multiplexer, tab_name: &str) -> bool {
+    mux.pane_command(SESSION_NAME, tab_name)
+        .map(|cmd| readiness::AGENT_COMMANDS.contains(&cmd.as_str()))
         .unwrap_or(false)
 }
 
@@ -302,11 +177,22 @@ pub fn generate_prompt(
     }
 }
 
+/// Script shown in the new terminal while opencode starts in a detached
+/// session; pastes the prompt in `prompt_file` once the pane runs opencode and
+/// its UI has been drawn, then deletes the file. Uses the functions from
+/// [`readiness::script_probe`].
 pub fn generate_loading_animation_script(
+    mux: &dyn Multiplexer,
     session_name: &str,
     tab_name: &str,
-    escaped_prompt: &str,
+    prompt_file: &Path,
 ) -> String {
+    let send_prompt = mux.script_paste_file(session_name, tab_name, prompt_file);
+    let remove_prompt = command_line("rm", &["-f", &prompt_file.to_string_lossy()]);
+    let timeout = Stage::Agent.timeout().as_secs();
+    let interval = readiness::POLL_INTERVAL.as_secs_f32();
+    let polls = Stage::Agent.timeout().as_millis() / readiness::POLL_INTERVAL.as_millis();
+
     format!(
         r##"
 clear
@@ -388,10 +274,11 @@ draw_base
 
 NODE_READY=0
 PROGRESS=0
+DEADLINE=$((SECONDS + {timeout}))
+i=0
 
-for i in $(seq 1 90); do
-    PANE_CMD=$(tmux display-message -p -t "{session_name}:{tab_name}" '#{{pane_current_command}}' 2>/dev/null)
-    if [ "$PANE_CMD" = "node" ]; then
+while [ $SECONDS -lt $DEADLINE ]; do
+    if agent_ready; then
         NODE_READY=1
         while [ $PROGRESS -lt $BAR_WIDTH ]; do
             PROGRESS=$((PROGRESS + 2))
@@ -401,17 +288,19 @@ for i in $(seq 1 90); do
         done
         break
     fi
-    PROGRESS=$((i * BAR_WIDTH / 100))
+    i=$((i + 1))
+    PROGRESS=$((i * BAR_WIDTH / {polls}))
     [ $PROGRESS -gt $((BAR_WIDTH - 8)) ] && PROGRESS=$((BAR_WIDTH - 8))
     draw_progress $PROGRESS
-    sleep 0.35
+    sleep {interval}
 done
 
 if [ $NODE_READY -eq 1 ]; then
+    {send_prompt}
     draw_success
-    sleep 2.5
-    tmux send-keys -t "{session_name}:{tab_name}" "{escaped_prompt}" Enter
+    sleep 1
 fi
+{remove_prompt}
 
 printf '\e[?25h'
 stty echo 2>/dev/null
@@ -419,11 +308,52 @@ stty echo 2>/dev/null
     )
 }
 
+/// Waits in the background for opencode to be ready in `tab_name`, then pastes
+/// the prompt in `prompt_file` and deletes it. For multiplexers that can only
+/// be queried once a client attaches. Uses the functions from
+/// [`readiness::script_probe`].
+pub fn generate_background_prompt_script(
+    mux: &dyn Multiplexer,
+    session_name: &str,
+    tab_name: &str,
+    prompt_file: &Path,
+) -> String {
+    format!(
+        r#"(
+    wait_until agent_ready {timeout} && {send_prompt}
+    {remove_prompt}
+) >/dev/null 2>&1 &"#,
+        timeout = Stage::Agent.timeout().as_secs(),
+        send_prompt = mux.script_paste_file(session_name, tab_name, prompt_file),
+        remove_prompt = command_line("rm", &["-f", &prompt_file.to_string_lossy()]),
+    )
+}
+
+/// Writes `prompt` to a new 0600 file in `dir` (the runtime dir outside
+/// tests), for a launch script to paste without the prompt ever becoming
+/// script text.
+fn write_prompt_file(dir: &Path, tab_name: &str, prompt: &str) -> TerminalResult<PathBuf> {
+    ipc_paths::prepare_private_dir(dir)?;
+    let path = dir.join(format!("prompt-{}-{}.txt", tab_name, uuid::Uuid::new_v4()));
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(&path)
+        .map_err(|e| format!("Failed to create prompt file {}: {}", path.display(), e))?;
+    file.write_all(prompt.as_bytes())
+        .map_err(|e| format!("Failed to write prompt file {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
 fn focus_task_terminal(
     app_handle: &tauri::AppHandle,
+    backends: TerminalBackends,
     project: &Project,
     task_id: &str,
-) -> TmuxResult<()> {
+) -> TerminalResult<Option<FocusStatus>> {
+    let mux = backends.multiplexer;
     let tab_name = get_tab_name(project, task_id);
     let project_path = project
         .routes
@@ -433,28 +363,31 @@ fn focus_task_terminal(
 
     eprintln!("[WorkoPilot] focus_task_terminal: tab_name={}", tab_name);
 
-    if find_tab_by_name(&tab_name)?.is_none() {
+    if !tab_exists(mux, &tab_name)? {
         eprintln!("[WorkoPilot] Tab does not exist, creating new tab");
-        launch_new_task_terminal(project_path, &tab_name, "")?;
+        launch_new_task_terminal(backends, project_path, &tab_name, "")?;
         crate::window::hide(app_handle);
-        return Ok(());
+        return Ok(None);
     }
 
-    select_tab(&tab_name)?;
+    mux.select_tab(SESSION_NAME, &tab_name)?;
 
-    if focus_terminal_window()? {
+    let status = focus_terminal_window(backends)?;
+    if status.focused() {
         crate::window::hide(app_handle);
     }
 
-    Ok(())
+    Ok(Some(status))
 }
 
 fn execute_task_action(
+    backends: TerminalBackends,
     project: &Project,
     task_id: &str,
     prompt: &str,
     force_new_tab: bool,
-) -> TmuxResult<()> {
+) -> TerminalResult<()> {
+    let mux = backends.multiplexer;
     let tab_name = get_tab_name(project, task_id);
     let project_path = project
         .routes
@@ -469,198 +402,369 @@ fn execute_task_action(
         prompt.len()
     );
 
-    let escaped_prompt = prompt.replace('\\', "\\\\").replace('"', "\\\"");
-
-    if !force_new_tab {
-        if let Some(_) = find_tab_by_name(&tab_name)? {
-            if is_opencode_running(&tab_name) {
-                eprintln!("[WorkoPilot] Tab exists with opencode, sending prompt directly");
-                send_keys(&tab_name, &escaped_prompt)?;
-                select_tab(&tab_name)?;
-                focus_terminal_window()?;
-                return Ok(());
-            }
-            eprintln!("[WorkoPilot] Tab exists but opencode not running, will recreate");
+    if !force_new_tab && tab_exists(mux, &tab_name)? {
+        if is_opencode_running(mux, &tab_name) {
+            eprintln!("[WorkoPilot] Tab exists with opencode, sending prompt directly");
+            mux.paste_text(SESSION_NAME, &tab_name, prompt)?;
+            mux.select_tab(SESSION_NAME, &tab_name)?;
+            focus_after_paste(backends);
+            return Ok(());
         }
+        eprintln!("[WorkoPilot] Tab exists but opencode not running, will recreate");
     }
 
-    launch_new_task_terminal(project_path, &tab_name, &escaped_prompt)
-}
-
-fn has_attached_client() -> bool {
-    Command::new("tmux")
-        .args(["list-clients", "-t", SESSION_NAME])
-        .output()
-        .map(|o| o.status.success() && !o.stdout.is_empty())
-        .unwrap_or(false)
+    launch_new_task_terminal(backends, project_path, &tab_name, prompt)
 }
 
 fn launch_new_task_terminal(
+    backends: TerminalBackends,
     project_path: &str,
     tab_name: &str,
-    escaped_prompt: &str,
-) -> TmuxResult<()> {
+    prompt: &str,
+) -> TerminalResult<()> {
+    let TerminalBackends {
+        emulator,
+        multiplexer: mux,
+    } = backends;
+
     eprintln!(
-        "[WorkoPilot] launch_new_task_terminal: tab={}, path={}",
-        tab_name, project_path
+        "[WorkoPilot] launch_new_task_terminal: tab={}, path={}, multiplexer={}",
+        tab_name,
+        project_path,
+        mux.id()
     );
 
-    let has_client = has_attached_client();
+    let has_client = mux.has_attached_client(SESSION_NAME);
     eprintln!("[WorkoPilot] Session has attached client: {}", has_client);
 
     if has_client {
-        eprintln!("[WorkoPilot] Reusing existing terminal, creating tab via tmux commands");
-
-        let _ = Command::new("tmux")
-            .args([
-                "kill-window",
-                "-t",
-                &format!("{}:{}", SESSION_NAME, tab_name),
-            ])
-            .output();
-
-        let new_window = Command::new("tmux")
-            .args([
-                "new-window",
-                "-t",
-                SESSION_NAME,
-                "-n",
-                tab_name,
-                "-c",
-                project_path,
-            ])
-            .output()
-            .map_err(|e| format!("Failed to create tmux window: {}", e))?;
-
-        if !new_window.status.success() {
-            return Err("Failed to create new tmux window".to_string());
+        eprintln!(
+            "[WorkoPilot] Reusing existing terminal, creating tab via {} commands",
+            mux.name()
+        );
+
+        mux.open_tab(SESSION_NAME, tab_name, project_path)?;
+
+        // Keys typed before the prompt are still buffered, so go ahead anyway.
+        if let Err(e) = readiness::wait_for(mux, SESSION_NAME, tab_name, Stage::Shell) {
+            eprintln!("[WorkoPilot] {}, starting opencode anyway", e);
         }
+        mux.send_keys(SESSION_NAME, tab_name, "opencode")?;
+
+        let mut dropped = None;
+        if !prompt.is_empty() {
+            match readiness::wait_for(mux, SESSION_NAME, tab_name, Stage::Agent) {
+                Ok(waited) => {
+                    eprintln!("[WorkoPilot] opencode ready after {:?}", waited);
+                    mux.paste_text(SESSION_NAME, tab_name, prompt)?;
+                }
+                // Running but never settled: better pasted early than lost.
+                Err(e) if is_opencode_running(mux, tab_name) => {
+                    eprintln!("[WorkoPilot] {}, pasting the prompt anyway", e);
+                    mux.paste_text(SESSION_NAME, tab_name, prompt)?;
+                }
+                // Never into the shell, where it would run as a command.
+                Err(e) => dropped = Some(e),
+            }
+        }
+        mux.select_tab(SESSION_NAME, tab_name)?;
+        focus_after_paste(backends);
 
-        std::thread::sleep(std::time::Duration::from_millis(300));
+        return match dropped {
+            Some(e) => Err(format!("{}; the prompt was not sent", e)),
+            None => Ok(()),
+        };
+    }
 
-        Command::new("tmux")
-            .args([
-                "send-keys",
-                "-t",
-                &format!("{}:{}", SESSION_NAME, tab_name),
-                "opencode",
-                "Enter",
-            ])
-            .output()
-            .map_err(|e| format!("Failed to send opencode command: {}", e))?;
+    let (script, prompt_file) = task_launch_script(
+        mux,
+        &ipc_paths::runtime_dir(),
+        project_path,
+        tab_name,
+        prompt,
+    )?;
 
-        std::thread::sleep(std::time::Duration::from_secs(3));
+    if let Err(e) = emulator.command("bash", &["-c", &script]).spawn() {
+        // No script will paste and delete it now.
+        if let Some(path) = prompt_file {
+            let _ = std::fs::remove_file(path);
+        }
+        return Err(format!("Failed to launch {}: {}", emulator.name(), e));
+    }
 
-        send_keys(tab_name, escaped_prompt)?;
-        select_tab(tab_name)?;
-        focus_terminal_window()?;
+    Ok(())
+}
 
-        return Ok(());
+/// Script run in a new terminal: opens `tab_name`, starts opencode, pastes
+/// `prompt` (if any, via a file in `prompt_dir`) once it is ready and attaches
+/// to the session. Also returns the prompt file, which the script deletes.
+fn task_launch_script(
+    mux: &dyn Multiplexer,
+    prompt_dir: &Path,
+    project_path: &str,
+    tab_name: &str,
+    prompt: &str,
+) -> TerminalResult<(String, Option<PathBuf>)> {
+    let mut script = ShellScript::new()
+        .snippet(mux.script_open_tab(SESSION_NAME, tab_name, project_path))
+        .blank()
+        .snippet(readiness::script_probe(mux, SESSION_NAME, tab_name))
+        .blank()
+        .command(
+            "wait_until",
+            &["shell_ready", &Stage::Shell.timeout().as_secs().to_string()],
+        )
+        .snippet(mux.script_send_keys(SESSION_NAME, tab_name, "opencode"))
+        .blank();
+
+    let mut prompt_file = None;
+    if !prompt.is_empty() {
+        let path = write_prompt_file(prompt_dir, tab_name, prompt)?;
+        script = script.snippet(if mux.can_probe_detached() {
+            generate_loading_animation_script(mux, SESSION_NAME, tab_name, &path)
+        } else {
+            generate_background_prompt_script(mux, SESSION_NAME, tab_name, &path)
+        });
+        prompt_file = Some(path);
     }
 
-    let loading_animation =
-        generate_loading_animation_script(SESSION_NAME, tab_name, escaped_prompt);
+    let script = script
+        .blank()
+        .snippet(mux.script_attach(SESSION_NAME, tab_name))
+        .build();
+    Ok((script, prompt_file))
+}
 
-    let script = format!(
-        r#"#!/usr/bin/env bash
-SESSION="{session_name}"
-TAB_NAME="{tab_name}"
-PROJECT_PATH="{project_path}"
+/// Terminal side effects of a [`TerminalAction`], separated from the sidecar
+/// lookups and status updates so those can be exercised without tmux.
+pub trait TaskLauncher {
+    fn launch_project(&self, project: &Project) -> TerminalResult<()>;
+    /// `None` when the tab had to be opened in a new terminal instead.
+    fn focus_task(&self, project: &Project, task_id: &str) -> TerminalResult<Option<FocusStatus>>;
+    fn execute_task(
+        &self,
+        project: &Project,
+        task_id: &str,
+        prompt: &str,
+        force_new_tab: bool,
+    ) -> TerminalResult<()>;
+}
 
-if tmux has-session -t "$SESSION" 2>/dev/null; then
-    tmux kill-window -t "$SESSION:$TAB_NAME" 2>/dev/null
-    tmux new-window -t "$SESSION" -n "$TAB_NAME" -c "$PROJECT_PATH"
-else
-    tmux new-session -d -s "$SESSION" -n "$TAB_NAME" -c "$PROJECT_PATH"
-fi
+/// Launches tasks in the shared session through the configured terminal
+/// emulator and multiplexer.
+pub struct SessionLauncher<'a> {
+    app_handle: &'a tauri::AppHandle,
+}
 
-sleep 0.3
-tmux send-keys -t "$SESSION:$TAB_NAME" "opencode" Enter
+impl<'a> SessionLauncher<'a> {
+    pub fn new(app_handle: &'a tauri::AppHandle) -> Self {
+        Self { app_handle }
+    }
 
-{loading_animation}
+    fn backends(&self) -> TerminalResult<TerminalBackends> {
+        let state = self.app_handle.state::<AppState>();
+        let (emulator_setting, multiplexer_setting) = match state.db.lock() {
+            Ok(db) => (
+                db.get_setting(terminal_emulator::SETTING).ok().flatten(),
+                db.get_setting(multiplexer::SETTING).ok().flatten(),
+            ),
+            Err(_) => (None, None),
+        };
 
-tmux select-window -t "$SESSION:$TAB_NAME"
-tmux attach-session -t "$SESSION"
-"#,
-        session_name = SESSION_NAME,
-        tab_name = tab_name,
-        project_path = project_path,
-        loading_animation = loading_animation,
-    );
+        let emulator = terminal_emulator::preference(emulator_setting);
+        let multiplexer = multiplexer::preference(multiplexer_setting);
+        Ok(TerminalBackends {
+            emulator: terminal_emulator::select(
+                emulator.as_deref(),
+                terminal_emulator::is_installed,
+            )?,
+            multiplexer: multiplexer::select(
+                multiplexer.as_deref(),
+                terminal_emulator::is_installed,
+            )?,
+        })
+    }
+}
 
-    Command::new("alacritty")
-        .arg("-e")
-        .arg("bash")
-        .arg("-c")
-        .arg(&script)
-        .spawn()
-        .map_err(|e| format!("Failed to launch terminal: {}", e))?;
+impl TaskLauncher for SessionLauncher<'_> {
+    fn launch_project(&self, project: &Project) -> TerminalResult<()> {
+        launch_project_session(self.backends()?, project)
+    }
 
-    Ok(())
+    fn focus_task(&self, project: &Project, task_id: &str) -> TerminalResult<Option<FocusStatus>> {
+        focus_task_terminal(self.app_handle, self.backends()?, project, task_id)
+    }
+
+    fn execute_task(
+        &self,
+        project: &Project,
+        task_id: &str,
+        prompt: &str,
+        force_new_tab: bool,
+    ) -> TerminalResult<()> {
+        execute_task_action(self.backends()?, project, task_id, prompt, force_new_tab)
+    }
+}
+
+/// Choice of terminal emulator or multiplexer, as shown on the Settings page.
+#[derive(Debug, Serialize)]
+pub struct TerminalBackendSettings {
+    /// Saved setting; `auto` when unset.
+    pub selected: String,
+    /// Set when the environment variable overrides the setting.
+    pub env_override: Option<String>,
+    /// Backend that will actually be used, if any is usable.
+    pub active: Option<String>,
+    pub available: Vec<BackendInfo>,
+}
+
+fn read_setting(state: &State<'_, AppState>, key: &str) -> Result<Option<String>, String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.get_setting(key).map_err(|e| e.to_string())
+}
+
+fn save_setting(state: &State<'_, AppState>, key: &str, value: &str) -> Result<(), String> {
+    let db = state.db.lock().map_err(|e| e.to_string())?;
+    db.set_setting(key, value).map_err(|e| e.to_string())
+}
+
+fn env_override(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|v| !v.trim().is_empty())
+}
+
+#[tauri::command]
+pub fn get_terminal_emulators(
+    state: State<'_, AppState>,
+) -> Result<TerminalBackendSettings, String> {
+    let setting = read_setting(&state, terminal_emulator::SETTING)?;
+    let preference = terminal_emulator::preference(setting.clone());
+
+    Ok(TerminalBackendSettings {
+        selected: setting.unwrap_or_else(|| terminal_emulator::AUTO.to_string()),
+        env_override: env_override(terminal_emulator::ENV_VAR),
+        active: terminal_emulator::select(preference.as_deref(), terminal_emulator::is_installed)
+            .ok()
+            .map(|e| e.id().to_string()),
+        available: terminal_emulator::detect(),
+    })
+}
+
+#[tauri::command]
+pub fn set_terminal_emulator(
+    state: State<'_, AppState>,
+    emulator: String,
+) -> Result<TerminalBackendSettings, String> {
+    let emulator = emulator.trim().to_lowercase();
+    if emulator != terminal_emulator::AUTO && terminal_emulator::from_id(&emulator).is_none() {
+        return Err(format!("Unknown terminal emulator: {}", emulator));
+    }
+
+    save_setting(&state, terminal_emulator::SETTING, &emulator)?;
+    eprintln!("[WorkoPilot] Terminal emulator set to {}", emulator);
+
+    get_terminal_emulators(state)
+}
+
+#[tauri::command]
+pub fn get_terminal_multiplexers(
+    state: State<'_, AppState>,
+) -> Result<TerminalBackendSettings, String> {
+    let setting = read_setting(&state, multiplexer::SETTING)?;
+    let preference = multiplexer::preference(setting.clone());
+
+    Ok(TerminalBackendSettings {
+        selected: setting.unwrap_or_else(|| multiplexer::AUTO.to_string()),
+        env_override: env_override(multiplexer::ENV_VAR),
+        active: multiplexer::select(preference.as_deref(), terminal_emulator::is_installed)
+            .ok()
+            .map(|m| m.id().to_string()),
+        available: multiplexer::detect(),
+    })
 }
 
 #[tauri::command]
-pub fn terminal_action(
+pub fn set_terminal_multiplexer(
+    state: State<'_, AppState>,
+    multiplexer: String,
+) -> Result<TerminalBackendSettings, String> {
+    let multiplexer = multiplexer.trim().to_lowercase();
+    if multiplexer != multiplexer::AUTO && multiplexer::from_id(&multiplexer).is_none() {
+        return Err(format!("Unknown terminal multiplexer: {}", multiplexer));
+    }
+
+    save_setting(&state, multiplexer::SETTING, &multiplexer)?;
+    eprintln!("[WorkoPilot] Terminal multiplexer set to {}", multiplexer);
+
+    get_terminal_multiplexers(state)
+}
+
+#[tauri::command]
+pub async fn terminal_action(
     app_handle: tauri::AppHandle,
-    state: State<AppState>,
+    state: State<'_, AppState>,
     action: String,
     project_id: String,
     task_id: Option<String>,
     subtask_id: Option<String>,
-) -> Result<(), String> {
-    let action_type = TerminalAction::from_str(&action)?;
+) -> Result<Option<FocusStatus>, SidecarError> {
+    let action_type = TerminalAction::from_str(&action).map_err(SidecarError::Validation)?;
 
     eprintln!(
         "[WorkoPilot] terminal_action: {:?}, project: {}, task: {:?}, subtask: {:?}",
         action_type, project_id, task_id, subtask_id
     );
 
-    let (project, task_opt) = get_project_and_task(&state, &project_id, task_id.as_deref())?;
+    let trpc = state.sidecar.sidecar.trpc().map_err(SidecarError::Transport)?;
+    let launcher = SessionLauncher::new(&app_handle);
+    run_terminal_action(
+        &trpc,
+        &launcher,
+        action_type,
+        &project_id,
+        task_id,
+        subtask_id,
+    )
+}
+
+/// Runs `action_type`; focusing a session returns which window was raised.
+pub fn run_terminal_action(
+    trpc: &TrpcClient,
+    launcher: &dyn TaskLauncher,
+    action_type: TerminalAction,
+    project_id: &str,
+    task_id: Option<String>,
+    subtask_id: Option<String>,
+) -> Result<Option<FocusStatus>, SidecarError> {
+    let (project, task_opt) = get_project_and_task(trpc, project_id, task_id.as_deref())?;
 
     match action_type {
         TerminalAction::LaunchProject => {
-            launch_project_session(&project)?;
+            launcher.launch_project(&project)?;
         }
 
         TerminalAction::FocusSession => {
             let tid = task_id.ok_or("task_id required for focus action")?;
-            focus_task_terminal(&app_handle, &project, &tid)?;
+            return Ok(launcher.focus_task(&project, &tid)?);
         }
 
         TerminalAction::Structure => {
             let tid = task_id.ok_or("task_id required for structure action")?;
             let task = task_opt.ok_or("task not found")?;
 
-            sidecar_call!(
-                state,
-                "tasks.updateStatus",
-                json!({
-                    "id": tid,
-                    "status": "in_progress",
-                    "modifiedBy": "user"
-                })
-            )?;
+            trpc.tasks().update_status(&tid, "in_progress", "user")?;
 
             let prompt = generate_prompt(&action_type, &task, None);
-            execute_task_action(&project, &tid, &prompt, true)?;
+            launcher.execute_task(&project, &tid, &prompt, true)?;
         }
 
         TerminalAction::ExecuteAll => {
             let tid = task_id.ok_or("task_id required for execute_all action")?;
             let task = task_opt.ok_or("task not found")?;
 
-            sidecar_call!(
-                state,
-                "tasks.updateStatus",
-                json!({
-                    "id": tid,
-                    "status": "in_progress",
-                    "modifiedBy": "user"
-                })
-            )?;
+            trpc.tasks().update_status(&tid, "in_progress", "user")?;
 
             let prompt = generate_prompt(&action_type, &task, None);
-            execute_task_action(&project, &tid, &prompt, false)?;
+            launcher.execute_task(&project, &tid, &prompt, false)?;
         }
 
         TerminalAction::ExecuteSubtask => {
@@ -668,18 +772,10 @@ pub fn terminal_action(
             let task = task_opt.ok_or("task not found")?;
             let sid = subtask_id.ok_or("subtask_id required for execute_subtask action")?;
 
-            sidecar_call!(
-                state,
-                "tasks.updateStatus",
-                json!({
-                    "id": tid,
-                    "status": "in_progress",
-                    "modifiedBy": "user"
-                })
-            )?;
+            trpc.tasks().update_status(&tid, "in_progress", "user")?;
 
             let prompt = generate_prompt(&action_type, &task, Some(&sid));
-            execute_task_action(&project, &tid, &prompt, false)?;
+            launcher.execute_task(&project, &tid, &prompt, false)?;
         }
 
         TerminalAction::Review => {
@@ -687,7 +783,7 @@ pub fn terminal_action(
             let task = task_opt.ok_or("task not found")?;
 
             let prompt = generate_prompt(&action_type, &task, None);
-            execute_task_action(&project, &tid, &prompt, false)?;
+            launcher.execute_task(&project, &tid, &prompt, false)?;
         }
 
         TerminalAction::Commit => {
@@ -695,88 +791,250 @@ pub fn terminal_action(
             let task = task_opt.ok_or("task not found")?;
 
             let prompt = generate_prompt(&action_type, &task, None);
-            execute_task_action(&project, &tid, &prompt, false)?;
+            launcher.execute_task(&project, &tid, &prompt, false)?;
         }
     }
 
-    Ok(())
+    Ok(None)
 }
 
-fn launch_project_session(project: &Project) -> TmuxResult<()> {
-    let tmux_config = project.tmux_config.as_ref().ok_or("No tmux config")?;
+fn launch_project_session(backends: TerminalBackends, project: &Project) -> TerminalResult<()> {
+    let layout = project.layout.as_ref().ok_or("No session layout")?;
     let routes = &project.routes;
 
-    let session_name = &tmux_config.session_name;
-    let mut sorted_tabs = tmux_config.tabs.clone();
+    let mut sorted_tabs = layout.tabs.clone();
     sorted_tabs.sort_by_key(|t| t.order);
 
-    let mut script = format!(
-        r#"#!/usr/bin/env bash
-SESSION="{session_name}"
+    let tabs = sorted_tabs
+        .iter()
+        .map(|tab| {
+            let route = routes
+                .iter()
+                .find(|r| r.id == tab.route_id)
+                .or_else(|| routes.first())
+                .ok_or("No routes configured")?;
+
+            Ok(TabSpec {
+                name: tab.name.clone(),
+                cwd: route.path.clone(),
+                startup_command: tab.startup_command.clone().filter(|cmd| !cmd.is_empty()),
+            })
+        })
+        .collect::<TerminalResult<Vec<_>>>()?;
+
+    let script = ShellScript::new()
+        .snippet(
+            backends
+                .multiplexer
+                .script_layout(&layout.session_name, &tabs),
+        )
+        .build();
+
+    let TerminalBackends {
+        emulator,
+        multiplexer,
+    } = backends;
+    emulator
+        .command("bash", &["-c", &script])
+        .spawn()
+        .map_err(|e| {
+            format!(
+                "Failed to launch {} in {}: {}",
+                multiplexer.name(),
+                emulator.name(),
+                e
+            )
+        })?;
 
-if tmux has-session -t "$SESSION" 2>/dev/null; then
-    tmux attach-session -t "$SESSION"
-    exit 0
-fi
+    Ok(())
+}
 
-"#
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar_transport::{CallKind, InMemoryTransport};
+    use serde_json::json;
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct RecordingLauncher {
+        calls: Mutex<Vec<String>>,
+    }
 
-    for (i, tab) in sorted_tabs.iter().enumerate() {
-        let route = routes
-            .iter()
-            .find(|r| r.id == tab.route_id)
-            .unwrap_or_else(|| routes.first().unwrap());
+    impl RecordingLauncher {
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+    }
 
-        let path = &route.path;
-        let tab_name = &tab.name;
+    impl TaskLauncher for RecordingLauncher {
+        fn launch_project(&self, project: &Project) -> TerminalResult<()> {
+            self.calls.lock().unwrap().push(format!("launch:{}", project.id));
+            Ok(())
+        }
 
-        if i == 0 {
-            script.push_str(&format!(
-                r#"tmux new-session -d -s "$SESSION" -n "{tab_name}" -c "{path}"
-"#
-            ));
-        } else {
-            script.push_str(&format!(
-                r#"tmux new-window -t "$SESSION" -n "{tab_name}" -c "{path}"
-"#
-            ));
+        fn focus_task(
+            &self,
+            _project: &Project,
+            task_id: &str,
+        ) -> TerminalResult<Option<FocusStatus>> {
+            self.calls.lock().unwrap().push(format!("focus:{}", task_id));
+            Ok(Some(FocusStatus {
+                backend: "fake".to_string(),
+                window: None,
+            }))
         }
 
-        if let Some(cmd) = &tab.startup_command {
-            if !cmd.is_empty() {
-                script.push_str(&format!(
-                    r#"tmux send-keys -t "$SESSION:{tab_name}" "{cmd}" Enter
-"#
-                ));
-            }
+        fn execute_task(
+            &self,
+            _project: &Project,
+            task_id: &str,
+            _prompt: &str,
+            force_new_tab: bool,
+        ) -> TerminalResult<()> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("execute:{}:{}", task_id, force_new_tab));
+            Ok(())
         }
     }
 
-    if let Some(first_tab) = sorted_tabs.first() {
-        script.push_str(&format!(
-            r#"
-tmux select-window -t "$SESSION:{}"
-tmux attach-session -t "$SESSION"
-"#,
-            first_tab.name
-        ));
+    fn task_json(title: &str) -> serde_json::Value {
+        json!({
+            "id": "t1",
+            "title": title,
+            "status": "pending",
+            "priority": 1,
+            "category": "bug",
+            "complexity": null,
+            "context": { "description": null, "technical_notes": null, "acceptance_criteria": null },
+            "subtasks": [],
+            "ai_metadata": { "last_interaction": null, "last_completed_action": null },
+            "timestamps": { "created_at": "2025-01-01T00:00:00Z", "started_at": null, "completed_at": null },
+            "modified_at": null,
+            "project_id": "p1",
+            "due_date": null,
+            "scheduled_date": null
+        })
     }
 
-    Command::new("alacritty")
-        .arg("-e")
-        .arg("bash")
-        .arg("-c")
-        .arg(&script)
-        .spawn()
-        .map_err(|e| format!("Failed to launch tmux: {}", e))?;
+    fn scripted_sidecar() -> Arc<InMemoryTransport> {
+        Arc::new(
+            InMemoryTransport::new()
+                .respond(
+                    "projects.get",
+                    json!({
+                        "id": "p1",
+                        "name": "My Project",
+                        "path": "/tmp/my-project",
+                        "description": null,
+                        "routes": [{ "id": "r1", "path": "/tmp/my-project", "order": 0, "env_path": null }],
+                        "created_at": null,
+                        "color": null
+                    }),
+                )
+                .respond("tasks.getFull", task_json("Fix login"))
+                .respond("tasks.updateStatus", json!({ "id": "t1" })),
+        )
+    }
 
-    Ok(())
-}
+    fn run(
+        action: TerminalAction,
+        task_id: Option<&str>,
+        subtask_id: Option<&str>,
+    ) -> (
+        Arc<InMemoryTransport>,
+        RecordingLauncher,
+        Result<Option<FocusStatus>, SidecarError>,
+    ) {
+        let sidecar = scripted_sidecar();
+        let launcher = RecordingLauncher::default();
+        let result = run_terminal_action(
+            &TrpcClient::new(sidecar.clone()),
+            &launcher,
+            action,
+            "p1",
+            task_id.map(str::to_string),
+            subtask_id.map(str::to_string),
+        );
+        (sidecar, launcher, result)
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_status_changing_actions_mark_task_in_progress() {
+        let cases = [
+            (TerminalAction::Structure, None, "execute:t1:true"),
+            (TerminalAction::ExecuteAll, None, "execute:t1:false"),
+            (TerminalAction::ExecuteSubtask, Some("s1"), "execute:t1:false"),
+        ];
+
+        for (action, subtask_id, launched) in cases {
+            let (sidecar, launcher, result) = run(action.clone(), Some("t1"), subtask_id);
+            assert!(result.is_ok(), "{:?}: {:?}", action, result);
+
+            let updates = sidecar.calls_to("tasks.updateStatus");
+            assert_eq!(updates.len(), 1, "{:?}", action);
+            assert_eq!(updates[0].kind, CallKind::Mutation);
+            assert_eq!(
+                updates[0].input,
+                Some(json!({ "id": "t1", "status": "in_progress", "modifiedBy": "user" }))
+            );
+            assert_eq!(launcher.calls(), vec![launched.to_string()]);
+        }
+    }
+
+    #[test]
+    fn test_other_actions_leave_status_alone() {
+        let cases = [
+            (TerminalAction::LaunchProject, None, "launch:p1"),
+            (TerminalAction::FocusSession, Some("t1"), "focus:t1"),
+            (TerminalAction::Review, Some("t1"), "execute:t1:false"),
+            (TerminalAction::Commit, Some("t1"), "execute:t1:false"),
+        ];
+
+        for (action, task_id, launched) in cases {
+            let (sidecar, launcher, result) = run(action.clone(), task_id, None);
+            let status = result.unwrap();
+            assert!(sidecar.calls_to("tasks.updateStatus").is_empty(), "{:?}", action);
+            assert_eq!(launcher.calls(), vec![launched.to_string()]);
+            // Only focusing reports which window was raised.
+            assert_eq!(
+                status.map(|s| s.backend),
+                (action == TerminalAction::FocusSession).then(|| "fake".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_missing_subtask_fails_before_status_update() {
+        let (sidecar, launcher, result) = run(TerminalAction::ExecuteSubtask, Some("t1"), None);
+
+        assert!(result.unwrap_err().to_string().contains("subtask_id required"));
+        assert!(sidecar.calls_to("tasks.updateStatus").is_empty());
+        assert!(launcher.calls().is_empty());
+    }
+
+    #[test]
+    fn test_unknown_project_is_reported() {
+        let sidecar = Arc::new(InMemoryTransport::new().respond("projects.get", json!(null)));
+        let launcher = RecordingLauncher::default();
+        let result = run_terminal_action(
+            &TrpcClient::new(sidecar.clone()),
+            &launcher,
+            TerminalAction::LaunchProject,
+            "missing",
+            None,
+            None,
+        );
+
+        assert_eq!(
+            result.unwrap_err(),
+            SidecarError::NotFound("Project not found: missing".to_string())
+        );
+        assert_eq!(sidecar.calls().len(), 1);
+    }
 
     #[test]
     fn test_terminal_action_from_str() {
@@ -820,7 +1078,7 @@ mod tests {
             description: None,
             display_order: 0,
             routes: vec![],
-            tmux_config: None,
+            layout: None,
             business_rules: None,
             tmux_configured: false,
             created_at: None,
@@ -834,5 +1092,49 @@ mod tests {
         // Test with short task_id
         let tab_name = get_tab_name(&project, "abc123");
         assert_eq!(tab_name, "MyProject-abc123");
+
+        let project = Project {
+            name: "x$(id)`; 'y'\n:.".to_string(),
+            ..project
+        };
+        assert_eq!(get_tab_name(&project, "abc123"), "xidy-abc123");
+    }
+
+    #[test]
+    fn test_adversarial_prompt_stays_out_of_launch_script() {
+        let title = "Fix $(touch /tmp/pwned) `id` !! \"x\" it's\nrm -rf ~";
+        let task: TaskFull = serde_json::from_value(task_json(title)).unwrap();
+        let prompt = generate_prompt(&TerminalAction::ExecuteAll, &task, None);
+        let project_path = "/tmp/it's $(reboot) `id`";
+        if crate::shell_script::bash_missing() {
+            return;
+        }
+        let dir = tempfile::tempdir().unwrap();
+        let prompt_dir = dir.path().join("workopilot");
+
+        for mux in multiplexer::MULTIPLEXERS {
+            let (script, returned_file) =
+                task_launch_script(*mux, &prompt_dir, project_path, "proj-t1", &prompt).unwrap();
+
+            assert!(!script.contains("touch"), "{}: prompt leaked", mux.id());
+            assert!(script.contains(&crate::shell_script::shell_quote(project_path)));
+            let syntax = std::process::Command::new("bash")
+                .args(["-n", "-c", &script])
+                .status()
+                .unwrap();
+            assert!(syntax.success(), "{}: invalid script", mux.id());
+
+            // The prompt travels in a private file the script deletes.
+            let prompt_file = script
+                .lines()
+                .find_map(|l| l.trim().strip_prefix("rm '-f' '"))
+                .and_then(|l| l.strip_suffix('\''))
+                .unwrap();
+            assert_eq!(returned_file.as_deref(), Some(Path::new(prompt_file)));
+            assert!(Path::new(prompt_file).starts_with(&prompt_dir));
+            let metadata = std::fs::metadata(prompt_file).unwrap();
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+            assert_eq!(std::fs::read_to_string(prompt_file).unwrap(), prompt);
+        }
     }
 }
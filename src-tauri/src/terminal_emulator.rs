@@ -0,0 +1,282 @@
+use serde::Serialize;
+use std::process::Command;
+
+pub const ENV_VAR: &str = "WORKOPILOT_TERMINAL";
+pub const SETTING: &str = "terminal_emulator";
+/// Setting value that picks the first installed emulator.
+pub const AUTO: &str = "auto";
+
+/// A terminal emulator the tmux session can be opened in.
+pub trait TerminalEmulator: Sync {
+    /// Value stored in the `terminal_emulator` setting.
+    fn id(&self) -> &'static str;
+    fn name(&self) -> &'static str;
+    fn binary(&self) -> &'static str;
+    /// Arguments that open a new window running `program` with `args`.
+    fn launch_args(&self, program: &str, args: &[&str]) -> Vec<String>;
+    /// WM_CLASS of its windows under X11/XWayland.
+    fn window_class(&self) -> &'static str;
+    /// app_id of its windows when running natively on Wayland.
+    fn app_id(&self) -> &'static str {
+        self.window_class()
+    }
+
+    fn command(&self, program: &str, args: &[&str]) -> Command {
+        let mut command = Command::new(self.binary());
+        command.args(self.launch_args(program, args));
+        command
+    }
+}
+
+fn with_program(prefix: &[&str], program: &str, args: &[&str]) -> Vec<String> {
+    prefix
+        .iter()
+        .chain(std::iter::once(&program))
+        .chain(args)
+        .map(|s| s.to_string())
+        .collect()
+}
+
+pub struct Alacritty;
+pub struct Kitty;
+pub struct WezTerm;
+pub struct Foot;
+pub struct GnomeTerminal;
+
+impl TerminalEmulator for Alacritty {
+    fn id(&self) -> &'static str {
+        "alacritty"
+    }
+
+    fn name(&self) -> &'static str {
+        "Alacritty"
+    }
+
+    fn binary(&self) -> &'static str {
+        "alacritty"
+    }
+
+    fn launch_args(&self, program: &str, args: &[&str]) -> Vec<String> {
+        with_program(&["-e"], program, args)
+    }
+
+    fn window_class(&self) -> &'static str {
+        "Alacritty"
+    }
+}
+
+impl TerminalEmulator for Kitty {
+    fn id(&self) -> &'static str {
+        "kitty"
+    }
+
+    fn name(&self) -> &'static str {
+        "kitty"
+    }
+
+    fn binary(&self) -> &'static str {
+        "kitty"
+    }
+
+    fn launch_args(&self, program: &str, args: &[&str]) -> Vec<String> {
+        with_program(&[], program, args)
+    }
+
+    fn window_class(&self) -> &'static str {
+        "kitty"
+    }
+}
+
+impl TerminalEmulator for WezTerm {
+    fn id(&self) -> &'static str {
+        "wezterm"
+    }
+
+    fn name(&self) -> &'static str {
+        "WezTerm"
+    }
+
+    fn binary(&self) -> &'static str {
+        "wezterm"
+    }
+
+    fn launch_args(&self, program: &str, args: &[&str]) -> Vec<String> {
+        with_program(&["start", "--"], program, args)
+    }
+
+    fn window_class(&self) -> &'static str {
+        "org.wezfurlong.wezterm"
+    }
+}
+
+impl TerminalEmulator for Foot {
+    fn id(&self) -> &'static str {
+        "foot"
+    }
+
+    fn name(&self) -> &'static str {
+        "foot"
+    }
+
+    fn binary(&self) -> &'static str {
+        "foot"
+    }
+
+    fn launch_args(&self, program: &str, args: &[&str]) -> Vec<String> {
+        with_program(&[], program, args)
+    }
+
+    fn window_class(&self) -> &'static str {
+        "foot"
+    }
+}
+
+impl TerminalEmulator for GnomeTerminal {
+    fn id(&self) -> &'static str {
+        "gnome-terminal"
+    }
+
+    fn name(&self) -> &'static str {
+        "GNOME Terminal"
+    }
+
+    fn binary(&self) -> &'static str {
+        "gnome-terminal"
+    }
+
+    fn launch_args(&self, program: &str, args: &[&str]) -> Vec<String> {
+        with_program(&["--"], program, args)
+    }
+
+    fn window_class(&self) -> &'static str {
+        "Gnome-terminal"
+    }
+
+    fn app_id(&self) -> &'static str {
+        "org.gnome.Terminal"
+    }
+}
+
+/// Supported emulators, in auto-detection order.
+pub const EMULATORS: &[&dyn TerminalEmulator] =
+    &[&Alacritty, &Kitty, &WezTerm, &Foot, &GnomeTerminal];
+
+pub fn from_id(id: &str) -> Option<&'static dyn TerminalEmulator> {
+    let id = id.trim().to_lowercase();
+    EMULATORS.iter().copied().find(|e| e.id() == id)
+}
+
+/// The emulator chosen by the user: `WORKOPILOT_TERMINAL` over the setting.
+pub fn preference(setting: Option<String>) -> Option<String> {
+    std::env::var(ENV_VAR)
+        .ok()
+        .into_iter()
+        .chain(setting)
+        .map(|v| v.trim().to_string())
+        .find(|v| !v.is_empty())
+}
+
+/// Resolves `preference` (an emulator id, `auto` or nothing) to an installed emulator.
+pub fn select(
+    preference: Option<&str>,
+    is_installed: impl Fn(&str) -> bool,
+) -> Result<&'static dyn TerminalEmulator, String> {
+    match preference.filter(|p| *p != AUTO) {
+        Some(id) => {
+            let emulator = from_id(id).ok_or_else(|| {
+                format!(
+                    "Unknown terminal emulator: {}. Valid values: {}, {}",
+                    id,
+                    AUTO,
+                    ids().join(", ")
+                )
+            })?;
+            if !is_installed(emulator.binary()) {
+                return Err(format!(
+                    "Terminal emulator {} is not installed ({} not found in PATH)",
+                    emulator.name(),
+                    emulator.binary()
+                ));
+            }
+            Ok(emulator)
+        }
+        None => EMULATORS
+            .iter()
+            .copied()
+            .find(|e| is_installed(e.binary()))
+            .ok_or_else(|| {
+                format!(
+                    "No supported terminal emulator found (tried {})",
+                    ids().join(", ")
+                )
+            }),
+    }
+}
+
+pub fn is_installed(binary: &str) -> bool {
+    which::which(binary).is_ok()
+}
+
+fn ids() -> Vec<&'static str> {
+    EMULATORS.iter().map(|e| e.id()).collect()
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct BackendInfo {
+    pub id: String,
+    pub name: String,
+    pub installed: bool,
+}
+
+pub fn detect() -> Vec<BackendInfo> {
+    EMULATORS
+        .iter()
+        .map(|e| BackendInfo {
+            id: e.id().to_string(),
+            name: e.name().to_string(),
+            installed: is_installed(e.binary()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_launch_args_run_program() {
+        let args = |e: &dyn TerminalEmulator| e.launch_args("bash", &["-c", "tmux attach"]);
+
+        assert_eq!(args(&Alacritty), ["-e", "bash", "-c", "tmux attach"]);
+        assert_eq!(args(&Kitty), ["bash", "-c", "tmux attach"]);
+        assert_eq!(args(&WezTerm), ["start", "--", "bash", "-c", "tmux attach"]);
+        assert_eq!(args(&GnomeTerminal), ["--", "bash", "-c", "tmux attach"]);
+    }
+
+    #[test]
+    fn test_select_prefers_setting_then_detection_order() {
+        let only = |installed: &'static [&'static str]| move |b: &str| installed.contains(&b);
+
+        assert_eq!(
+            select(None, only(&["foot", "kitty"])).unwrap().id(),
+            "kitty"
+        );
+        assert_eq!(select(Some(AUTO), only(&["foot"])).unwrap().id(), "foot");
+        assert_eq!(
+            select(Some("WezTerm"), only(&["wezterm", "alacritty"]))
+                .unwrap()
+                .window_class(),
+            "org.wezfurlong.wezterm"
+        );
+
+        assert!(matches!(
+            select(Some("kitty"), only(&["alacritty"])),
+            Err(e) if e.contains("not installed")
+        ));
+        assert!(matches!(
+            select(Some("xterm"), only(&["xterm"])),
+            Err(e) if e.contains("Unknown terminal emulator")
+        ));
+        assert!(select(None, only(&[])).is_err());
+    }
+}
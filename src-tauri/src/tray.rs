@@ -2,27 +2,34 @@ use tauri::{
     image::Image,
     menu::{Menu, MenuItem},
     tray::{TrayIcon, TrayIconBuilder},
-    App, Manager, RunEvent, WindowEvent,
+    App, AppHandle, Manager, RunEvent, WindowEvent,
 };
 
 use crate::settings;
+use crate::sidecar::{SidecarHealth, SidecarStatus};
 use crate::window;
 use crate::AppState;
 
+const TRAY_ID: &str = "workopilot-tray";
+
+fn base_tooltip(app: &AppHandle) -> String {
+    let shortcut_config = settings::get_saved_shortcut(app);
+    format!("WorkOpilot - {} para abrir", shortcut_config.display)
+}
+
 pub fn setup_tray(app: &App) -> Result<TrayIcon, Box<dyn std::error::Error>> {
     let icon = app.default_window_icon().cloned().unwrap_or_else(|| {
         Image::from_bytes(include_bytes!("../icons/32x32.png"))
             .expect("Failed to load embedded icon")
     });
 
-    let shortcut_config = settings::get_saved_shortcut(app.handle());
-    let tooltip = format!("WorkOpilot - {} para abrir", shortcut_config.display);
+    let tooltip = base_tooltip(app.handle());
 
     let show_item = MenuItem::with_id(app, "show", "Abrir WorkOpilot", true, None::<&str>)?;
     let quit_item = MenuItem::with_id(app, "quit", "Sair", true, None::<&str>)?;
     let menu = Menu::with_items(app, &[&show_item, &quit_item])?;
 
-    let tray = TrayIconBuilder::with_id("workopilot-tray")
+    let tray = TrayIconBuilder::with_id(TRAY_ID)
         .icon(icon)
         .tooltip(&tooltip)
         .icon_as_template(false)
@@ -45,19 +52,56 @@ pub fn setup_tray(app: &App) -> Result<TrayIcon, Box<dyn std::error::Error>> {
     Ok(tray)
 }
 
+pub fn set_sidecar_status(app: &AppHandle, status: &SidecarStatus) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+
+    let suffix = match status.status {
+        SidecarHealth::Starting => Some("sidecar iniciando"),
+        SidecarHealth::Degraded => Some("sidecar instável"),
+        SidecarHealth::Crashed => Some("sidecar parado"),
+        SidecarHealth::Healthy | SidecarHealth::Stopped => None,
+    };
+
+    let tooltip = match suffix {
+        Some(suffix) => format!("{} ({})", base_tooltip(app), suffix),
+        None => base_tooltip(app),
+    };
+
+    if let Err(e) = tray.set_tooltip(Some(&tooltip)) {
+        eprintln!("[WORKOPILOT] Failed to update tray tooltip: {}", e);
+    }
+}
+
 pub fn handle_run_event(app_handle: &tauri::AppHandle, event: RunEvent) {
     match event {
-        RunEvent::WindowEvent { label, event, .. } => {
-            if label == "main" {
-                if let WindowEvent::CloseRequested { api, .. } = event {
-                    api.prevent_close();
-                    window::hide(app_handle);
-                }
-            }
+        RunEvent::WindowEvent {
+            label,
+            event: WindowEvent::CloseRequested { api, .. },
+            ..
+        } if label == "main" => {
+            api.prevent_close();
+            window::hide(app_handle);
         }
         RunEvent::ExitRequested { .. } => {
             let state = app_handle.state::<AppState>();
 
+            if let Ok(mut supervisor) = state.sidecar.supervisor.lock() {
+                if let Some(ref mut supervisor) = *supervisor {
+                    supervisor.shutdown();
+                }
+                *supervisor = None;
+            }
+            state.sidecar.sidecar.stop();
+
+            if let Ok(mut db_watcher) = state.db_watcher.lock() {
+                if let Some(ref mut watcher) = *db_watcher {
+                    watcher.shutdown();
+                }
+                *db_watcher = None;
+            }
+
             if let Ok(mut ipc_socket) = state.ipc_socket.lock() {
                 if let Some(ref mut server) = *ipc_socket {
                     server.shutdown();
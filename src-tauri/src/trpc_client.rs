@@ -0,0 +1,810 @@
+use crate::commands::{Project, TaskFull};
+use crate::sidecar_error::SidecarError;
+use crate::sidecar_transport::{CallKind, CallTimeouts, SidecarTransport};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(15);
+
+pub type TrpcResult<T> = Result<T, SidecarError>;
+
+#[derive(Debug, Deserialize)]
+struct TrpcEnvelope {
+    result: Option<TrpcData>,
+    error: Option<TrpcErrorBody>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrpcData {
+    #[serde(default)]
+    data: Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrpcErrorBody {
+    message: String,
+    #[serde(default)]
+    data: Option<TrpcErrorData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrpcErrorData {
+    code: Option<String>,
+}
+
+struct HttpResponse {
+    status: u16,
+    body: Vec<u8>,
+}
+
+/// Typed client for the sidecar's tRPC routers, independent of how calls
+/// reach the sidecar (see [`SidecarTransport`]).
+#[derive(Clone)]
+pub struct TrpcClient {
+    transport: Arc<dyn SidecarTransport>,
+}
+
+impl TrpcClient {
+    pub fn new(transport: Arc<dyn SidecarTransport>) -> Self {
+        Self { transport }
+    }
+
+    pub fn http(base_url: &str) -> Result<Self, String> {
+        Ok(Self::new(Arc::new(HttpTransport::new(base_url)?)))
+    }
+
+    pub fn query<T: DeserializeOwned>(&self, path: &str, input: Option<Value>) -> TrpcResult<T> {
+        let data = self.transport.call(CallKind::Query, path, input)?;
+        deserialize_result(path, data)
+    }
+
+    pub fn mutation<T: DeserializeOwned>(&self, path: &str, input: Option<Value>) -> TrpcResult<T> {
+        let data = self.transport.call(CallKind::Mutation, path, input)?;
+        deserialize_result(path, data)
+    }
+
+    pub fn projects(&self) -> ProjectsApi<'_> {
+        ProjectsApi { client: self }
+    }
+
+    pub fn tasks(&self) -> TasksApi<'_> {
+        TasksApi { client: self }
+    }
+
+    pub fn subtasks(&self) -> SubtasksApi<'_> {
+        SubtasksApi { client: self }
+    }
+
+    pub fn executions(&self) -> ExecutionsApi<'_> {
+        ExecutionsApi { client: self }
+    }
+
+    pub fn settings(&self) -> SettingsApi<'_> {
+        SettingsApi { client: self }
+    }
+}
+
+fn deserialize_result<T: DeserializeOwned>(path: &str, data: Value) -> TrpcResult<T> {
+    serde_json::from_value(data).map_err(|e| {
+        SidecarError::Transport(format!("Failed to deserialize {} result: {}", path, e))
+    })
+}
+
+/// `procedures.get` answers `null` for a missing row; surface that as [`SidecarError::NotFound`].
+fn required<T>(value: Option<T>, what: &str, id: &str) -> TrpcResult<T> {
+    value.ok_or_else(|| SidecarError::NotFound(format!("{} not found: {}", what, id)))
+}
+
+/// Minimal tRPC-over-HTTP transport for the sidecar's `/trpc` endpoint.
+///
+/// Speaks plain HTTP/1.1 to the local URL captured from the sidecar handshake, without
+/// batching or a data transformer, matching how the sidecar server is set up.
+/// Each call must finish within its procedure's timeout, however slowly the
+/// response trickles in.
+#[derive(Debug, Clone)]
+pub struct HttpTransport {
+    host: String,
+    port: u16,
+    timeouts: CallTimeouts,
+}
+
+impl HttpTransport {
+    pub fn new(base_url: &str) -> Result<Self, String> {
+        let (host, port) = parse_base_url(base_url)?;
+        Ok(Self {
+            host,
+            port,
+            timeouts: CallTimeouts::new(DEFAULT_TIMEOUT),
+        })
+    }
+
+    pub fn with_timeouts(mut self, timeouts: CallTimeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+
+    fn connect(&self, timeout: Duration) -> TrpcResult<TcpStream> {
+        let addrs = (self.host.as_str(), self.port)
+            .to_socket_addrs()
+            .map_err(|e| SidecarError::Transport(format!("Failed to resolve {}: {}", self.host, e)))?;
+
+        let mut last_error = None;
+        for addr in addrs {
+            match TcpStream::connect_timeout(&addr, timeout) {
+                Ok(stream) => return Ok(stream),
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        Err(SidecarError::Transport(match last_error {
+            Some(e) => format!("Failed to connect to {}:{}: {}", self.host, self.port, e),
+            None => format!("No address found for {}:{}", self.host, self.port),
+        }))
+    }
+
+    fn send(
+        &self,
+        path: &str,
+        method: &str,
+        target: &str,
+        body: Option<&str>,
+    ) -> TrpcResult<HttpResponse> {
+        let timeout = self.timeouts.for_method(path);
+        let deadline = Instant::now() + timeout;
+        let mut stream = self.connect(timeout)?;
+        stream
+            .set_write_timeout(Some(timeout))
+            .map_err(|e| SidecarError::Transport(format!("Failed to set socket timeout: {}", e)))?;
+
+        let mut request = format!(
+            "{} {} HTTP/1.1\r\nHost: {}:{}\r\nAccept: application/json\r\nConnection: close\r\n",
+            method, target, self.host, self.port
+        );
+        if let Some(body) = body {
+            request.push_str(&format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            ));
+        } else {
+            request.push_str("Content-Length: 0\r\n\r\n");
+        }
+
+        stream
+            .write_all(request.as_bytes())
+            .and_then(|_| stream.flush())
+            .map_err(|e| io_error(path, timeout, "Write", e))?;
+
+        read_http_response(BufReader::new(DeadlineReader { stream, deadline }))
+            .map_err(|e| io_error(path, timeout, "Read", e))
+    }
+}
+
+fn io_error(path: &str, timeout: Duration, operation: &str, error: io::Error) -> SidecarError {
+    match error.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => SidecarError::Timeout {
+            method: path.to_string(),
+            timeout,
+        },
+        _ => SidecarError::Transport(format!("{} error: {}", operation, error)),
+    }
+}
+
+/// Gives each read only the time left until `deadline`.
+struct DeadlineReader {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf)
+    }
+}
+
+impl SidecarTransport for HttpTransport {
+    fn call(&self, kind: CallKind, path: &str, input: Option<Value>) -> TrpcResult<Value> {
+        let response = match kind {
+            CallKind::Query => {
+                let target = match input {
+                    Some(input) => format!(
+                        "/trpc/{}?input={}",
+                        path,
+                        percent_encode(&input.to_string())
+                    ),
+                    None => format!("/trpc/{}", path),
+                };
+                self.send(path, "GET", &target, None)?
+            }
+            CallKind::Mutation => {
+                let body = input.map(|input| input.to_string());
+                self.send(path, "POST", &format!("/trpc/{}", path), body.as_deref())?
+            }
+        };
+
+        decode_response(path, response)
+    }
+}
+
+fn parse_base_url(base_url: &str) -> Result<(String, u16), String> {
+    let rest = base_url
+        .trim()
+        .strip_prefix("http://")
+        .ok_or_else(|| format!("Unsupported tRPC URL (expected http://): {}", base_url))?;
+    let authority = rest.split('/').next().unwrap_or_default();
+
+    let (host, port) = authority
+        .rsplit_once(':')
+        .ok_or_else(|| format!("tRPC URL has no port: {}", base_url))?;
+    let port = port
+        .parse::<u16>()
+        .map_err(|e| format!("Invalid port in tRPC URL {}: {}", base_url, e))?;
+
+    Ok((host.to_string(), port))
+}
+
+fn percent_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn malformed(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_http_response<R: BufRead>(mut reader: R) -> io::Result<HttpResponse> {
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+        .ok_or_else(|| malformed(format!("Malformed status line: {}", status_line.trim())))?;
+
+    let mut content_length: Option<usize> = None;
+    let mut chunked = false;
+
+    loop {
+        let mut header = String::new();
+        reader.read_line(&mut header)?;
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => content_length = value.parse().ok(),
+                "transfer-encoding" => chunked = value.eq_ignore_ascii_case("chunked"),
+                _ => {}
+            }
+        }
+    }
+
+    let body = if chunked {
+        read_chunked_body(&mut reader)?
+    } else if let Some(length) = content_length {
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body)?;
+        body
+    } else {
+        let mut body = Vec::new();
+        reader.read_to_end(&mut body)?;
+        body
+    };
+
+    Ok(HttpResponse { status, body })
+}
+
+fn read_chunked_body<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+
+    loop {
+        let mut size_line = String::new();
+        reader.read_line(&mut size_line)?;
+        let size_hex = size_line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size_hex, 16)
+            .map_err(|_| malformed(format!("Malformed chunk size: {}", size_line.trim())))?;
+
+        if size == 0 {
+            break;
+        }
+
+        let mut chunk = vec![0; size];
+        reader.read_exact(&mut chunk)?;
+        body.extend_from_slice(&chunk);
+
+        let mut crlf = String::new();
+        reader.read_line(&mut crlf)?;
+    }
+
+    Ok(body)
+}
+
+fn decode_response(path: &str, response: HttpResponse) -> TrpcResult<Value> {
+    let envelope: TrpcEnvelope = serde_json::from_slice(&response.body).map_err(|e| {
+        SidecarError::Transport(format!(
+            "Invalid tRPC response for {} (HTTP {}): {}",
+            path, response.status, e
+        ))
+    })?;
+
+    if let Some(error) = envelope.error {
+        let code = error
+            .data
+            .and_then(|data| data.code)
+            .unwrap_or_else(|| "INTERNAL_SERVER_ERROR".to_string());
+        return Err(SidecarError::from_trpc_code(&code, error.message));
+    }
+
+    Ok(envelope.result.map(|r| r.data).unwrap_or(Value::Null))
+}
+
+// ============================================================================
+// Router APIs
+// ============================================================================
+
+pub struct ProjectsApi<'a> {
+    client: &'a TrpcClient,
+}
+
+impl ProjectsApi<'_> {
+    pub fn list(&self) -> TrpcResult<Vec<Project>> {
+        self.client.query("projects.list", None)
+    }
+
+    pub fn get(&self, id: &str) -> TrpcResult<Project> {
+        let project = self.client.query("projects.get", Some(json!({ "id": id })))?;
+        required(project, "Project", id)
+    }
+
+    pub fn get_stats(&self, project_id: &str) -> TrpcResult<Value> {
+        self.client
+            .query("projects.getStats", Some(json!({ "projectId": project_id })))
+    }
+
+    pub fn get_all_stats(&self) -> TrpcResult<Value> {
+        self.client.query("projects.getAllStats", None)
+    }
+
+    pub fn create(&self, input: Value) -> TrpcResult<Project> {
+        self.client.mutation("projects.create", Some(input))
+    }
+
+    /// `data` holds the optional fields accepted by `projects.update`.
+    pub fn update(&self, id: &str, data: Value) -> TrpcResult<Project> {
+        self.client
+            .mutation("projects.update", Some(with_field(data, "id", id)))
+    }
+
+    pub fn update_order(&self, ordered_ids: &[String]) -> TrpcResult<Value> {
+        self.client
+            .mutation("projects.updateOrder", Some(json!({ "orderedIds": ordered_ids })))
+    }
+
+    pub fn delete(&self, id: &str) -> TrpcResult<Value> {
+        self.client.mutation("projects.delete", Some(json!({ "id": id })))
+    }
+}
+
+pub struct TasksApi<'a> {
+    client: &'a TrpcClient,
+}
+
+impl TasksApi<'_> {
+    pub fn get(&self, id: &str) -> TrpcResult<Value> {
+        self.client.query("tasks.get", Some(json!({ "id": id })))
+    }
+
+    pub fn get_full(&self, id: &str) -> TrpcResult<TaskFull> {
+        let task = self.client.query("tasks.getFull", Some(json!({ "id": id })))?;
+        required(task, "Task", id)
+    }
+
+    pub fn list(&self, project_id: Option<&str>, status: Option<&str>) -> TrpcResult<Value> {
+        self.client.query(
+            "tasks.list",
+            Some(without_nulls(json!({ "projectId": project_id, "status": status }))),
+        )
+    }
+
+    pub fn list_full(
+        &self,
+        project_id: Option<&str>,
+        status: Option<&str>,
+    ) -> TrpcResult<Vec<TaskFull>> {
+        self.client.query(
+            "tasks.listFull",
+            Some(without_nulls(json!({ "projectId": project_id, "status": status }))),
+        )
+    }
+
+    /// `filters` holds the optional fields accepted by `tasks.listFullPaginated`.
+    pub fn list_full_paginated(&self, filters: Value) -> TrpcResult<Value> {
+        self.client.query("tasks.listFullPaginated", Some(filters))
+    }
+
+    pub fn list_urgent(&self) -> TrpcResult<Value> {
+        self.client.query("tasks.listUrgent", None)
+    }
+
+    pub fn list_active(&self) -> TrpcResult<Value> {
+        self.client.query("tasks.listActive", None)
+    }
+
+    pub fn list_for_date(&self, date: &str) -> TrpcResult<Value> {
+        self.client
+            .query("tasks.listForDate", Some(json!({ "date": date })))
+    }
+
+    pub fn list_for_month(&self, year: i32, month: u32) -> TrpcResult<Value> {
+        self.client.query(
+            "tasks.listForMonth",
+            Some(json!({ "year": year, "month": month })),
+        )
+    }
+
+    pub fn list_unscheduled(&self, project_id: Option<&str>) -> TrpcResult<Value> {
+        self.client.query(
+            "tasks.listUnscheduled",
+            Some(without_nulls(json!({ "projectId": project_id }))),
+        )
+    }
+
+    pub fn create(&self, input: Value) -> TrpcResult<TaskFull> {
+        self.client.mutation("tasks.create", Some(input))
+    }
+
+    /// `data` holds the optional fields accepted by `tasks.update`.
+    pub fn update(&self, id: &str, data: Value) -> TrpcResult<TaskFull> {
+        self.client
+            .mutation("tasks.update", Some(with_field(data, "id", id)))
+    }
+
+    pub fn update_status(&self, id: &str, status: &str, modified_by: &str) -> TrpcResult<Value> {
+        self.client.mutation(
+            "tasks.updateStatus",
+            Some(json!({ "id": id, "status": status, "modifiedBy": modified_by })),
+        )
+    }
+
+    pub fn schedule(&self, id: &str, date: &str) -> TrpcResult<TaskFull> {
+        self.client
+            .mutation("tasks.schedule", Some(json!({ "id": id, "date": date })))
+    }
+
+    pub fn unschedule(&self, id: &str) -> TrpcResult<TaskFull> {
+        self.client
+            .mutation("tasks.unschedule", Some(json!({ "id": id })))
+    }
+
+    pub fn save_full(&self, task: &TaskFull) -> TrpcResult<TaskFull> {
+        let input = serde_json::to_value(task)
+            .map_err(|e| SidecarError::Validation(format!("Serialize error: {}", e)))?;
+        self.client.mutation("tasks.saveFull", Some(input))
+    }
+
+    pub fn delete(&self, id: &str) -> TrpcResult<Value> {
+        self.client.mutation("tasks.delete", Some(json!({ "id": id })))
+    }
+}
+
+pub struct SubtasksApi<'a> {
+    client: &'a TrpcClient,
+}
+
+impl SubtasksApi<'_> {
+    pub fn get(&self, id: &str) -> TrpcResult<Value> {
+        self.client.query("subtasks.get", Some(json!({ "id": id })))
+    }
+
+    pub fn list_by_task_id(&self, task_id: &str) -> TrpcResult<Value> {
+        self.client
+            .query("subtasks.listByTaskId", Some(json!({ "taskId": task_id })))
+    }
+
+    pub fn create(&self, input: Value) -> TrpcResult<Value> {
+        self.client.mutation("subtasks.create", Some(input))
+    }
+
+    /// `data` holds the optional fields accepted by `subtasks.update`.
+    pub fn update(&self, id: &str, data: Value) -> TrpcResult<Value> {
+        self.client
+            .mutation("subtasks.update", Some(with_field(data, "id", id)))
+    }
+
+    pub fn update_status(&self, id: &str, status: &str) -> TrpcResult<Value> {
+        self.client.mutation(
+            "subtasks.updateStatus",
+            Some(json!({ "id": id, "status": status })),
+        )
+    }
+
+    pub fn delete(&self, id: &str) -> TrpcResult<Value> {
+        self.client
+            .mutation("subtasks.delete", Some(json!({ "id": id })))
+    }
+
+    pub fn reorder(&self, task_id: &str, ordered_ids: &[String]) -> TrpcResult<Value> {
+        self.client.mutation(
+            "subtasks.reorder",
+            Some(json!({ "taskId": task_id, "orderedIds": ordered_ids })),
+        )
+    }
+
+    pub fn delete_by_task_id(&self, task_id: &str) -> TrpcResult<Value> {
+        self.client
+            .mutation("subtasks.deleteByTaskId", Some(json!({ "taskId": task_id })))
+    }
+}
+
+pub struct ExecutionsApi<'a> {
+    client: &'a TrpcClient,
+}
+
+impl ExecutionsApi<'_> {
+    pub fn start(&self, task_id: &str, current_subtask_id: Option<&str>) -> TrpcResult<Value> {
+        self.client.mutation(
+            "executions.start",
+            Some(json!({ "task_id": task_id, "current_subtask_id": current_subtask_id })),
+        )
+    }
+
+    pub fn end(&self, task_id: &str, error_message: Option<&str>) -> TrpcResult<Value> {
+        self.client.mutation(
+            "executions.end",
+            Some(json!({ "taskId": task_id, "errorMessage": error_message })),
+        )
+    }
+
+    /// `data` holds the optional fields accepted by `executions.update`.
+    pub fn update(&self, id: &str, data: Value) -> TrpcResult<Value> {
+        self.client
+            .mutation("executions.update", Some(with_field(data, "id", id)))
+    }
+
+    pub fn get(&self, id: &str) -> TrpcResult<Value> {
+        self.client.query("executions.get", Some(json!({ "id": id })))
+    }
+
+    pub fn get_active_for_task(&self, task_id: &str) -> TrpcResult<Value> {
+        self.client
+            .query("executions.getActiveForTask", Some(json!({ "taskId": task_id })))
+    }
+
+    pub fn list_all_active(&self) -> TrpcResult<Value> {
+        self.client.query("executions.listAllActive", None)
+    }
+
+    pub fn cleanup_stale(&self, max_age_minutes: Option<u32>) -> TrpcResult<Value> {
+        self.client.mutation(
+            "executions.cleanupStale",
+            Some(without_nulls(json!({ "maxAgeMinutes": max_age_minutes }))),
+        )
+    }
+
+    pub fn get_terminal_for_task(&self, task_id: &str) -> TrpcResult<Value> {
+        self.client
+            .query("executions.getTerminalForTask", Some(json!({ "taskId": task_id })))
+    }
+
+    pub fn link_terminal(
+        &self,
+        task_id: &str,
+        tmux_session: &str,
+        current_subtask_id: Option<&str>,
+    ) -> TrpcResult<Value> {
+        self.client.mutation(
+            "executions.linkTerminal",
+            Some(json!({
+                "task_id": task_id,
+                "tmux_session": tmux_session,
+                "current_subtask_id": current_subtask_id
+            })),
+        )
+    }
+
+    pub fn unlink_terminal(&self, task_id: &str) -> TrpcResult<Value> {
+        self.client
+            .mutation("executions.unlinkTerminal", Some(json!({ "taskId": task_id })))
+    }
+
+    pub fn update_terminal_subtask(
+        &self,
+        task_id: &str,
+        subtask_id: Option<&str>,
+    ) -> TrpcResult<Value> {
+        self.client.mutation(
+            "executions.updateTerminalSubtask",
+            Some(json!({ "taskId": task_id, "subtaskId": subtask_id })),
+        )
+    }
+}
+
+pub struct SettingsApi<'a> {
+    client: &'a TrpcClient,
+}
+
+impl SettingsApi<'_> {
+    pub fn get(&self, key: &str) -> TrpcResult<Option<String>> {
+        self.client.query("settings.get", Some(json!({ "key": key })))
+    }
+
+    pub fn set(&self, key: &str, value: &str) -> TrpcResult<Value> {
+        self.client
+            .mutation("settings.set", Some(json!({ "key": key, "value": value })))
+    }
+
+    pub fn get_all(&self) -> TrpcResult<Value> {
+        self.client.query("settings.getAll", None)
+    }
+
+    pub fn delete(&self, key: &str) -> TrpcResult<Value> {
+        self.client
+            .mutation("settings.delete", Some(json!({ "key": key })))
+    }
+}
+
+/// Drops `null` fields, since zod `.optional()` inputs accept a missing key but not `null`.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(
+            object
+                .into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn with_field(data: Value, key: &str, value: &str) -> Value {
+    let mut object = match data {
+        Value::Object(object) => object,
+        _ => serde_json::Map::new(),
+    };
+    object.insert(key.to_string(), Value::String(value.to_string()));
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidecar_transport::InMemoryTransport;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_base_url() {
+        assert_eq!(
+            parse_base_url("http://localhost:4321").unwrap(),
+            ("localhost".to_string(), 4321)
+        );
+        assert_eq!(
+            parse_base_url("http://127.0.0.1:80/trpc\n").unwrap(),
+            ("127.0.0.1".to_string(), 80)
+        );
+        assert!(parse_base_url("https://localhost:4321").is_err());
+        assert!(parse_base_url("http://localhost").is_err());
+    }
+
+    #[test]
+    fn test_without_nulls_keeps_set_fields() {
+        assert_eq!(
+            without_nulls(json!({ "projectId": null, "status": "done" })),
+            json!({ "status": "done" })
+        );
+    }
+
+    #[test]
+    fn test_percent_encode_json_input() {
+        assert_eq!(
+            percent_encode(r#"{"id":"a b"}"#),
+            "%7B%22id%22%3A%22a%20b%22%7D"
+        );
+    }
+
+    #[test]
+    fn test_read_chunked_response() {
+        let raw = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+                   7\r\n{\"resul\r\n\
+                   11\r\nt\":{\"data\":\"ok\"}}\r\n\
+                   0\r\n\r\n";
+        let response = read_http_response(Cursor::new(raw)).unwrap();
+
+        assert_eq!(response.status, 200);
+        let data = decode_response("system.ping", response).unwrap();
+        assert_eq!(data, json!("ok"));
+    }
+
+    #[test]
+    fn test_decode_error_response() {
+        let body = r#"{"error":{"message":"Task not found","code":-32004,"data":{"code":"NOT_FOUND","httpStatus":404,"path":"tasks.get"}}}"#;
+        let raw = format!(
+            "HTTP/1.1 404 Not Found\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let response = read_http_response(Cursor::new(raw)).unwrap();
+
+        let error = decode_response("tasks.get", response).unwrap_err();
+        assert_eq!(error, SidecarError::NotFound("Task not found".to_string()));
+    }
+
+    #[test]
+    fn test_null_get_result_is_not_found() {
+        let transport = InMemoryTransport::new().respond("projects.get", Value::Null);
+        let client = TrpcClient::new(Arc::new(transport));
+
+        assert_eq!(
+            client.projects().get("p1").unwrap_err(),
+            SidecarError::NotFound("Project not found: p1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_read_timeout_maps_to_timeout_error() {
+        let error = io_error(
+            "tasks.list",
+            DEFAULT_TIMEOUT,
+            "Read",
+            io::Error::new(io::ErrorKind::WouldBlock, "timed out"),
+        );
+
+        assert_eq!(error.code(), "TIMEOUT");
+    }
+
+    #[test]
+    fn test_method_timeout_bounds_a_trickling_response() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\n");
+            // A header every 50ms keeps any single read well under the timeout.
+            while stream.write_all(b"X-Wait: 1\r\n").is_ok() {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        });
+
+        let mut timeouts = CallTimeouts::new(Duration::from_secs(60));
+        let timeout = Duration::from_millis(300);
+        timeouts.methods.insert("tasks.listFull".to_string(), timeout);
+        let transport = HttpTransport::new(&format!("http://127.0.0.1:{}", port))
+            .unwrap()
+            .with_timeouts(timeouts);
+
+        let started = Instant::now();
+        let error = transport
+            .call(CallKind::Query, "tasks.listFull", None)
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            SidecarError::Timeout {
+                method: "tasks.listFull".to_string(),
+                timeout,
+            }
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
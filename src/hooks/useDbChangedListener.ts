@@ -1,23 +1,93 @@
 import { useEffect, useRef } from "react";
-import { safeListen } from "../services/tauri";
+import { isTauri, safeInvoke, safeListen } from "../services/tauri";
 import {
   useDbRefetchStore,
-  type DbChangedPayload,
+  type DbChangedBatchPayload,
 } from "../stores/dbRefetch";
 
+const LAST_SEQ_KEY = "workopilot:lastEventSeq";
+
+interface EventJournalPage {
+  events: { seq: number }[];
+  latest_seq: number;
+  has_more: boolean;
+  truncated: boolean;
+  batch: DbChangedBatchPayload;
+}
+
+function readLastSeq(): number | null {
+  const stored = localStorage.getItem(LAST_SEQ_KEY);
+  return stored === null ? null : Number(stored);
+}
+
+function storeLastSeq(seq: number) {
+  if (seq > (readLastSeq() ?? 0)) {
+    localStorage.setItem(LAST_SEQ_KEY, String(seq));
+  }
+}
+
 export function useDbChangedListener() {
-  const triggerRefetch = useDbRefetchStore((s) => s.triggerRefetch);
+  const triggerBatchRefetch = useDbRefetchStore((s) => s.triggerBatchRefetch);
+  const triggerFullRefetch = useDbRefetchStore((s) => s.triggerFullRefetch);
   const unlistenRef = useRef<(() => void) | null>(null);
 
   useEffect(() => {
-    safeListen<DbChangedPayload>("db-changed", (event) => {
-      triggerRefetch(event.payload);
+    // Replays changes missed while the webview was reloading or hidden.
+    async function catchUp() {
+      if (!isTauri()) return;
+      const since = readLastSeq();
+      try {
+        if (since === null) {
+          // First run: everything is fetched anyway, only record the position.
+          const page = await safeInvoke<EventJournalPage>("get_events_since", {
+            since: 0,
+            limit: 1,
+          });
+          localStorage.setItem(LAST_SEQ_KEY, String(page.latest_seq));
+          return;
+        }
+
+        let cursor = since;
+        for (;;) {
+          const page = await safeInvoke<EventJournalPage>("get_events_since", {
+            since: cursor,
+          });
+          if (page.truncated) {
+            // The journal no longer reaches back to `since`.
+            triggerFullRefetch();
+          } else if (page.batch.count > 0) {
+            triggerBatchRefetch(page.batch);
+          }
+
+          const last = page.events[page.events.length - 1];
+          if (page.truncated || !page.has_more || !last) {
+            storeLastSeq(page.latest_seq);
+            return;
+          }
+          cursor = last.seq;
+        }
+      } catch (error) {
+        console.error("[WorkoPilot] Failed to replay missed events:", error);
+      }
+    }
+
+    function onVisibilityChange() {
+      if (document.visibilityState === "visible") catchUp();
+    }
+
+    safeListen<DbChangedBatchPayload>("db-changed-batch", (event) => {
+      if (event.payload.last_seq !== null) storeLastSeq(event.payload.last_seq);
+      triggerBatchRefetch(event.payload);
     }).then((fn) => {
       unlistenRef.current = fn;
     });
 
+    catchUp();
+    document.addEventListener("visibilitychange", onVisibilityChange);
+
     return () => {
       unlistenRef.current?.();
+      document.removeEventListener("visibilitychange", onVisibilityChange);
     };
-  }, [triggerRefetch]);
+  }, [triggerBatchRefetch, triggerFullRefetch]);
 }
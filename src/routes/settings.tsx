@@ -6,7 +6,9 @@ import { HotkeyInput, type HotkeyValue } from "../components/HotkeyInput";
 import { Switch } from "@/components/ui/switch";
 import { trpc } from "../services/trpc";
 import { PageHeader } from "@/components/PageHeader";
-import { Settings } from "lucide-react";
+import { CustomSelect } from "@/components/ui";
+import { cn } from "@/lib/utils";
+import { ChevronDown, Settings } from "lucide-react";
 
 interface ShortcutConfig {
   modifier: string;
@@ -14,6 +16,19 @@ interface ShortcutConfig {
   display: string;
 }
 
+interface TerminalBackendInfo {
+  id: string;
+  name: string;
+  installed: boolean;
+}
+
+interface TerminalBackendSettings {
+  selected: string;
+  env_override: string | null;
+  active: string | null;
+  available: TerminalBackendInfo[];
+}
+
 function SettingsPage() {
   const [currentShortcut, setCurrentShortcut] = useState<ShortcutConfig | null>(null);
   const [error, setError] = useState("");
@@ -139,6 +154,8 @@ function SettingsPage() {
             </div>
           </div>
 
+          <TerminalEmulatorCard />
+
           <TrpcStatusCard />
 
           <div className="bg-card border border-border p-4 mt-4">
@@ -170,6 +187,113 @@ function SettingsPage() {
   );
 }
 
+function TerminalEmulatorCard() {
+  return (
+    <div className="bg-card border border-border p-4 mt-4">
+      <h2 className="text-sm text-muted-foreground uppercase tracking-wide mb-4">Terminal</h2>
+      <p className="text-xs text-muted-foreground mb-4">
+        Emulador e multiplexador usados para abrir as sessoes das tarefas.
+      </p>
+
+      <div className="space-y-4">
+        <TerminalBackendSelect
+          label="Emulador"
+          envVar="WORKOPILOT_TERMINAL"
+          getCommand="get_terminal_emulators"
+          setCommand="set_terminal_emulator"
+          argName="emulator"
+        />
+        <TerminalBackendSelect
+          label="Multiplexador"
+          envVar="WORKOPILOT_MULTIPLEXER"
+          getCommand="get_terminal_multiplexers"
+          setCommand="set_terminal_multiplexer"
+          argName="multiplexer"
+        />
+      </div>
+    </div>
+  );
+}
+
+interface TerminalBackendSelectProps {
+  label: string;
+  envVar: string;
+  getCommand: string;
+  setCommand: string;
+  argName: string;
+}
+
+function TerminalBackendSelect({ label, envVar, getCommand, setCommand, argName }: TerminalBackendSelectProps) {
+  const [settings, setSettings] = useState<TerminalBackendSettings | null>(null);
+  const [error, setError] = useState("");
+
+  useEffect(() => {
+    safeInvoke<TerminalBackendSettings>(getCommand)
+      .then(setSettings)
+      .catch((e) => console.error(`Failed to load ${argName} settings:`, e));
+  }, [getCommand, argName]);
+
+  async function handleChange(value: string) {
+    setError("");
+    try {
+      setSettings(await safeInvoke<TerminalBackendSettings>(setCommand, { [argName]: value }));
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
+  const items: TerminalBackendInfo[] = [
+    { id: "auto", name: "Automatico (primeiro instalado)", installed: true },
+    ...(settings?.available ?? []),
+  ];
+  const activeName = settings?.available.find((e) => e.id === settings.active)?.name;
+
+  return (
+    <div className="space-y-2">
+      <span className="block text-xs text-muted-foreground">{label}</span>
+      <CustomSelect
+        items={items}
+        value={settings?.selected ?? "auto"}
+        onValueChange={(id) => handleChange(id)}
+        disabled={!settings || settings.env_override !== null}
+        triggerClassName="flex items-center gap-2 px-2 py-1.5 h-8 w-full border border-border bg-card hover:bg-popover hover:border-muted-foreground transition-colors"
+        contentClassName="min-w-[220px]"
+        renderTrigger={() => (
+          <>
+            <span className="flex-1 text-sm text-foreground truncate text-left">
+              {items.find((item) => item.id === (settings?.selected ?? "auto"))?.name}
+            </span>
+            <ChevronDown className="size-3 text-muted-foreground shrink-0" />
+          </>
+        )}
+        renderItem={(item, isSelected) => (
+          <div
+            className={cn(
+              "flex items-center gap-2 px-3 py-2 cursor-pointer text-sm transition-colors",
+              isSelected ? "bg-popover" : "hover:bg-popover",
+            )}
+          >
+            <span className="flex-1 text-foreground">{item.name}</span>
+            {!item.installed && (
+              <span className="text-xs text-muted-foreground">nao instalado</span>
+            )}
+          </div>
+        )}
+      />
+
+      <div className="text-xs text-muted-foreground">
+        {settings?.env_override
+          ? `Definido por ${envVar}=${settings.env_override}`
+          : activeName
+            ? `Em uso: ${activeName}`
+            : "Nenhum suportado encontrado"}
+      </div>
+
+      {error && <div className="text-sm text-destructive">{error}</div>}
+    </div>
+  );
+}
+
 function TrpcStatusCard() {
   const pingQuery = trpc.system.ping.useQuery(undefined, {
     refetchInterval: 5000,
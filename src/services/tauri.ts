@@ -16,6 +16,30 @@ export async function safeInvoke<T>(command: string, args?: Record<string, unkno
 	return invoke<T>(command, args)
 }
 
+/** Error shape returned by commands that call the sidecar (see src-tauri/src/sidecar_error.rs). */
+export type SidecarErrorCode =
+	| "NOT_FOUND"
+	| "VALIDATION"
+	| "TIMEOUT"
+	| "CANCELLED"
+	| "TRANSPORT"
+	| "FAILED"
+	| (string & {})
+
+export interface SidecarError {
+	code: SidecarErrorCode
+	message: string
+}
+
+export function isSidecarError(error: unknown): error is SidecarError {
+	return (
+		typeof error === "object" &&
+		error !== null &&
+		typeof (error as SidecarError).code === "string" &&
+		typeof (error as SidecarError).message === "string"
+	)
+}
+
 export async function safeListen<T>(
 	event: string,
 	handler: (event: { payload: T }) => void
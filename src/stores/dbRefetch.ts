@@ -1,16 +1,59 @@
 import { create } from "zustand";
 
 export interface DbChangedPayload {
-  entity_type: "task" | "subtask" | "execution" | "terminal";
+  entity_type:
+    | "project"
+    | "task"
+    | "subtask"
+    | "execution"
+    | "terminal"
+    | "category"
+    | "urgency"
+    | "setting";
   entity_id: string;
   operation: "create" | "update" | "delete";
   project_id?: string;
 }
 
+export interface DbChangedBatchGroup {
+  project_id: string | null;
+  entity_type: DbChangedPayload["entity_type"];
+  created: string[];
+  updated: string[];
+  deleted: string[];
+}
+
+export interface DbChangedBatchPayload {
+  count: number;
+  last_seq: number | null;
+  groups: DbChangedBatchGroup[];
+}
+
 interface DbRefetchState {
   lastChange: DbChangedPayload | null;
   changeCounter: number;
   triggerRefetch: (payload: DbChangedPayload) => void;
+  triggerBatchRefetch: (batch: DbChangedBatchPayload) => void;
+  triggerFullRefetch: () => void;
+}
+
+function singleChange(batch: DbChangedBatchPayload): DbChangedPayload | null {
+  if (batch.count !== 1 || batch.groups.length !== 1) return null;
+
+  const group = batch.groups[0];
+  const [operation, ids] =
+    group.created.length > 0
+      ? (["create", group.created] as const)
+      : group.deleted.length > 0
+        ? (["delete", group.deleted] as const)
+        : (["update", group.updated] as const);
+
+  return {
+    entity_type: group.entity_type,
+    entity_id: ids[0],
+    operation,
+    project_id: group.project_id ?? undefined,
+  };
 }
 
 export const useDbRefetchStore = create<DbRefetchState>((set) => ({
@@ -21,4 +64,16 @@ export const useDbRefetchStore = create<DbRefetchState>((set) => ({
       lastChange: payload,
       changeCounter: state.changeCounter + 1,
     })),
+  // A batch with several changes leaves lastChange null, which listeners treat
+  // as "anything may have changed".
+  triggerBatchRefetch: (batch: DbChangedBatchPayload) =>
+    set((state) => ({
+      lastChange: singleChange(batch),
+      changeCounter: state.changeCounter + 1,
+    })),
+  triggerFullRefetch: () =>
+    set((state) => ({
+      lastChange: null,
+      changeCounter: state.changeCounter + 1,
+    })),
 }));